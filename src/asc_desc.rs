@@ -6,25 +6,49 @@ use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-//use crate::search::facet::BadGeoError;
-use crate::{CriterionError,  UserError};
+use crate::search::facet::BadGeoError;
+use crate::{is_reserved_keyword, CriterionError, UserError};
 
 /// This error type is never supposed to be shown to the end user.
 /// You must always cast it to a sort error or a criterion error.
 #[derive(Error, Debug)]
 pub enum AscDescError {
+    #[error("{}", .0)]
+    GeoError(BadGeoError),
     #[error("Неверный синтаксис для параметра asc/desc: ожидалось выражение, заканчивающееся на `:asc` или `:desc`, найдено `{name}`.")]
     InvalidSyntax { name: String },
     #[error("`{name}` является зарезервированным ключевым словом и поэтому не может быть использовано в качестве правила asc/desc.")]
     ReservedKeyword { name: String },
 }
 
-
+impl From<BadGeoError> for AscDescError {
+    fn from(geo_error: BadGeoError) -> Self {
+        AscDescError::GeoError(geo_error)
+    }
+}
 
 impl From<AscDescError> for CriterionError {
     fn from(error: AscDescError) -> Self {
         match error {
+            AscDescError::GeoError(_) => {
+                CriterionError::ReservedNameForSort { name: "_geoPoint".to_string() }
+            }
             AscDescError::InvalidSyntax { name } => CriterionError::InvalidName { name },
+            AscDescError::ReservedKeyword { name } if name.starts_with("_geoPoint") => {
+                CriterionError::ReservedNameForSort { name: "_geoPoint".to_string() }
+            }
+            AscDescError::ReservedKeyword { name } if name.starts_with("_random") => {
+                CriterionError::ReservedNameForSort { name: "_random".to_string() }
+            }
+            AscDescError::ReservedKeyword { name } if name.starts_with("_geoRadius") => {
+                CriterionError::ReservedNameForFilter { name: "_geoRadius".to_string() }
+            }
+            AscDescError::ReservedKeyword { name } if name.starts_with("_geoBoundingBox") => {
+                CriterionError::ReservedNameForFilter { name: "_geoBoundingBox".to_string() }
+            }
+            AscDescError::ReservedKeyword { name } if name.starts_with("_geoPolygon") => {
+                CriterionError::ReservedNameForFilter { name: "_geoPolygon".to_string() }
+            }
             AscDescError::ReservedKeyword { name } => CriterionError::ReservedName { name },
         }
     }
@@ -33,13 +57,49 @@ impl From<AscDescError> for CriterionError {
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum Member {
     Field(String),
+    Geo([f64; 2]),
+    /// Orders the documents pseudo-randomly, the same seed always gives the same order.
+    Random(u64),
 }
 
 impl FromStr for Member {
     type Err = AscDescError;
 
     fn from_str(text: &str) -> Result<Member, Self::Err> {
-        Ok(Member::Field(text.to_string()))
+        if let Some(seed) = text.strip_prefix("_random(").and_then(|text| text.strip_suffix(')')) {
+            let seed = seed
+                .trim()
+                .parse()
+                .map_err(|_| AscDescError::ReservedKeyword { name: text.to_string() })?;
+            return Ok(Member::Random(seed));
+        }
+
+        match text.strip_prefix("_geoPoint(").and_then(|text| text.strip_suffix(')')) {
+            Some(point) => {
+                let (lat, lng) = point
+                    .split_once(',')
+                    .ok_or_else(|| AscDescError::ReservedKeyword { name: text.to_string() })
+                    .and_then(|(lat, lng)| {
+                        lat.trim()
+                            .parse()
+                            .and_then(|lat| lng.trim().parse().map(|lng| (lat, lng)))
+                            .map_err(|_| AscDescError::ReservedKeyword { name: text.to_string() })
+                    })?;
+                if !(-90.0..=90.0).contains(&lat) {
+                    return Err(BadGeoError::Lat(lat))?;
+                } else if !(-180.0..=180.0).contains(&lng) {
+                    return Err(BadGeoError::Lng(lng))?;
+                }
+                Ok(Member::Geo([lat, lng]))
+            }
+            None => {
+                let name = text.split_once('(').map_or(text, |(name, _)| name);
+                if is_reserved_keyword(name) {
+                    return Err(AscDescError::ReservedKeyword { name: text.to_string() })?;
+                }
+                Ok(Member::Field(text.to_string()))
+            }
+        }
     }
 }
 
@@ -47,6 +107,8 @@ impl fmt::Display for Member {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Member::Field(name) => f.write_str(name),
+            Member::Geo([lat, lng]) => write!(f, "_geoPoint({}, {})", lat, lng),
+            Member::Random(seed) => write!(f, "_random({})", seed),
         }
     }
 }
@@ -55,6 +117,14 @@ impl Member {
     pub fn field(&self) -> Option<&str> {
         match self {
             Member::Field(field) => Some(field),
+            Member::Geo(_) | Member::Random(_) => None,
+        }
+    }
+
+    pub fn geo_point(&self) -> Option<&[f64; 2]> {
+        match self {
+            Member::Geo(point) => Some(point),
+            Member::Field(_) | Member::Random(_) => None,
         }
     }
 }
@@ -92,6 +162,8 @@ impl FromStr for AscDesc {
 
 #[derive(Error, Debug)]
 pub enum SortError {
+    #[error("{}", AscDescError::GeoError(.error.clone()))]
+    BadGeoPointUsage { error: BadGeoError },
     #[error("Неверный синтаксис для параметра sort: ожидалось выражение, заканчивающееся на `:asc` или `:desc`, найдено `{name}`.")]
     InvalidName { name: String },
     #[error("`{name}` является зарезервированным ключевым словом и поэтому не может быть использовано в качестве выражения сортировки.")]
@@ -101,6 +173,7 @@ pub enum SortError {
 impl From<AscDescError> for SortError {
     fn from(error: AscDescError) -> Self {
         match error {
+            AscDescError::GeoError(error) => SortError::BadGeoPointUsage { error },
             AscDescError::InvalidSyntax { name } => SortError::InvalidName { name },
             AscDescError::ReservedKeyword { name } => SortError::ReservedName { name },
         }
@@ -132,6 +205,15 @@ mod tests {
             ("a12:asc", Asc(Field(S("a12")))),
             ("42:asc", Asc(Field(S("42")))),
             ("truc(12, 13):desc", Desc(Field(S("truc(12, 13)")))),
+            ("_geoPoint(42, 59):asc", Asc(Geo([42., 59.]))),
+            ("_geoPoint(42.459, 59):desc", Desc(Geo([42.459, 59.]))),
+            ("_geoPoint(42, 59.895):desc", Desc(Geo([42., 59.895]))),
+            ("_geoPoint(90.000000000, 180):desc", Desc(Geo([90., 180.]))),
+            ("_geoPoint(-90, -180.0000000000):asc", Asc(Geo([-90., -180.]))),
+            ("_geoPoint(42.0002, 59.895):desc", Desc(Geo([42.0002, 59.895]))),
+            ("_geoPoint(42., 59.):desc", Desc(Geo([42., 59.]))),
+            ("_random(42):asc", Asc(Random(42))),
+            ("_random( 18446744073709551615 ):desc", Desc(Random(u64::MAX))),
         ];
 
         for (req, expected) in valid_req {
@@ -172,11 +254,11 @@ mod tests {
             ),
             ("_geoPoint(35, 85, 75):asc", ReservedKeyword { name: S("_geoPoint(35, 85, 75)") }),
             ("_geoPoint(18):asc", ReservedKeyword { name: S("_geoPoint(18)") }),
-            // ("_geoPoint(200, 200):asc", GeoError(BadGeoError::Lat(200.))),
-            // ("_geoPoint(90.000001, 0):asc", GeoError(BadGeoError::Lat(90.000001))),
-            // ("_geoPoint(0, -180.000001):desc", GeoError(BadGeoError::Lng(-180.000001))),
-            // ("_geoPoint(159.256, 130):asc", GeoError(BadGeoError::Lat(159.256))),
-            // ("_geoPoint(12, -2021):desc", GeoError(BadGeoError::Lng(-2021.))),
+            ("_geoPoint(200, 200):asc", GeoError(BadGeoError::Lat(200.))),
+            ("_geoPoint(90.000001, 0):asc", GeoError(BadGeoError::Lat(90.000001))),
+            ("_geoPoint(0, -180.000001):desc", GeoError(BadGeoError::Lng(-180.000001))),
+            ("_geoPoint(159.256, 130):asc", GeoError(BadGeoError::Lat(159.256))),
+            ("_geoPoint(12, -2021):desc", GeoError(BadGeoError::Lng(-2021.))),
             ("_geo(12, -2021):asc", ReservedKeyword { name: S("_geo(12, -2021)") }),
             ("_geo(12, -2021):desc", ReservedKeyword { name: S("_geo(12, -2021)") }),
             ("_geoDistance(12, -2021):asc", ReservedKeyword { name: S("_geoDistance(12, -2021)") }),
@@ -184,6 +266,9 @@ mod tests {
                 "_geoDistance(12, -2021):desc",
                 ReservedKeyword { name: S("_geoDistance(12, -2021)") },
             ),
+            ("_random:asc", ReservedKeyword { name: S("_random") }),
+            ("_random(seed):asc", ReservedKeyword { name: S("_random(seed)") }),
+            ("_random(-1):desc", ReservedKeyword { name: S("_random(-1)") }),
         ];
 
         for (req, expected_error) in invalid_req {
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{AscDesc, Member};
+use crate::ranking_expression::{ExpressionError, RankingExpression};
 
 #[derive(Error, Debug)]
 pub enum CriterionError {
@@ -22,9 +23,13 @@ pub enum CriterionError {
 `{name}` может быть использовано только для фильтрации во время поиска"
     )]
     ReservedNameForFilter { name: String },
+    #[error("Выражение ранжирования `{expression}` недействительно. {error}")]
+    InvalidExpression { expression: String, error: ExpressionError },
+    #[error("Правило ранжирования `{criterion}` недействительно. {reason}")]
+    InvalidDecay { criterion: String, reason: String },
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum Criterion {
 
     Words,
@@ -42,6 +47,96 @@ pub enum Criterion {
     Asc(String),
 
     Desc(String),
+    /// Sorts the documents by the descending value of a formula over numeric facets.
+    Expression(String),
+    /// Sorts the documents by how close the value of a numeric facet is to an origin.
+    Decay(DecayCriterion),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DecayFunction {
+    Gauss,
+    Exp,
+    Linear,
+}
+
+impl FromStr for DecayFunction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gauss" => Ok(DecayFunction::Gauss),
+            "exp" => Ok(DecayFunction::Exp),
+            "linear" => Ok(DecayFunction::Linear),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for DecayFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecayFunction::Gauss => f.write_str("gauss"),
+            DecayFunction::Exp => f.write_str("exp"),
+            DecayFunction::Linear => f.write_str("linear"),
+        }
+    }
+}
+
+/// The configuration of a decay ranking rule.
+///
+/// The score of a document only depends on the distance between the value of `field` and
+/// `origin`. It is 1 up to `offset` and is equal to `decay` at `offset + scale`.
+/// The values and the origin are numbers or Unix timestamps in seconds, the date strings
+/// are converted to timestamps. When there is no origin, the current timestamp is used.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DecayCriterion {
+    pub field: String,
+    pub function: DecayFunction,
+    pub scale: f64,
+    #[serde(default)]
+    pub offset: f64,
+    #[serde(default = "DecayCriterion::default_decay")]
+    pub decay: f64,
+    #[serde(default)]
+    pub origin: Option<f64>,
+}
+
+impl DecayCriterion {
+    fn default_decay() -> f64 {
+        0.5
+    }
+
+    fn validate(&self) -> Result<(), CriterionError> {
+        let reason = if !(self.scale > 0.0 && self.scale.is_finite()) {
+            "Масштаб должен быть положительным числом."
+        } else if !(self.offset >= 0.0 && self.offset.is_finite()) {
+            "Смещение должно быть неотрицательным числом."
+        } else if !(self.decay > 0.0 && self.decay < 1.0) {
+            "Затухание должно быть строго между 0 и 1."
+        } else if self.origin.is_some_and(|origin| !origin.is_finite()) {
+            "Начало отсчета должно быть конечным числом."
+        } else {
+            return Ok(());
+        };
+
+        Err(CriterionError::InvalidDecay {
+            criterion: Criterion::Decay(self.clone()).to_string(),
+            reason: reason.to_string(),
+        })
+    }
+
+    /// Returns the score, between 0 and 1, of a value given the origin.
+    pub fn score(&self, value: f64, origin: f64) -> f64 {
+        let distance = ((value - origin).abs() - self.offset).max(0.0);
+        match self.function {
+            DecayFunction::Gauss => self.decay.powf((distance / self.scale).powi(2)),
+            DecayFunction::Exp => self.decay.powf(distance / self.scale),
+            DecayFunction::Linear => (1.0 - distance * (1.0 - self.decay) / self.scale).max(0.0),
+        }
+    }
 }
 
 impl Criterion {
@@ -52,6 +147,27 @@ impl Criterion {
             _otherwise => None,
         }
     }
+
+    /// Checks that the parameters of the criterion are valid.
+    pub fn validate(&self) -> Result<(), CriterionError> {
+        match self {
+            Criterion::Expression(_) => self.expression().transpose().map(drop),
+            Criterion::Decay(decay) => decay.validate(),
+            _otherwise => Ok(()),
+        }
+    }
+
+    /// Parses the expression of a `Criterion::Expression`.
+    pub fn expression(&self) -> Option<Result<RankingExpression, CriterionError>> {
+        match self {
+            Criterion::Expression(expression) => Some(
+                RankingExpression::from_str(expression).map_err(|error| {
+                    CriterionError::InvalidExpression { expression: expression.clone(), error }
+                }),
+            ),
+            _otherwise => None,
+        }
+    }
 }
 
 impl FromStr for Criterion {
@@ -65,9 +181,38 @@ impl FromStr for Criterion {
             "attribute" => Ok(Criterion::Attribute),
             "sort" => Ok(Criterion::Sort),
             "exactness" => Ok(Criterion::Exactness),
+            text if text.starts_with("expression(") && text.ends_with(')') => {
+                let criterion = Criterion::Expression(text["expression(".len()..text.len() - 1].to_string());
+                criterion.expression().transpose()?;
+                Ok(criterion)
+            }
+            text if text.starts_with("decay(") && text.ends_with(')') => {
+                let invalid = || CriterionError::InvalidName { name: text.to_string() };
+                let args: Vec<_> = text["decay(".len()..text.len() - 1].split(',').map(str::trim).collect();
+                if !(3..=6).contains(&args.len()) {
+                    return Err(invalid());
+                }
+                let number = |i: usize| args.get(i).map(|arg| arg.parse::<f64>().map_err(|_| invalid())).transpose();
+                let decay = DecayCriterion {
+                    field: args[0].to_string(),
+                    function: args[1].parse().map_err(|_| invalid())?,
+                    scale: number(2)?.unwrap_or_default(),
+                    offset: number(3)?.unwrap_or_default(),
+                    decay: number(4)?.unwrap_or_else(DecayCriterion::default_decay),
+                    origin: number(5)?,
+                };
+                decay.validate()?;
+                Ok(Criterion::Decay(decay))
+            }
             text => match AscDesc::from_str(text)? {
                 AscDesc::Asc(Member::Field(field)) => Ok(Criterion::Asc(field)),
                 AscDesc::Desc(Member::Field(field)) => Ok(Criterion::Desc(field)),
+                AscDesc::Asc(Member::Geo(_)) | AscDesc::Desc(Member::Geo(_)) => {
+                    Err(CriterionError::ReservedNameForSort { name: "_geoPoint".to_string() })
+                }
+                AscDesc::Asc(Member::Random(_)) | AscDesc::Desc(Member::Random(_)) => {
+                    Err(CriterionError::ReservedNameForSort { name: "_random".to_string() })
+                }
             },
         }
     }
@@ -97,6 +242,15 @@ impl fmt::Display for Criterion {
             Exactness => f.write_str("exactness"),
             Asc(attr) => write!(f, "{}:asc", attr),
             Desc(attr) => write!(f, "{}:desc", attr),
+            Expression(expression) => write!(f, "expression({})", expression),
+            Decay(decay) => {
+                let DecayCriterion { field, function, scale, offset, decay, origin } = decay;
+                write!(f, "decay({field}, {function}, {scale}, {offset}, {decay}")?;
+                if let Some(origin) = origin {
+                    write!(f, ", {origin}")?;
+                }
+                f.write_str(")")
+            }
         }
     }
 }
@@ -123,6 +277,29 @@ mod tests {
             ("truc:machin:desc", Criterion::Desc(S("truc:machin"))),
             ("hello-world!:desc", Criterion::Desc(S("hello-world!"))),
             ("it's spacy over there:asc", Criterion::Asc(S("it's spacy over there"))),
+            ("expression(log(1 + popularity) * rating)", Criterion::Expression(S("log(1 + popularity) * rating"))),
+            (
+                "decay(published_at, gauss, 86400)",
+                Criterion::Decay(DecayCriterion {
+                    field: S("published_at"),
+                    function: DecayFunction::Gauss,
+                    scale: 86400.0,
+                    offset: 0.0,
+                    decay: 0.5,
+                    origin: None,
+                }),
+            ),
+            (
+                "decay(price, linear, 10, 2, 0.25, 100)",
+                Criterion::Decay(DecayCriterion {
+                    field: S("price"),
+                    function: DecayFunction::Linear,
+                    scale: 10.0,
+                    offset: 2.0,
+                    decay: 0.25,
+                    origin: Some(100.0),
+                }),
+            ),
         ];
 
         for (input, expected) in valid_criteria {
@@ -147,9 +324,23 @@ mod tests {
             ("price:aasc", InvalidName { name: S("price:aasc") }),
             ("price:asc and desc", InvalidName { name: S("price:asc and desc") }),
             ("price:asc:truc", InvalidName { name: S("price:asc:truc") }),
+            (
+                "expression(log(popularity)",
+                InvalidExpression { expression: S("log(popularity"), error: ExpressionError::UnexpectedEnd },
+            ),
+            ("decay(price, square, 10)", InvalidName { name: S("decay(price, square, 10)") }),
+            (
+                "decay(price, exp, -1)",
+                InvalidDecay {
+                    criterion: S("decay(price, exp, -1, 0, 0.5)"),
+                    reason: S("Масштаб должен быть положительным числом."),
+                },
+            ),
             ("_geo:asc", ReservedName { name: S("_geo") }),
             ("_geoDistance:asc", ReservedName { name: S("_geoDistance") }),
             ("_geoPoint:asc", ReservedNameForSort { name: S("_geoPoint") }),
+            ("_random(42):asc", ReservedNameForSort { name: S("_random") }),
+            ("_random(seed):desc", ReservedNameForSort { name: S("_random") }),
             ("_geoPoint(42, 75):asc", ReservedNameForSort { name: S("_geoPoint") }),
             ("_geoRadius:asc", ReservedNameForFilter { name: S("_geoRadius") }),
             ("_geoRadius(42, 75, 59):asc", ReservedNameForFilter { name: S("_geoRadius") }),
@@ -158,6 +349,7 @@ mod tests {
                 "_geoBoundingBox([42, 75], [75, 59]):asc",
                 ReservedNameForFilter { name: S("_geoBoundingBox") },
             ),
+            ("_geoPolygon:asc", ReservedNameForFilter { name: S("_geoPolygon") }),
         ];
 
         for (input, expected) in invalid_criteria {
@@ -129,6 +129,16 @@ pub enum UserError {
         }
     )]
     InvalidSortableAttribute { field: String, valid_fields: BTreeSet<String>},
+    #[error("Атрибут `{}` не может быть использован для distinct. {}",
+        .field,
+        match .valid_fields.is_empty() {
+            true => "Этот индекс не имеет настроенных фильтруемых атрибутов.".to_string(),
+            false => format!("Доступны следующие фильтруемые атрибуты: `{}`.",
+                    valid_fields.iter().map(AsRef::as_ref).collect::<Vec<&str>>().join(", "),
+                ),
+        }
+    )]
+    InvalidDistinctAttribute { field: String, valid_fields: BTreeSet<String>},
     #[error("Атрибут `{}` не поддается фасетному поиску. {}",
         .field,
         match .valid_fields.is_empty() {
@@ -150,6 +160,10 @@ pub enum UserError {
         field: String,
         valid_fields: BTreeSet<String>,
     },
+    #[error("Курсор поиска недействителен или не может быть использован в этом поиске.")]
+    InvalidSearchCursor,
+    #[error("Правило переписывания запроса `{id}` недействительно: {reason}.")]
+    InvalidQueryRewriteRule { id: String, reason: String },
     #[error("Уже открыта среда с другими опциями")]
     InvalidLmdbOpenOptions,
     #[error("Чтобы использовать параметр сортировки во время поиска, необходимо указать критерий `sort` в настройке rankingRules.")]
@@ -176,6 +190,8 @@ pub enum UserError {
     SortError(#[from] SortError),
     #[error("Был использован неизвестный идентификатор документа: `{document_id}`.")]
     UnknownInternalDocumentId { document_id: DocumentId },
+    #[error("Документ с идентификатором `{document_id}` не найден.")]
+    UnknownExternalDocumentId { document_id: String },
     #[error("Настройка `typoTolerance` недействительна. `twoTypos` должны быть больше или равны `oneTypo`.")]
     InvalidMinTypoWordLenSetting(u8, u8),
     #[error(transparent)]
@@ -239,6 +255,12 @@ pub enum UserError {
     },
     #[error("`.embedders.{embedder_name}.dimensions`: не может быть нулевым")]
     InvalidSettingsDimensions { embedder_name: String },
+    #[error("`.embedders.{embedder_name}.aggregationTopK`: не может быть нулевым")]
+    InvalidSettingsAggregationTopK { embedder_name: String },
+    #[error("`.embedders.{embedder_name}.rescoreFactor`: не может быть нулевым")]
+    InvalidSettingsRescoreFactor { embedder_name: String },
+    #[error("`.embedders.{embedder_name}.quantization`: не может быть изменена или удалена после квантования векторов")]
+    InvalidSettingsQuantization { embedder_name: String },
     #[error("`.embedders.{embedder_name}.url`: Невозможно разобрать `{url}`: {inner_error}")]
     InvalidUrl { embedder_name: String, inner_error: url::ParseError, url: String },
 }
@@ -0,0 +1,33 @@
+use std::borrow::Cow;
+use std::convert::TryInto;
+
+use heed::BoxedError;
+
+use super::SliceTooShortError;
+
+pub struct BEU16BEU32Codec;
+
+impl<'a> heed::BytesDecode<'a> for BEU16BEU32Codec {
+    type DItem = (u16, u32);
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, BoxedError> {
+        if bytes.len() != 6 {
+            return Err(SliceTooShortError.into());
+        }
+        let (n_bytes, m_bytes) = bytes.split_at(2);
+        let n = n_bytes.try_into().map(u16::from_be_bytes)?;
+        let m = m_bytes.try_into().map(u32::from_be_bytes)?;
+        Ok((n, m))
+    }
+}
+
+impl<'a> heed::BytesEncode<'a> for BEU16BEU32Codec {
+    type EItem = (u16, u32);
+
+    fn bytes_encode((n, m): &Self::EItem) -> Result<Cow<[u8]>, BoxedError> {
+        let mut bytes = Vec::with_capacity(6);
+        bytes.extend_from_slice(&n.to_be_bytes());
+        bytes.extend_from_slice(&m.to_be_bytes());
+        Ok(Cow::Owned(bytes))
+    }
+}
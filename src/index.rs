@@ -20,26 +20,30 @@ use crate::heed_codec::facet::{
     FieldIdCodec, OrderedF64Codec,
 };
 use crate::heed_codec::{
-    BEU16StrCodec, FstSetCodec, ScriptLanguageCodec, StrBEU16Codec, StrRefCodec,
+    BEU16BEU32Codec, BEU16StrCodec, FstSetCodec, ScriptLanguageCodec, StrBEU16Codec, StrRefCodec,
 };
 use crate::order_by_map::OrderByMap;
 use crate::proximity::ProximityPrecision;
-use crate::vector::EmbeddingConfig;
+use crate::vector::{EmbeddingConfig, VectorDistance, VectorQuantization};
 use crate::{
     default_criteria, CboRoaringBitmapCodec, Criterion, DocumentId, ExternalDocumentsIds,
-     FieldDistribution, FieldId, FieldIdWordCountCodec, ObkvCodec,
+    FieldDistribution, FieldId, GeoPoint, FieldIdWordCountCodec, ObkvCodec,
     Result, RoaringBitmapCodec, RoaringBitmapLenCodec, Search, U8StrStrCodec, BEU16, BEU32, BEU64,
 };
 use crate::search::facet::FacetDistribution;
 use crate::update::analyzer_settings::{AnalyzerConfig, AnalyzerConfigs};
 use crate::update::split_config::SplitJoinConfig;
 use crate::update::typo_config::TypoConfig;
+use crate::update::curation_rules::CurationRule;
+use crate::update::query_rewrite_rules::QueryRewriteRule;
 
 pub const DEFAULT_MIN_WORD_LEN_ONE_TYPO: u8 = 5;
 pub const DEFAULT_MIN_WORD_LEN_TWO_TYPOS: u8 = 9;
 
 pub mod main_key {
     pub const CRITERIA_KEY: &str = "criteria";
+    pub const CURATION_RULES_KEY: &str = "curation-rules";
+    pub const DISTINCT_FIELD_KEY: &str = "distinct-field-key";
     pub const DOCUMENTS_IDS_KEY: &str = "documents-ids";
     pub const HIDDEN_FACETED_FIELDS_KEY: &str = "hidden-faceted-fields";
     pub const FILTERABLE_FIELDS_KEY: &str = "filterable-fields";
@@ -50,6 +54,7 @@ pub mod main_key {
     pub const GEO_RTREE_KEY: &str = "geo-rtree";
     pub const PRIMARY_KEY_KEY: &str = "primary-key";
     pub const SEARCHABLE_FIELDS_KEY: &str = "searchable-fields";
+    pub const SEARCHABLE_ATTRIBUTE_WEIGHTS_KEY: &str = "searchable-attribute-weights";
     pub const USER_DEFINED_SEARCHABLE_FIELDS_KEY: &str = "user-defined-searchable-fields";
     pub const SYNONYMS_KEY: &str = "synonyms";
     pub const USER_DEFINED_SYNONYMS_KEY: &str = "user-defined-synonyms";
@@ -63,6 +68,7 @@ pub mod main_key {
     pub const SORT_FACET_VALUES_BY: &str = "sort-facet-values-by";
     pub const PAGINATION_MAX_TOTAL_HITS: &str = "pagination-max-total-hits";
     pub const PROXIMITY_PRECISION: &str = "proximity-precision";
+    pub const QUERY_REWRITE_RULES_KEY: &str = "query-rewrite-rules";
     pub const EMBEDDING_CONFIGS: &str = "embedding_configs";
     pub const ANALYZER_CONFIGS: &str = "analyzer_configs";
 }
@@ -92,6 +98,7 @@ pub mod db_name {
     pub const FIELD_ID_DOCID_FACET_STRINGS: &str = "field-id-docid-facet-strings";
     pub const VECTOR_EMBEDDER_CATEGORY_ID: &str = "vector-embedder-category-id";
     pub const VECTOR_ARROY: &str = "vector-arroy";
+    pub const VECTOR_QUANTIZED: &str = "vector-quantized";
     pub const DOCUMENTS: &str = "documents";
     pub const SCRIPT_LANGUAGE_DOCIDS: &str = "script_language_docids";
 }
@@ -152,7 +159,12 @@ pub struct Index {
     /// Maps an embedder name to its id in the arroy store.
     pub embedder_category_id: Database<Str, U8>,
     /// Vector store based on arroy™.
+    ///
+    /// The trees of each embedder are built with its own distance, the database is remapped accordingly.
     pub vector_arroy: arroy::Database<arroy::distances::Angular>,
+    /// Maps the arroy tree index and the document id with the quantized vector of the document,
+    /// stored instead of the arroy tree when the embedder is quantized.
+    pub vector_quantized: Database<BEU16BEU32Codec, Bytes>,
 
     /// Maps the document id to the document as an obkv store.
     pub(crate) documents: Database<BEU32, ObkvCodec>,
@@ -206,6 +218,7 @@ impl Index {
         let embedder_category_id =
             env.create_database(&mut wtxn, Some(VECTOR_EMBEDDER_CATEGORY_ID))?;
         let vector_arroy = env.create_database(&mut wtxn, Some(VECTOR_ARROY))?;
+        let vector_quantized = env.create_database(&mut wtxn, Some(VECTOR_QUANTIZED))?;
 
         let documents = env.create_database(&mut wtxn, Some(DOCUMENTS))?;
         wtxn.commit()?;
@@ -234,6 +247,7 @@ impl Index {
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
             vector_arroy,
+            vector_quantized,
             embedder_category_id,
             documents,
         })
@@ -420,6 +434,69 @@ impl Index {
             .unwrap_or_default())
     }
 
+    /* geo rtree */
+
+    /// Writes the provided `rtree` which associates coordinates to documents ids.
+    pub(crate) fn put_geo_rtree(
+        &self,
+        wtxn: &mut RwTxn,
+        rtree: &RTree<GeoPoint>,
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, SerdeBincode<RTree<GeoPoint>>>().put(
+            wtxn,
+            main_key::GEO_RTREE_KEY,
+            rtree,
+        )
+    }
+
+    /// Delete the `rtree` which associates coordinates to documents ids.
+    pub(crate) fn delete_geo_rtree(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(wtxn, main_key::GEO_RTREE_KEY)
+    }
+
+    /// Returns the `rtree` which associates coordinates to documents ids.
+    pub fn geo_rtree(&self, rtxn: &RoTxn) -> Result<Option<RTree<GeoPoint>>> {
+        match self
+            .main
+            .remap_types::<Str, SerdeBincode<RTree<GeoPoint>>>()
+            .get(rtxn, main_key::GEO_RTREE_KEY)?
+        {
+            Some(rtree) => Ok(Some(rtree)),
+            None => Ok(None),
+        }
+    }
+
+    /* geo faceted */
+
+    /// Writes the documents ids that are faceted with a _geo field.
+    pub(crate) fn put_geo_faceted_documents_ids(
+        &self,
+        wtxn: &mut RwTxn,
+        docids: &RoaringBitmap,
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, RoaringBitmapCodec>().put(
+            wtxn,
+            main_key::GEO_FACETED_DOCUMENTS_IDS_KEY,
+            docids,
+        )
+    }
+
+    /// Delete the documents ids that are faceted with a _geo field.
+    pub(crate) fn delete_geo_faceted_documents_ids(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(wtxn, main_key::GEO_FACETED_DOCUMENTS_IDS_KEY)
+    }
+
+    /// Retrieve all the documents ids that are faceted with a _geo field.
+    pub fn geo_faceted_documents_ids(&self, rtxn: &RoTxn) -> heed::Result<RoaringBitmap> {
+        match self
+            .main
+            .remap_types::<Str, RoaringBitmapCodec>()
+            .get(rtxn, main_key::GEO_FACETED_DOCUMENTS_IDS_KEY)?
+        {
+            Some(docids) => Ok(docids),
+            None => Ok(RoaringBitmap::new()),
+        }
+    }
 
     /* searchable fields */
 
@@ -529,6 +606,61 @@ impl Index {
             .get(rtxn, main_key::USER_DEFINED_SEARCHABLE_FIELDS_KEY)
     }
 
+    /* searchable attribute weights */
+
+    /// Writes the weights of the searchable attributes in the database.
+    pub(crate) fn put_searchable_attribute_weights(
+        &self,
+        wtxn: &mut RwTxn,
+        weights: &BTreeMap<String, u16>,
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, SerdeJson<_>>().put(
+            wtxn,
+            main_key::SEARCHABLE_ATTRIBUTE_WEIGHTS_KEY,
+            weights,
+        )
+    }
+
+    /// Deletes the weights of the searchable attributes in the database.
+    pub(crate) fn delete_searchable_attribute_weights(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(wtxn, main_key::SEARCHABLE_ATTRIBUTE_WEIGHTS_KEY)
+    }
+
+    /// Returns the weights of the searchable attributes, when there is no weight
+    /// the attributes are ranked by their order in the searchable fields.
+    pub fn searchable_attribute_weights(&self, rtxn: &RoTxn) -> heed::Result<BTreeMap<String, u16>> {
+        Ok(self
+            .main
+            .remap_types::<Str, SerdeJson<_>>()
+            .get(rtxn, main_key::SEARCHABLE_ATTRIBUTE_WEIGHTS_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Identical to `searchable_attribute_weights`, but returns the ids instead.
+    ///
+    /// The nested fields of an attribute share its weight.
+    pub fn searchable_attribute_weights_ids(&self, rtxn: &RoTxn) -> Result<HashMap<FieldId, u16>> {
+        let weights = self.searchable_attribute_weights(rtxn)?;
+        if weights.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        let mut weights_ids = HashMap::new();
+        for (field_id, name) in fields_ids_map.iter() {
+            let weight = weights
+                .iter()
+                .filter(|(attribute, _)| crate::is_faceted_by(name, attribute))
+                .max_by_key(|(attribute, _)| attribute.len())
+                .map(|(_, weight)| *weight);
+            if let Some(weight) = weight {
+                weights_ids.insert(field_id, weight);
+            }
+        }
+
+        Ok(weights_ids)
+    }
+
     /* filterable fields */
 
     /// Writes the filterable fields names in the database.
@@ -609,6 +741,23 @@ impl Index {
         Ok(fields.into_iter().filter_map(|name| fields_ids_map.id(&name)).collect())
     }
 
+    /* distinct field */
+
+    /// Writes the distinct field name in the database.
+    pub(crate) fn put_distinct_field(&self, wtxn: &mut RwTxn, distinct_field: &str) -> heed::Result<()> {
+        self.main.remap_types::<Str, Str>().put(wtxn, main_key::DISTINCT_FIELD_KEY, distinct_field)
+    }
+
+    /// Deletes the distinct field name in the database.
+    pub(crate) fn delete_distinct_field(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(wtxn, main_key::DISTINCT_FIELD_KEY)
+    }
+
+    /// Returns the distinct field name.
+    pub fn distinct_field<'t>(&self, rtxn: &'t RoTxn) -> heed::Result<Option<&'t str>> {
+        self.main.remap_types::<Str, Str>().get(rtxn, main_key::DISTINCT_FIELD_KEY)
+    }
+
     /* faceted fields */
 
     /// Writes the faceted fields in the database.
@@ -652,19 +801,33 @@ impl Index {
 
     /// Returns the user defined faceted fields names.
     ///
-    /// The user faceted fields are the union of all the filterable, sortable, distinct, and Asc/Desc fields.
+    /// The user faceted fields are the union of all the filterable, sortable, distinct, Asc/Desc,
+    /// decay fields and the fields used by the ranking expressions.
     pub fn user_defined_faceted_fields(&self, rtxn: &RoTxn) -> Result<HashSet<String>> {
         let filterable_fields = self.filterable_fields(rtxn)?;
         let sortable_fields = self.sortable_fields(rtxn)?;
+        let distinct_field = self.distinct_field(rtxn)?;
+        let criteria = self.criteria(rtxn)?;
         let asc_desc_fields =
-            self.criteria(rtxn)?.into_iter().filter_map(|criterion| match criterion {
-                Criterion::Asc(field) | Criterion::Desc(field) => Some(field),
+            criteria.iter().filter_map(|criterion| match criterion {
+                Criterion::Asc(field) | Criterion::Desc(field) => Some(field.clone()),
+                Criterion::Decay(decay) => Some(decay.field.clone()),
                 _otherwise => None,
             });
+        let expression_fields = criteria
+            .iter()
+            .filter_map(|criterion| criterion.expression().and_then(|expression| expression.ok()))
+            .flat_map(|expression| {
+                expression.fields().into_iter().map(str::to_string).collect::<Vec<_>>()
+            });
 
         let mut faceted_fields = filterable_fields;
         faceted_fields.extend(sortable_fields);
         faceted_fields.extend(asc_desc_fields);
+        faceted_fields.extend(expression_fields);
+        if let Some(field) = distinct_field {
+            faceted_fields.insert(field.to_owned());
+        }
 
         Ok(faceted_fields)
     }
@@ -827,6 +990,56 @@ impl Index {
         Ok(self.synonyms(rtxn)?.remove(&words))
     }
 
+    /* curation rules */
+
+    pub(crate) fn put_curation_rules(&self, wtxn: &mut RwTxn, rules: &[CurationRule]) -> heed::Result<()> {
+        self.main.remap_types::<Str, SerdeJson<&[CurationRule]>>().put(
+            wtxn,
+            main_key::CURATION_RULES_KEY,
+            &rules,
+        )
+    }
+
+    pub(crate) fn delete_curation_rules(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(wtxn, main_key::CURATION_RULES_KEY)
+    }
+
+    /// Returns the rules pinning and hiding documents for some queries.
+    pub fn curation_rules(&self, rtxn: &RoTxn) -> heed::Result<Vec<CurationRule>> {
+        Ok(self
+            .main
+            .remap_types::<Str, SerdeJson<_>>()
+            .get(rtxn, main_key::CURATION_RULES_KEY)?
+            .unwrap_or_default())
+    }
+
+    /* query rewrite rules */
+
+    pub(crate) fn put_query_rewrite_rules(
+        &self,
+        wtxn: &mut RwTxn,
+        rules: &[QueryRewriteRule],
+    ) -> heed::Result<()> {
+        self.main.remap_types::<Str, SerdeJson<&[QueryRewriteRule]>>().put(
+            wtxn,
+            main_key::QUERY_REWRITE_RULES_KEY,
+            &rules,
+        )
+    }
+
+    pub(crate) fn delete_query_rewrite_rules(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.remap_key_type::<Str>().delete(wtxn, main_key::QUERY_REWRITE_RULES_KEY)
+    }
+
+    /// Returns the rules rewriting the queries, in the order they are applied.
+    pub fn query_rewrite_rules(&self, rtxn: &RoTxn) -> heed::Result<Vec<QueryRewriteRule>> {
+        Ok(self
+            .main
+            .remap_types::<Str, SerdeJson<_>>()
+            .get(rtxn, main_key::QUERY_REWRITE_RULES_KEY)?
+            .unwrap_or_default())
+    }
+
     /* words prefixes fst */
 
     /// Writes the FST which is the words prefixes dictionary of the engine.
@@ -1078,6 +1291,28 @@ impl Index {
             .unwrap_or_default())
     }
 
+    /// Returns the distance of the vectors of the embedder, the angular distance for an unknown embedder.
+    pub fn embedder_distance(&self, rtxn: &RoTxn<'_>, embedder_name: &str) -> Result<VectorDistance> {
+        Ok(self
+            .embedding_configs(rtxn)?
+            .into_iter()
+            .find_map(|(name, config)| (name == embedder_name).then_some(config.distance))
+            .unwrap_or_default())
+    }
+
+    /// Returns the quantization of the vectors of the embedder, `None` when they aren't quantized.
+    pub fn embedder_quantization(
+        &self,
+        rtxn: &RoTxn<'_>,
+        embedder_name: &str,
+    ) -> Result<Option<VectorQuantization>> {
+        Ok(self
+            .embedding_configs(rtxn)?
+            .into_iter()
+            .find_map(|(name, config)| (name == embedder_name).then_some(config.quantization))
+            .flatten())
+    }
+
     pub(crate) fn put_embedding_configs(
         &self,
         wtxn: &mut RwTxn<'_>,
@@ -1143,11 +1378,12 @@ impl Index {
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use std::collections::HashSet;
+    use std::collections::{BTreeMap, HashSet};
     use std::ops::Deref;
+    use std::sync::Arc;
 
     use big_s::S;
-    use heed::{EnvOpenOptions, RwTxn};
+    use heed::{EnvOpenOptions, RoTxn, RwTxn};
     use maplit::hashset;
     use tempfile::TempDir;
 
@@ -1155,10 +1391,13 @@ pub(crate) mod tests {
     use crate::error::{Error, InternalError};
     use crate::index::{DEFAULT_MIN_WORD_LEN_ONE_TYPO, DEFAULT_MIN_WORD_LEN_TWO_TYPOS};
     use crate::update::{
-        self, IndexDocuments, IndexDocumentsConfig, IndexDocumentsMethod, IndexerConfig, Settings,
+        self, IndexDocuments, IndexDocumentsConfig, IndexDocumentsMethod, IndexerConfig, Setting,
+        Settings,
     };
     use crate::{ obkv_to_json, Index, Search, SearchResult};
     use crate::search::facet::Filter;
+    use crate::vector::settings::{EmbedderSource, EmbeddingSettings};
+    use crate::vector::{Embedder, EmbeddingConfig, EmbeddingConfigs};
 
     pub(crate) struct TempIndex {
         pub inner: Index,
@@ -1207,7 +1446,8 @@ pub(crate) mod tests {
                 |_| (),
                 || false,
             )
-            .unwrap();
+            .unwrap()
+            .with_embedders(self.embedders(wtxn)?);
             let (builder, user_error) = builder.add_documents(documents).unwrap();
             user_error?;
             builder.execute()?;
@@ -1226,6 +1466,21 @@ pub(crate) mod tests {
             Ok(())
         }
 
+        /// Builds the embedders configured in the settings of the index.
+        fn embedders(&self, rtxn: &RoTxn) -> Result<EmbeddingConfigs, crate::error::Error> {
+            let embedders = self
+                .embedding_configs(rtxn)?
+                .into_iter()
+                .map(|(name, EmbeddingConfig { embedder_options, prompt, .. })| {
+                    let prompt = Arc::new(prompt.try_into().map_err(crate::Error::from)?);
+                    let embedder =
+                        Arc::new(Embedder::new(embedder_options).map_err(crate::vector::Error::from)?);
+                    Ok((name, (embedder, prompt)))
+                })
+                .collect::<Result<_, crate::error::Error>>()?;
+            Ok(EmbeddingConfigs::new(embedders))
+        }
+
         pub fn update_settings(
             &self,
             update: impl Fn(&mut Settings),
@@ -1246,6 +1501,29 @@ pub(crate) mod tests {
             Ok(())
         }
 
+        /// Sets a user provided embedder of two dimensions named `manual`, customised by `update`.
+        pub fn set_manual_embedder(
+            &self,
+            update: impl Fn(&mut EmbeddingSettings),
+        ) -> Result<(), crate::error::Error> {
+            self.update_settings(|settings| {
+                let mut embedder = EmbeddingSettings {
+                    source: Setting::Set(EmbedderSource::UserProvided),
+                    dimensions: Setting::Set(2),
+                    ..Default::default()
+                };
+                update(&mut embedder);
+                settings.set_embedder_settings(BTreeMap::from([(S("manual"), Setting::Set(embedder))]));
+            })
+        }
+
+        /// Builds the embedder set by [`TempIndex::set_manual_embedder`].
+        pub fn manual_embedder(&self) -> Arc<Embedder> {
+            let rtxn = self.read_txn().unwrap();
+            let (embedder, _) = self.embedders(&rtxn).unwrap().get("manual").unwrap();
+            embedder
+        }
+
         pub fn delete_documents_using_wtxn<'t>(
             &'t self,
             wtxn: &mut RwTxn<'t>,
@@ -21,6 +21,7 @@ pub mod documents;
 
 mod asc_desc;
 mod criterion;
+pub mod ranking_expression;
 mod error;
 mod external_documents_ids;
 pub mod facet;
@@ -50,7 +51,9 @@ use serde_json::Value;
 pub use {charabia as tokenizer, heed};
 
 pub use self::asc_desc::{AscDesc, AscDescError, Member, SortError};
-pub use self::criterion::{default_criteria, Criterion, CriterionError};
+pub use self::criterion::{
+    default_criteria, Criterion, CriterionError, DecayCriterion, DecayFunction,
+};
 pub use self::error::{
     Error, FieldIdMapMissingEntry, InternalError, SerializationError, UserError,
 };
@@ -63,10 +66,12 @@ pub use self::heed_codec::{
     UncheckedU8StrStrCodec,
 };
 pub use self::index::Index;
-pub use self::search::facet::{OrderBy, FacetValueHit, SearchForFacetValues, DEFAULT_VALUES_PER_FACET};
-pub use self::search::matches::{FormatOptions, MatchBounds, MatcherBuilder, MatchingWords };
+pub use self::search::facet::{BadGeoError, OrderBy, FacetValueHit, SearchForFacetValues, DEFAULT_VALUES_PER_FACET};
+pub use self::search::matches::{FormatOptions, Fragment, MatchBounds, MatcherBuilder, MatchingWords };
 pub use self::search::{
-    Filter, Search, SearchResult, SemanticSearch, TermsMatchingStrategy,
+    DocumentsOptions, Filter, HybridHit, HybridHitSource, HybridMergeStrategy, QueryExplanation, Search,
+    SearchCursor, SearchHit, SearchResult, SemanticSearch, SimilarOptions, SimilarResult, SuggestOptions,
+    Suggestion, TermsMatchingStrategy, VectorSearchStrategy,
 };
 pub use self::update::thread_pool_no_abort::{ThreadPoolNoAbortBuilder, ThreadPoolNoAbort};
 
@@ -92,6 +97,7 @@ pub type SmallVec8<T> = smallvec::SmallVec<[T; 8]>;
 /// A GeoPoint is a point in cartesian plan, called xyz_point in the code. Its metadata
 /// is a tuple composed of 1. the DocumentId of the associated document and 2. the original point
 /// expressed in term of latitude and longitude.
+pub type GeoPoint = rstar::primitives::GeomWithData<[f64; 3], (DocumentId, [f64; 2])>;
 
 /// The maximum length a LMDB key can be.
 ///
@@ -126,7 +132,7 @@ impl fmt::Debug for TimeBudget {
         f.debug_struct("TimeBudget")
             .field("started_at", &self.started_at)
             .field("budget", &self.budget)
-            .field("left", &(self.budget - self.started_at.elapsed()))
+            .field("left", &self.budget.saturating_sub(self.started_at.elapsed()))
             .finish()
     }
 }
@@ -321,6 +327,13 @@ pub fn lat_lng_to_xyz(coord: &[f64; 2]) -> [f64; 3] {
     [x, y, z]
 }
 
+/// Returns `true` if the keyword is reserved to the geo search or the random sort and can't be
+/// used as a field name.
+pub fn is_reserved_keyword(keyword: &str) -> bool {
+    ["_geo", "_geoDistance", "_geoPoint", "_geoRadius", "_geoBoundingBox", "_geoPolygon", "_random"]
+        .contains(&keyword)
+}
+
 /// Returns `true` if the field match one of the faceted fields.
 /// See the function [`is_faceted_by`] below to see what “matching” means.
 pub fn is_faceted(field: &str, faceted_fields: impl IntoIterator<Item = impl AsRef<str>>) -> bool {
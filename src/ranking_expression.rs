@@ -0,0 +1,357 @@
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ExpressionError {
+    #[error("Неожиданный символ `{found}` на позиции {position}.")]
+    UnexpectedChar { found: char, position: usize },
+    #[error("Неожиданный конец выражения.")]
+    UnexpectedEnd,
+    #[error("Неизвестная функция `{name}`. К допустимым функциям относятся: log, log10, exp, sqrt, abs, min, max и pow.")]
+    UnknownFunction { name: String },
+    #[error("Функция `{name}` принимает {expected} аргумент(а), получено {found}.")]
+    InvalidArity { name: String, expected: usize, found: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Log,
+    Log10,
+    Exp,
+    Sqrt,
+    Abs,
+    Min,
+    Max,
+    Pow,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        match name {
+            "log" => Some(Function::Log),
+            "log10" => Some(Function::Log10),
+            "exp" => Some(Function::Exp),
+            "sqrt" => Some(Function::Sqrt),
+            "abs" => Some(Function::Abs),
+            "min" => Some(Function::Min),
+            "max" => Some(Function::Max),
+            "pow" => Some(Function::Pow),
+            _ => None,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Function::Log | Function::Log10 | Function::Exp | Function::Sqrt | Function::Abs => 1,
+            Function::Min | Function::Max | Function::Pow => 2,
+        }
+    }
+
+    fn apply(&self, args: &[f64]) -> f64 {
+        match self {
+            Function::Log => args[0].ln(),
+            Function::Log10 => args[0].log10(),
+            Function::Exp => args[0].exp(),
+            Function::Sqrt => args[0].sqrt(),
+            Function::Abs => args[0].abs(),
+            Function::Min => args[0].min(args[1]),
+            Function::Max => args[0].max(args[1]),
+            Function::Pow => args[0].powf(args[1]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+/// A formula over the numeric facets of a document, e.g. `log(1 + popularity) * rating`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RankingExpression {
+    Number(f64),
+    Field(String),
+    Neg(Box<RankingExpression>),
+    Binary(Operator, Box<RankingExpression>, Box<RankingExpression>),
+    Call(Function, Vec<RankingExpression>),
+}
+
+impl RankingExpression {
+    /// Returns the names of the fields used by the expression.
+    pub fn fields(&self) -> Vec<&str> {
+        let mut fields = Vec::new();
+        self.collect_fields(&mut fields);
+        fields.sort_unstable();
+        fields.dedup();
+        fields
+    }
+
+    fn collect_fields<'a>(&'a self, fields: &mut Vec<&'a str>) {
+        match self {
+            RankingExpression::Number(_) => (),
+            RankingExpression::Field(name) => fields.push(name),
+            RankingExpression::Neg(inner) => inner.collect_fields(fields),
+            RankingExpression::Binary(_, left, right) => {
+                left.collect_fields(fields);
+                right.collect_fields(fields);
+            }
+            RankingExpression::Call(_, args) => args.iter().for_each(|arg| arg.collect_fields(fields)),
+        }
+    }
+
+    /// Evaluates the expression, `value` gives the value of a field for the document.
+    pub fn evaluate(&self, value: &impl Fn(&str) -> f64) -> f64 {
+        match self {
+            RankingExpression::Number(number) => *number,
+            RankingExpression::Field(name) => value(name),
+            RankingExpression::Neg(inner) => -inner.evaluate(value),
+            RankingExpression::Binary(operator, left, right) => {
+                let (left, right) = (left.evaluate(value), right.evaluate(value));
+                match operator {
+                    Operator::Add => left + right,
+                    Operator::Sub => left - right,
+                    Operator::Mul => left * right,
+                    Operator::Div => left / right,
+                    Operator::Pow => left.powf(right),
+                }
+            }
+            RankingExpression::Call(function, args) => {
+                let args: Vec<_> = args.iter().map(|arg| arg.evaluate(value)).collect();
+                function.apply(&args)
+            }
+        }
+    }
+}
+
+impl FromStr for RankingExpression {
+    type Err = ExpressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { input: s, position: 0 };
+        let expression = parser.expression()?;
+        parser.skip_whitespaces();
+        match parser.peek() {
+            Some(found) => Err(ExpressionError::UnexpectedChar { found, position: parser.position }),
+            None => Ok(expression),
+        }
+    }
+}
+
+impl fmt::Display for RankingExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RankingExpression::Number(number) => write!(f, "{number}"),
+            RankingExpression::Field(name) => f.write_str(name),
+            RankingExpression::Neg(inner) => write!(f, "-({inner})"),
+            RankingExpression::Binary(operator, left, right) => {
+                let operator = match operator {
+                    Operator::Add => "+",
+                    Operator::Sub => "-",
+                    Operator::Mul => "*",
+                    Operator::Div => "/",
+                    Operator::Pow => "^",
+                };
+                write!(f, "({left} {operator} {right})")
+            }
+            RankingExpression::Call(function, args) => {
+                let name = format!("{function:?}").to_lowercase();
+                write!(f, "{name}(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+/// A recursive descent parser over the following grammar:
+///
+/// ```text
+/// expression := term (('+' | '-') term)*
+/// term       := unary (('*' | '/') unary)*
+/// unary      := '-' unary | power
+/// power      := primary ('^' unary)?
+/// primary    := number | function '(' expression (',' expression)* ')' | field | '(' expression ')'
+/// ```
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.position += c.len_utf8();
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespaces();
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ExpressionError> {
+        if self.eat(expected) {
+            return Ok(());
+        }
+        match self.peek() {
+            Some(found) => Err(ExpressionError::UnexpectedChar { found, position: self.position }),
+            None => Err(ExpressionError::UnexpectedEnd),
+        }
+    }
+
+    fn expression(&mut self) -> Result<RankingExpression, ExpressionError> {
+        let mut left = self.term()?;
+        loop {
+            let operator = if self.eat('+') {
+                Operator::Add
+            } else if self.eat('-') {
+                Operator::Sub
+            } else {
+                return Ok(left);
+            };
+            let right = self.term()?;
+            left = RankingExpression::Binary(operator, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn term(&mut self) -> Result<RankingExpression, ExpressionError> {
+        let mut left = self.unary()?;
+        loop {
+            let operator = if self.eat('*') {
+                Operator::Mul
+            } else if self.eat('/') {
+                Operator::Div
+            } else {
+                return Ok(left);
+            };
+            let right = self.unary()?;
+            left = RankingExpression::Binary(operator, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Result<RankingExpression, ExpressionError> {
+        if self.eat('-') {
+            return Ok(RankingExpression::Neg(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<RankingExpression, ExpressionError> {
+        let base = self.primary()?;
+        if self.eat('^') {
+            let exponent = self.unary()?;
+            return Ok(RankingExpression::Binary(Operator::Pow, Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<RankingExpression, ExpressionError> {
+        self.skip_whitespaces();
+        let start = self.position;
+        match self.peek() {
+            None => Err(ExpressionError::UnexpectedEnd),
+            Some('(') => {
+                self.position += 1;
+                let expression = self.expression()?;
+                self.expect(')')?;
+                Ok(expression)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                while self.peek().map_or(false, |c| c.is_ascii_digit() || c == '.') {
+                    self.position += 1;
+                }
+                self.input[start..self.position]
+                    .parse()
+                    .map(RankingExpression::Number)
+                    .map_err(|_| ExpressionError::UnexpectedChar { found: c, position: start })
+            }
+            Some(c) if c.is_alphanumeric() || c == '_' => {
+                while self.peek().map_or(false, |c| c.is_alphanumeric() || c == '_' || c == '.') {
+                    self.position += self.peek().map_or(0, char::len_utf8);
+                }
+                let name = &self.input[start..self.position];
+                if !self.eat('(') {
+                    return Ok(RankingExpression::Field(name.to_string()));
+                }
+
+                let function = Function::from_name(name)
+                    .ok_or_else(|| ExpressionError::UnknownFunction { name: name.to_string() })?;
+                let mut args = vec![self.expression()?];
+                while self.eat(',') {
+                    args.push(self.expression()?);
+                }
+                self.expect(')')?;
+                if args.len() != function.arity() {
+                    return Err(ExpressionError::InvalidArity {
+                        name: name.to_string(),
+                        expected: function.arity(),
+                        found: args.len(),
+                    });
+                }
+                Ok(RankingExpression::Call(function, args))
+            }
+            Some(found) => Err(ExpressionError::UnexpectedChar { found, position: start }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_evaluate() {
+        let expression: RankingExpression = "log(1 + popularity) * rating".parse().unwrap();
+        assert_eq!(expression.fields(), ["popularity", "rating"]);
+
+        let value = expression.evaluate(&|field| match field {
+            "popularity" => std::f64::consts::E - 1.0,
+            "rating" => 4.0,
+            _ => 0.0,
+        });
+        assert!((value - 4.0).abs() < 1e-9);
+
+        let expression: RankingExpression = "-2 ^ 2 + max(a.b, 3) / 2".parse().unwrap();
+        assert_eq!(expression.evaluate(&|_| 5.0), -4.0 + 2.5);
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        let invalid = [
+            ("", ExpressionError::UnexpectedEnd),
+            ("1 +", ExpressionError::UnexpectedEnd),
+            ("(rating", ExpressionError::UnexpectedEnd),
+            ("rating rating", ExpressionError::UnexpectedChar { found: 'r', position: 7 }),
+            ("rating $ 2", ExpressionError::UnexpectedChar { found: '$', position: 7 }),
+            ("ln(rating)", ExpressionError::UnknownFunction { name: "ln".to_string() }),
+            (
+                "pow(rating)",
+                ExpressionError::InvalidArity { name: "pow".to_string(), expected: 2, found: 1 },
+            ),
+        ];
+
+        for (input, expected) in invalid {
+            assert_eq!(input.parse::<RankingExpression>().unwrap_err(), expected, "{input}");
+        }
+    }
+}
@@ -1,11 +1,11 @@
 use std::cmp::Ordering;
 
 use itertools::Itertools;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::{ distance_between_two_points};
+use crate::distance_between_two_points;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ScoreDetails {
     Words(Words),
     Typo(Typo),
@@ -13,18 +13,28 @@ pub enum ScoreDetails {
     Exactness(ExactWords),
     Attribute(Attribute),
     Sort(Sort),
+    GeoSort(GeoSort),
+    Random(Random),
     Vector(Vector),
+    Expression(Expression),
+    Decay(Decay),
 }
 
 #[derive(Clone, Copy)]
 pub enum ScoreValue<'a> {
     Score(f64),
     Sort(&'a Sort),
+    GeoSort(&'a GeoSort),
+    Random(&'a Random),
+    Expression(&'a Expression),
 }
 
 enum RankOrValue<'a> {
     Rank(Rank),
     Sort(&'a Sort),
+    GeoSort(&'a GeoSort),
+    Random(&'a Random),
+    Expression(&'a Expression),
     Score(f64),
 }
 
@@ -41,7 +51,31 @@ impl ScoreDetails {
             ScoreDetails::Attribute(details) => Some(details.rank()),
             ScoreDetails::Exactness(details) => Some(details.rank()),
             ScoreDetails::Sort(_) => None,
+            ScoreDetails::GeoSort(_) => None,
+            ScoreDetails::Random(_) => None,
             ScoreDetails::Vector(_) => None,
+            ScoreDetails::Expression(_) => None,
+            ScoreDetails::Decay(_) => None,
+        }
+    }
+
+    /// Compares the details given by the same ranking rule to two buckets,
+    /// `Greater` means that the bucket of `self` is returned before the bucket of `other`.
+    pub fn partial_cmp_bucket(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (ScoreDetails::Sort(left), ScoreDetails::Sort(right)) => left.partial_cmp(right),
+            (ScoreDetails::GeoSort(left), ScoreDetails::GeoSort(right)) => left.partial_cmp(right),
+            (ScoreDetails::Random(left), ScoreDetails::Random(right)) => left.partial_cmp(right),
+            (ScoreDetails::Vector(left), ScoreDetails::Vector(right)) => left.partial_cmp(right),
+            (ScoreDetails::Expression(left), ScoreDetails::Expression(right)) => left.partial_cmp(right),
+            (ScoreDetails::Decay(left), ScoreDetails::Decay(right)) => left.partial_cmp(right),
+            (left, right) if std::mem::discriminant(left) == std::mem::discriminant(right) => {
+                let (left, right) = (left.rank()?, right.rank()?);
+                let left_score = left.rank as u64 * right.max_rank as u64;
+                let right_score = right.rank as u64 * left.max_rank as u64;
+                Some(left_score.cmp(&right_score))
+            }
+            _ => None,
         }
     }
 
@@ -70,6 +104,9 @@ impl ScoreDetails {
             .map(|rank_or_value| match rank_or_value {
                 RankOrValue::Rank(r) => ScoreValue::Score(r.local_score()),
                 RankOrValue::Sort(s) => ScoreValue::Sort(s),
+                RankOrValue::GeoSort(g) => ScoreValue::GeoSort(g),
+                RankOrValue::Random(r) => ScoreValue::Random(r),
+                RankOrValue::Expression(e) => ScoreValue::Expression(e),
                 RankOrValue::Score(s) => ScoreValue::Score(s),
             })
     }
@@ -82,6 +119,10 @@ impl ScoreDetails {
             ScoreDetails::Attribute(f) => RankOrValue::Rank(f.rank()),
             ScoreDetails::Exactness(e) => RankOrValue::Rank(e.rank()),
             ScoreDetails::Sort(sort) => RankOrValue::Sort(sort),
+            ScoreDetails::GeoSort(geosort) => RankOrValue::GeoSort(geosort),
+            ScoreDetails::Random(random) => RankOrValue::Random(random),
+            ScoreDetails::Expression(expression) => RankOrValue::Expression(expression),
+            ScoreDetails::Decay(decay) => RankOrValue::Score(decay.score),
             ScoreDetails::Vector(vector) => {
                 RankOrValue::Score(vector.similarity.as_ref().map(|s| *s as f64).unwrap_or(0.0f64))
             }
@@ -126,12 +167,15 @@ impl ScoreDetails {
                     order += 1;
                 }
                 ScoreDetails::Attribute(attribute) => {
-                    let fid_details = serde_json::json!({
+                    let mut fid_details = serde_json::json!({
                         "order": order,
                         "attribute": attribute.attribute,
                         "maxAttribute": attribute.max_attribute,
                         "score": attribute.rank().local_score(),
                     });
+                    if let Some(weight) = attribute.weight {
+                        fid_details["weight"] = weight.into();
+                    }
                     details_map.insert("attribute".into(), fid_details);
                     order += 1;
                 }
@@ -155,16 +199,62 @@ impl ScoreDetails {
                     details_map.insert(sort, sort_details);
                     order += 1;
                 }
+                ScoreDetails::GeoSort(details) => {
+                    let sort = format!(
+                        "_geoPoint({}, {}):{}",
+                        details.target_point[0],
+                        details.target_point[1],
+                        if details.ascending { "asc" } else { "desc" }
+                    );
+                    let value = details.value;
+                    let distance = details.distance();
+                    let sort_details = serde_json::json!({
+                        "order": order,
+                        "value": value,
+                        "distance": distance,
+                    });
+                    details_map.insert(sort, sort_details);
+                    order += 1;
+                }
+                ScoreDetails::Random(details) => {
+                    let sort = format!("_random({}):{}", details.seed, if details.ascending { "asc" } else { "desc" });
+                    let random_details = serde_json::json!({
+                        "order": order,
+                        "value": details.value,
+                    });
+                    details_map.insert(sort, random_details);
+                    order += 1;
+                }
                 ScoreDetails::Vector(s) => {
                     let similarity = s.similarity.as_ref();
 
-                    let details = serde_json::json!({
+                    let mut details = serde_json::json!({
                         "order": order,
                         "similarity": similarity,
                     });
+                    if let Some(best_vector) = s.best_vector {
+                        details["bestVector"] = best_vector.into();
+                    }
                     details_map.insert("vectorSort".into(), details);
                     order += 1;
                 }
+                ScoreDetails::Expression(details) => {
+                    let expression_details = serde_json::json!({
+                        "order": order,
+                        "value": details.value,
+                    });
+                    details_map.insert(format!("expression({})", details.expression), expression_details);
+                    order += 1;
+                }
+                ScoreDetails::Decay(details) => {
+                    let decay_details = serde_json::json!({
+                        "order": order,
+                        "value": details.value,
+                        "score": details.score,
+                    });
+                    details_map.insert(format!("decay({})", details.field_name), decay_details);
+                    order += 1;
+                }
             }
         }
         details_map
@@ -173,7 +263,7 @@ impl ScoreDetails {
 
 
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Words {
     pub matching_words: u32,
     pub max_matching_words: u32,
@@ -193,7 +283,7 @@ impl Words {
 ///
 /// In exactness, the number of matching words can actually be 0 with a non-zero score,
 /// if no words from the query appear exactly in the document.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct ExactWords {
     pub exact_words: u32,
     pub max_exact_words: u32,
@@ -215,7 +305,7 @@ impl ExactWords {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Proximity {
     pub proximity: u32,
     pub max_proximity: u32,
@@ -237,7 +327,7 @@ impl Proximity {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Typo {
     pub typo_count: u32,
     pub max_typo_count: u32,
@@ -259,10 +349,13 @@ impl Typo {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Attribute {
     pub attribute: u32,
     pub max_attribute: u32,
+    /// The sum of the weights of the attributes matched by each query word, the greatest one
+    /// among the matches of the bucket, only present when the searchable attributes have weights.
+    pub weight: Option<u32>,
 }
 
 impl Attribute {
@@ -274,6 +367,7 @@ impl Attribute {
         Self {
             attribute: rank.rank.saturating_sub(1),
             max_attribute: rank.max_rank.saturating_sub(1),
+            weight: None,
         }
     }
 }
@@ -318,7 +412,7 @@ impl Rank {
 }
 
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Sort {
     pub field_name: String,
     pub ascending: bool,
@@ -359,8 +453,122 @@ impl PartialOrd for Sort {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeoSort {
+    pub target_point: [f64; 2],
+    pub ascending: bool,
+    pub value: Option<[f64; 2]>,
+}
+
+impl PartialOrd for GeoSort {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.target_point != other.target_point {
+            return None;
+        }
+        if self.ascending != other.ascending {
+            return None;
+        }
+        Some(match (self.distance(), other.distance()) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(left), Some(right)) => {
+                let order = left.partial_cmp(&right)?;
+                if self.ascending {
+                    // when ascending, the one with the smallest distance has the best score
+                    order.reverse()
+                } else {
+                    order
+                }
+            }
+        })
+    }
+}
+
+impl GeoSort {
+    /// Returns the distance in meters between the target point and the point of the document.
+    pub fn distance(&self) -> Option<f64> {
+        self.value.map(|value| distance_between_two_points(&self.target_point, &value))
+    }
+}
+
+/// The pseudo-random value drawn for a document from the seed of a random sort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Random {
+    pub seed: u64,
+    pub ascending: bool,
+    pub value: u64,
+}
+
+impl PartialOrd for Random {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.seed != other.seed || self.ascending != other.ascending {
+            return None;
+        }
+        let order = self.value.cmp(&other.value);
+        // when ascending, the smallest value is returned first
+        Some(if self.ascending { order.reverse() } else { order })
+    }
+}
+
+/// The value of a ranking expression for a document, `None` when it isn't a number.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Expression {
+    pub expression: String,
+    pub value: Option<f64>,
+}
+
+impl PartialOrd for Expression {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.expression != other.expression {
+            return None;
+        }
+        match (self.value, other.value) {
+            (None, None) => Some(Ordering::Equal),
+            (None, Some(_)) => Some(Ordering::Less),
+            (Some(_), None) => Some(Ordering::Greater),
+            // the greatest values are returned first
+            (Some(left), Some(right)) => left.partial_cmp(&right),
+        }
+    }
+}
+
+/// The score given by a decay function to the value of a field.
+///
+/// `value` is the value closest to the origin among the values with this score,
+/// it is `None` when the document has no value for the field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Decay {
+    pub field_name: String,
+    pub value: Option<f64>,
+    pub score: f64,
+}
+
+impl PartialOrd for Decay {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.field_name != other.field_name {
+            return None;
+        }
+        match self.score.partial_cmp(&other.score)? {
+            // the documents without value come after the others
+            Ordering::Equal => Some(self.value.is_some().cmp(&other.value.is_some())),
+            order => Some(order),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Vector {
     pub similarity: Option<f32>,
+    /// The index of the vector of the document the most similar to the query.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_vector: Option<u8>,
+}
+
+impl PartialOrd for Vector {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        // the vector that matched doesn't change the rank of the document
+        self.similarity.partial_cmp(&other.similarity)
+    }
 }
 
@@ -1,21 +1,24 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::num::NonZeroUsize;
-use arroy::distances::Angular;
-use arroy::{ItemId, Reader};
+use arroy::ItemId;
 use fst::Set;
 use heed::RoTxn;
 use roaring::RoaringBitmap;
+use rstar::RTree;
 
 use crate::search::search::SearchContext;
-use crate::{Criterion, FieldId, FieldsIdsMap, Result, TermsMatchingStrategy, UserError};
+use heed::types::Bytes;
+use crate::{Criterion, DocumentId, FieldId, FieldsIdsMap, GeoPoint, Result, TermsMatchingStrategy, UserError};
 use crate::heed_codec::BytesRefCodec;
-use crate::heed_codec::facet::FacetGroupKeyCodec;
+use crate::heed_codec::facet::{FacetGroupKey, FacetGroupKeyCodec, FacetGroupValueCodec, FieldDocIdFacetF64Codec, FieldDocIdFacetStringCodec};
 use crate::search::facet::{ascending_facet_sort, AscendingSortIter, descending_facet_sort, DescendingSortIter};
 use crate::search::query_graph::QueryGraph;
 use crate::search::utils::bit_set::BitSet;
 use crate::update::split_config::SplitJoinConfig;
 use crate::update::typo_config::TypoConfig;
+use crate::vector::distance::VectorReader;
+use crate::vector::{EmbeddingConfig, VectorDistance, VectorQuantization};
 
 pub type Fid = u16;
 pub type Position = u16;
@@ -30,6 +33,7 @@ pub trait Context<'t> {
     fn typo_config(&self) -> Result<TypoConfig>;
     fn exact_words(&mut self) -> Result<Set<Cow<[u8]>>>;
     fn searchable_fields_ids(&mut self) -> Result<Option<Vec<FieldId>>>;
+    fn searchable_attribute_weights(&mut self) -> Result<HashMap<FieldId, u16>>;
     fn word_pair_frequency(
         &mut self,
         left_word: &str,
@@ -57,11 +61,15 @@ pub trait Context<'t> {
     fn phrase_docids(&mut self, path: &[String]) -> Result<&RoaringBitmap>;
     fn split_docids(&mut self, path: &(String, String)) -> Result<&RoaringBitmap>;
     fn embedder_category_id(&self, embedder_name: &str) -> Result<u8>;
-    fn vector_reader(&self, index: u16) -> arroy::Result<Reader<Angular>>;
+    fn vector_reader(&self, index: u16, distance: VectorDistance, quantization: Option<VectorQuantization>) -> arroy::Result<VectorReader>;
+    fn embedding_config(&self, embedder_name: &str) -> Result<Option<EmbeddingConfig>>;
     fn ascending_number_sort<'a>(&self, tnx: &'a RoTxn<'a>, fid: Fid, candidates: RoaringBitmap) -> Result<AscendingSortIter<'a>>;
     fn ascending_string_sort<'a>(&self, tnx: &'a RoTxn<'a>, fid: Fid, candidates: RoaringBitmap) -> Result<AscendingSortIter<'a>>;
     fn descending_number_sort<'a>(&self, tnx: &'a RoTxn<'a>, fid: Fid, candidates: RoaringBitmap) -> Result<DescendingSortIter<'a>>;
     fn txn(&self) -> &'t RoTxn<'t>;
+    fn distinct_docids(&mut self, fid: Fid, docid: DocumentId) -> Result<RoaringBitmap>;
+    fn distinct_universe(&mut self, fid: Fid, candidates: &RoaringBitmap) -> Result<RoaringBitmap>;
+    fn geo_rtree(&self) -> Result<Option<RTree<GeoPoint>>>;
     fn descending_string_sort<'a>(&self, tnx: &'a RoTxn<'a>, fid: Fid, candidates: RoaringBitmap) -> Result<DescendingSortIter<'a>>;
 }
 
@@ -106,6 +114,10 @@ impl<'t> Context<'t> for SearchContext<'t> {
         self.index.searchable_fields_ids(self.txn)
     }
 
+    fn searchable_attribute_weights(&mut self) -> Result<HashMap<FieldId, u16>> {
+        self.index.searchable_attribute_weights_ids(self.txn)
+    }
+
     fn word_pair_frequency(&mut self, left_word: &str, right_word: &str, proximity: u8) -> Result<u64> {
         self.get_db_word_pair_proximity_docids_len(left_word,right_word,proximity).map(Option::unwrap_or_default)
     }
@@ -185,8 +197,16 @@ impl<'t> Context<'t> for SearchContext<'t> {
             .ok_or_else(|| UserError::InvalidEmbedder(embedder_name.to_owned()).into())
     }
 
-    fn vector_reader(&self, index: u16) -> arroy::Result<Reader<Angular>> {
-        arroy::Reader::open(self.txn, index, self.index.vector_arroy)
+    fn vector_reader(&self, index: u16, distance: VectorDistance, quantization: Option<VectorQuantization>) -> arroy::Result<VectorReader> {
+        VectorReader::open(self.txn, index, self.index.vector_arroy, self.index.vector_quantized, distance, quantization)
+    }
+
+    fn embedding_config(&self, embedder_name: &str) -> Result<Option<EmbeddingConfig>> {
+        Ok(self
+            .index
+            .embedding_configs(self.txn)?
+            .into_iter()
+            .find_map(|(name, config)| (name == embedder_name).then_some(config)))
     }
 
 
@@ -224,6 +244,71 @@ impl<'t> Context<'t> for SearchContext<'t> {
         self.txn
     }
 
+    fn distinct_docids(&mut self, fid: Fid, docid: DocumentId) -> Result<RoaringBitmap> {
+        let mut key_buffer: Vec<_> = fid.to_be_bytes().to_vec();
+        key_buffer.extend_from_slice(&docid.to_be_bytes());
+
+        let mut docids = RoaringBitmap::new();
+        let numbers = self.index.field_id_docid_facet_f64s
+            .remap_key_type::<Bytes>()
+            .prefix_iter(self.txn, &key_buffer)?
+            .remap_key_type::<FieldDocIdFacetF64Codec>();
+        for result in numbers {
+            let ((_, _, value), ()) = result?;
+            let key = FacetGroupKey { field_id: fid, level: 0, left_bound: value };
+            if let Some(group) = self.index.facet_id_f64_docids.get(self.txn, &key)? {
+                docids |= group.bitmap;
+            }
+        }
+
+        let strings = self.index.field_id_docid_facet_strings
+            .remap_key_type::<Bytes>()
+            .prefix_iter(self.txn, &key_buffer)?
+            .remap_key_type::<FieldDocIdFacetStringCodec>();
+        for result in strings {
+            let ((_, _, normalized_value), _) = result?;
+            let key = FacetGroupKey { field_id: fid, level: 0, left_bound: normalized_value };
+            if let Some(group) = self.index.facet_id_string_docids.get(self.txn, &key)? {
+                docids |= group.bitmap;
+            }
+        }
+
+        docids.insert(docid);
+        Ok(docids)
+    }
+
+    /// Keeps a single document of the candidates for each value of the distinct field,
+    /// the documents without a value are all kept.
+    fn distinct_universe(&mut self, fid: Fid, candidates: &RoaringBitmap) -> Result<RoaringBitmap> {
+        let mut level_0_prefix: Vec<_> = fid.to_be_bytes().to_vec();
+        level_0_prefix.push(0);
+
+        let numbers = self.index.facet_id_f64_docids
+            .remap_types::<Bytes, Bytes>()
+            .prefix_iter(self.txn, &level_0_prefix)?
+            .remap_types::<FacetGroupKeyCodec<BytesRefCodec>, FacetGroupValueCodec>();
+        let strings = self.index.facet_id_string_docids
+            .remap_types::<Bytes, Bytes>()
+            .prefix_iter(self.txn, &level_0_prefix)?
+            .remap_types::<FacetGroupKeyCodec<BytesRefCodec>, FacetGroupValueCodec>();
+
+        let mut universe = candidates.clone();
+        for result in numbers.chain(strings) {
+            let (_, group) = result?;
+            let mut docids = group.bitmap & &universe;
+            if let Some(first) = docids.min() {
+                docids.remove(first);
+                universe -= docids;
+            }
+        }
+
+        Ok(universe)
+    }
+
+    fn geo_rtree(&self) -> Result<Option<RTree<GeoPoint>>> {
+        self.index.geo_rtree(self.txn)
+    }
+
     fn descending_string_sort<'a>(&self, tnx: &'a RoTxn<'a>, fid: Fid, candidates: RoaringBitmap) -> Result<DescendingSortIter<'a>> {
         let number_db = self.index.facet_id_string_docids.remap_key_type::<FacetGroupKeyCodec<BytesRefCodec>>();
         Ok(descending_facet_sort(
@@ -0,0 +1,209 @@
+use std::ops::Range;
+
+use analyzer::analyzer::BoxAnalyzer;
+use heed::RoTxn;
+use roaring::RoaringBitmap;
+
+use crate::score_details::ScoreDetails;
+use crate::update::curation_rules::QueryMatch;
+use crate::{DocumentId, Index, Result};
+
+/// The documents pinned and hidden by the curation rules matching the query of a search.
+#[derive(Debug, Default)]
+pub struct Curation {
+    /// The pinned documents sorted by position, a position is never pinned twice.
+    pinned: Vec<(u64, DocumentId)>,
+    hidden: RoaringBitmap,
+}
+
+impl Curation {
+    /// Gathers the rules matching the query, the pinned documents that aren't part
+    /// of the universe or that are hidden are ignored.
+    pub fn new(
+        index: &Index,
+        rtxn: &RoTxn,
+        query: Option<&str>,
+        analyzer: &Option<String>,
+        universe: &RoaringBitmap,
+    ) -> Result<Self> {
+        let mut curation = Curation::default();
+        let Some(query) = query else {
+            return Ok(curation);
+        };
+        let rules = index.curation_rules(rtxn)?;
+        if rules.is_empty() {
+            return Ok(curation);
+        }
+
+        let analyzer = index.analyzer(rtxn, analyzer)?;
+        let normalized_query = normalize(&analyzer, query);
+        let external_documents_ids = index.external_documents_ids();
+        let mut pinned = Vec::new();
+        for rule in rules {
+            let matches = match rule.query_match {
+                QueryMatch::Exact => rule.query == query,
+                QueryMatch::Normalized => normalize(&analyzer, &rule.query) == normalized_query,
+            };
+            if !matches {
+                continue;
+            }
+
+            for id in &rule.hidden {
+                if let Some(docid) = external_documents_ids.get(rtxn, id)? {
+                    curation.hidden.insert(docid);
+                }
+            }
+            for document in &rule.pinned {
+                if let Some(docid) = external_documents_ids.get(rtxn, &document.id)? {
+                    pinned.push((document.position as u64, docid));
+                }
+            }
+        }
+
+        // the first rule pinning a document or a position wins
+        let mut seen = RoaringBitmap::new();
+        pinned.sort_by_key(|(position, _)| *position);
+        pinned.dedup_by_key(|(position, _)| *position);
+        curation.pinned = pinned
+            .into_iter()
+            .filter(|(_, docid)| {
+                universe.contains(*docid) && !curation.hidden.contains(*docid) && seen.insert(*docid)
+            })
+            .collect();
+
+        Ok(curation)
+    }
+
+    /// The documents that must be removed from the universe of the ranked search.
+    pub fn curated_documents(&self) -> RoaringBitmap {
+        let mut documents = self.hidden.clone();
+        documents.extend(self.pinned.iter().map(|(_, docid)| *docid));
+        documents
+    }
+
+    pub fn pinned_documents(&self) -> RoaringBitmap {
+        self.pinned.iter().map(|(_, docid)| *docid).collect()
+    }
+
+    /// Returns the number of documents pinned in the given range of positions.
+    pub fn pinned_count(&self, positions: Range<u64>) -> u64 {
+        self.pinned.iter().filter(|(position, _)| positions.contains(position)).count() as u64
+    }
+
+    /// Inserts the pinned documents at their positions among the ranked documents of the page
+    /// starting at the position `start`, the pinned documents have no score details.
+    ///
+    /// When there are not enough ranked documents to reach the position of a pinned document,
+    /// it follows the last document of the page.
+    pub fn merge(
+        &self,
+        start: u64,
+        limit: u64,
+        documents_ids: Vec<DocumentId>,
+        document_scores: Vec<Vec<ScoreDetails>>,
+    ) -> (Vec<DocumentId>, Vec<Vec<ScoreDetails>>) {
+        let mut pinned = self
+            .pinned
+            .iter()
+            .filter(|(position, _)| (start..start.saturating_add(limit)).contains(position))
+            .peekable();
+        let mut ranked = documents_ids.into_iter().zip(document_scores);
+
+        let mut output = Vec::with_capacity(limit.min(self.pinned.len() as u64 + ranked.len() as u64) as usize);
+        let mut position = start;
+        loop {
+            match pinned.next_if(|(pinned_position, _)| *pinned_position <= position) {
+                Some((_, docid)) => output.push((*docid, Vec::new())),
+                None => match ranked.next() {
+                    Some(hit) => output.push(hit),
+                    None => {
+                        output.extend(pinned.map(|(_, docid)| (*docid, Vec::new())));
+                        break;
+                    }
+                },
+            }
+            position += 1;
+        }
+
+        output.into_iter().unzip()
+    }
+}
+
+/// Returns the words of the text once normalized by the analyzer.
+fn normalize(analyzer: &BoxAnalyzer, text: &str) -> Vec<String> {
+    analyzer
+        .analyze(text)
+        .as_iter()
+        .filter_map(|token| (token.is_word() && !token.text.is_empty()).then(|| token.text.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
+    use crate::index::tests::TempIndex;
+    use crate::search::facet::Filter;
+    use crate::update::curation_rules::{CurationRule, PinnedDocument, QueryMatch};
+    use crate::{Search, SearchResult};
+
+    #[test]
+    fn search_with_curation_rules() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_curation_rules(vec![
+                    CurationRule {
+                        query: S("hello"),
+                        query_match: QueryMatch::Exact,
+                        pinned: vec![
+                            PinnedDocument { id: S("3"), position: 0 },
+                            PinnedDocument { id: S("5"), position: 2 },
+                        ],
+                        hidden: vec![S("1")],
+                    },
+                    CurationRule {
+                        query: S("world hello"),
+                        query_match: QueryMatch::Normalized,
+                        pinned: vec![PinnedDocument { id: S("6"), position: 1 }],
+                        hidden: vec![S("0")],
+                    },
+                ]);
+            })
+            .unwrap();
+        let documents: Vec<_> =
+            (0..7).map(|id| serde_json::json!({ "id": id, "name": "hello world" })).collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let search = |query: &str, offset: u64, limit: u64| {
+            let SearchResult { documents_ids, .. } =
+                Search::new(&rtxn, &index).query(query).offset(offset).limit(limit).execute().unwrap();
+            documents_ids
+        };
+
+        assert_eq!(search("hello", 0, 20), [3, 0, 5, 2, 4, 6]);
+        assert_eq!(search("hello", 1, 2), [0, 5]);
+        assert_eq!(search("hello", 3, 20), [2, 4, 6]);
+        assert_eq!(search("World  HELLO", 0, 20), [1, 6, 2, 3, 4, 5]);
+        assert_eq!(search("hello world", 0, 20), [0, 1, 2, 3, 4, 5, 6]);
+
+        // the pinned documents can't escape the filter
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("id") });
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_json(&serde_json::json!({ "id": 5 })).unwrap().unwrap();
+        let SearchResult { documents_ids, .. } =
+            Search::new(&rtxn, &index).query("hello").filter(filter).execute().unwrap();
+        assert_eq!(documents_ids, [5]);
+
+        index.update_settings(|settings| settings.reset_curation_rules()).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.curation_rules(&rtxn).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::score_details::ScoreDetails;
+use crate::{DocumentId, Error, UserError};
+
+/// The position of the last hit of a search, used to fetch the hits that follow it
+/// without sorting again the buckets returned before it.
+///
+/// It is meant to be given back as is to [`crate::Search::after`], either with its serde
+/// representation or with its opaque string representation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchCursor {
+    /// The document id of the last hit.
+    pub(crate) docid: DocumentId,
+    /// The scores of the last hit, one per ranking rule.
+    pub(crate) scores: Vec<ScoreDetails>,
+    /// The number of hits returned up to and including the last hit.
+    pub(crate) position: u64,
+}
+
+impl SearchCursor {
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+impl fmt::Display for SearchCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = serde_json::to_vec(self).map_err(|_| fmt::Error)?;
+        for byte in bytes {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SearchCursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() % 2 != 0 || !s.is_ascii() {
+            return Err(UserError::InvalidSearchCursor.into());
+        }
+        let bytes = (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| UserError::InvalidSearchCursor)?;
+
+        serde_json::from_slice(&bytes).map_err(|_| UserError::InvalidSearchCursor.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::score_details::{Sort, Words};
+    use crate::Search;
+
+    #[test]
+    fn cursor_string_roundtrip() {
+        let cursor = SearchCursor {
+            docid: 42,
+            scores: vec![
+                ScoreDetails::Words(Words { matching_words: 2, max_matching_words: 3 }),
+                ScoreDetails::Sort(Sort {
+                    field_name: "price".to_string(),
+                    ascending: true,
+                    value: serde_json::json!(12.5),
+                }),
+            ],
+            position: 20,
+        };
+
+        let encoded = cursor.to_string();
+        assert_eq!(encoded.parse::<SearchCursor>().unwrap(), cursor);
+        assert!("not a cursor".parse::<SearchCursor>().is_err());
+    }
+
+    #[test]
+    fn search_after_cursor() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "hello world" },
+                { "id": 1, "name": "hello" },
+                { "id": 2, "name": "world hello" },
+                { "id": 3, "name": "hello there world" },
+                { "id": 4, "name": "hello again" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let all = Search::new(&rtxn, &index).query("hello world").limit(4).execute().unwrap();
+
+        let first_page = Search::new(&rtxn, &index).query("hello world").limit(2).execute().unwrap();
+        assert_eq!(first_page.documents_ids, all.documents_ids[..2]);
+        let cursor = first_page.cursor.unwrap();
+        assert_eq!(cursor.position(), 2);
+
+        let cursor = cursor.to_string().parse().unwrap();
+        let second_page =
+            Search::new(&rtxn, &index).query("hello world").after(cursor).limit(2).execute().unwrap();
+        assert_eq!(second_page.documents_ids, all.documents_ids[2..]);
+    }
+}
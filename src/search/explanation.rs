@@ -0,0 +1,132 @@
+use std::num::NonZeroUsize;
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::score_details::ScoreDetails;
+use crate::search::context::Context;
+use crate::search::query_graph::{NodeData, QueryGraph};
+use crate::search::query_parser::TermKind;
+use crate::vector::VectorQuantization;
+
+/// A structured description of how a search has been resolved, meant to be serialized
+/// to debug the relevancy of the returned documents.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryExplanation {
+    /// The nodes of the query graph, a node is identified by its position in this list.
+    pub nodes: Vec<NodeExplanation>,
+    /// The ranking rules in the order they have been applied.
+    pub ranking_rules: Vec<RankingRuleExplanation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeExplanation {
+    pub kind: NodeKind,
+    pub successors: Vec<usize>,
+    /// The number of documents matching the term, `None` for the start and end nodes.
+    pub docids_count: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum NodeKind {
+    Start,
+    Term { term_kind: TermKind, is_negative: bool, position: RangeInclusive<usize> },
+    End,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankingRuleExplanation {
+    pub id: String,
+    /// The buckets returned by the ranking rule, in the order they have been visited.
+    pub buckets: Vec<BucketExplanation>,
+    /// The strategies of the nearest neighbours searches of a vector sort, in the order they have been run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vector_strategies: Vec<VectorSearchStrategy>,
+}
+
+/// How a vector sort looked for the nearest documents among its candidates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum VectorSearchStrategy {
+    /// The distances to the vectors of every candidate have been computed.
+    Exact { candidates: u64 },
+    /// The nearest documents have been looked for in the arroy trees,
+    /// or among the quantized vectors of the candidates when the embedder is quantized.
+    Approximate { candidates: u64, search_k: Option<NonZeroUsize>, quantization: Option<VectorQuantization> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BucketExplanation {
+    pub score: ScoreDetails,
+    /// The number of documents of the bucket that were not already returned.
+    pub candidates: u64,
+}
+
+impl QueryExplanation {
+    pub(crate) fn new(
+        ctx: &mut impl Context,
+        graph: &QueryGraph,
+        ranking_rules: Vec<RankingRuleExplanation>,
+    ) -> Result<Self> {
+        let mut nodes = Vec::with_capacity(graph.nodes.len());
+        for (node_id, node) in graph.nodes.iter().enumerate() {
+            let (kind, docids_count) = match &node.data {
+                NodeData::Start => (NodeKind::Start, None),
+                NodeData::End => (NodeKind::End, None),
+                NodeData::Term(term) => {
+                    let docids_count = ctx.node_docids(node_id, graph)?.len();
+                    let kind = NodeKind::Term {
+                        term_kind: term.term_kind.clone(),
+                        is_negative: term.is_negative,
+                        position: term.position.clone(),
+                    };
+                    (kind, Some(docids_count))
+                }
+            };
+            nodes.push(NodeExplanation {
+                kind,
+                successors: node.successors.iter().collect(),
+                docids_count,
+            });
+        }
+
+        Ok(QueryExplanation { nodes, ranking_rules })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::index::tests::TempIndex;
+    use crate::{Search, SearchResult};
+
+    #[test]
+    fn search_explanation() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "hello world" },
+                { "id": 1, "name": "hello" },
+                { "id": 2, "name": "world" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { explanation, .. } =
+            Search::new(&rtxn, &index).query("hello world").explain(true).execute().unwrap();
+        let explanation = explanation.unwrap();
+
+        let ids: Vec<_> = explanation.ranking_rules.iter().map(|rule| rule.id.as_str()).collect();
+        assert_eq!(ids, ["words", "typo", "proximity", "attribute", "exactness"]);
+        assert!(!explanation.ranking_rules[0].buckets.is_empty());
+        assert!(explanation.nodes.iter().any(|node| node.docids_count == Some(2)));
+        let json = serde_json::to_value(&explanation).unwrap();
+        assert_eq!(json["nodes"][0]["kind"]["type"], "Start");
+
+        let SearchResult { explanation, .. } =
+            Search::new(&rtxn, &index).query("hello world").execute().unwrap();
+        assert!(explanation.is_none());
+    }
+}
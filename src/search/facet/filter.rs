@@ -7,6 +7,7 @@ use std::str::FromStr;
 use either::Either;
 use super::condition::Condition;
 use roaring::RoaringBitmap;
+use rstar::AABB;
 use query_lang::query::ast::{AndOperator, EqOperator, ExistsOperator, FieldOperator, InOperator, IsEmptyOperator, LeafValue, NotOperator, Operator, OrOperator, Predicate, Value};
 use query_lang::query::ParseError;
 
@@ -69,6 +70,43 @@ impl<'a> Display for FilterError<'a> {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum BadGeoError {
+    Lat(f64),
+    Lng(f64),
+    BoundingBoxTopIsBelowBottom(f64, f64),
+    PolygonTooFewPoints(usize),
+}
+
+impl std::error::Error for BadGeoError {}
+
+impl Display for BadGeoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BoundingBoxTopIsBelowBottom(top, bottom) => {
+                write!(f, "Верхняя широта `{top}` находится ниже нижней широты `{bottom}`.")
+            }
+            Self::PolygonTooFewPoints(count) => {
+                write!(f, "Многоугольник должен содержать как минимум 3 точки, получено `{count}`.")
+            }
+            Self::Lat(lat) => write!(
+                f,
+                "Неверная широта `{lat}`. Широта должна находиться в диапазоне от -90 до 90 градусов."
+            ),
+            Self::Lng(lng) => write!(
+                f,
+                "Неверная долгота `{lng}`. Долгота должна находиться в диапазоне от -180 до 180 градусов."
+            ),
+        }
+    }
+}
+
+impl From<BadGeoError> for Error {
+    fn from(error: BadGeoError) -> Self {
+        Self::UserError(UserError::InvalidFilter(error.to_string()))
+    }
+}
+
 impl<'a> From<ParseError> for Error {
     fn from(error: ParseError) -> Self {
         Self::UserError(UserError::InvalidFilter(error.to_string()))
@@ -192,6 +230,60 @@ impl Filter {
         Self::inner_evaluate(self.condition.clone(), rtxn, index, &filterable_fields, "")
     }
 
+    /// Checks that every field of the filter is filterable, without evaluating it.
+    pub fn check_filterable_fields(&self, filterable_fields: &HashSet<String>) -> Result<()> {
+        Self::check_predicate(&self.condition, filterable_fields, "")
+    }
+
+    fn check_predicate(predicate: &Predicate, filterable_fields: &HashSet<String>, field: &str) -> Result<()> {
+        match predicate {
+            Predicate::Leaf(_) => Self::check_field(field, filterable_fields),
+            Predicate::Operators(operators) => operators
+                .iter()
+                .try_for_each(|operator| Self::check_operator(operator, filterable_fields, field)),
+        }
+    }
+
+    fn check_operator(operator: &Operator, filterable_fields: &HashSet<String>, field: &str) -> Result<()> {
+        match operator {
+            Operator::Field(FieldOperator{ field: field_path, .. }) if field.is_empty() && is_geo_operator(&field_path.to_string()) => {
+                if filterable_fields.contains("_geo") {
+                    Ok(())
+                } else {
+                    Err(Error::UserError(UserError::InvalidFilter(FilterError::AttributeNotFilterable {
+                        attribute: "_geo",
+                        filterable_fields: filterable_fields.clone(),
+                    }.to_string())))
+                }
+            }
+            Operator::Field(FieldOperator{ field: field_path, predicate }) => {
+                let field = if field.is_empty(){
+                    field_path.to_string()
+                } else {
+                    format!("{field}.{field_path}")
+                };
+
+                Self::check_predicate(predicate, filterable_fields, &field)
+            }
+            Operator::Not(NotOperator(predicate)) => Self::check_predicate(predicate, filterable_fields, field),
+            Operator::Or(OrOperator(predicates)) | Operator::And(AndOperator(predicates)) => predicates
+                .iter()
+                .try_for_each(|predicate| Self::check_predicate(predicate, filterable_fields, field)),
+            _ => Self::check_field(field, filterable_fields),
+        }
+    }
+
+    fn check_field(field: &str, filterable_fields: &HashSet<String>) -> Result<()> {
+        if crate::is_faceted(field, filterable_fields) {
+            Ok(())
+        } else {
+            Err(Error::UserError(UserError::InvalidFilter(FilterError::AttributeNotFilterable {
+                attribute: field,
+                filterable_fields: filterable_fields.clone(),
+            }.to_string())))
+        }
+    }
+
     fn evaluate_condition(
         rtxn: &heed::RoTxn,
         index: &Index,
@@ -349,6 +441,16 @@ impl Filter {
         field: &str,
     ) -> Result<RoaringBitmap> {
         match operator {
+            Operator::Field(FieldOperator{ field: field_path, predicate }) if field.is_empty() && is_geo_operator(&field_path.to_string()) => {
+                match predicate {
+                    Predicate::Leaf(LeafValue(value)) => {
+                        Self::evaluate_geo(&field_path.to_string(), value.into(), rtxn, index, filterable_fields)
+                    }
+                    _ => Err(Error::UserError(UserError::InvalidFilter(format!(
+                        "Оператор `{field_path}` ожидает массив координат."
+                    )))),
+                }
+            }
             Operator::Field(FieldOperator{ field: field_path, predicate }) => {
                 let field = if field.is_empty(){
                     field_path.to_string()
@@ -467,6 +569,111 @@ impl Filter {
         }
     }
 
+    /// Evaluates a geo operator, `value` contains its arguments:
+    /// - `_geoRadius`: `[lat, lng, distance in meters]`,
+    /// - `_geoBoundingBox`: `[[top right lat, top right lng], [bottom left lat, bottom left lng]]`,
+    /// - `_geoPolygon`: `[[lat, lng], [lat, lng], [lat, lng], ...]`, the polygon is implicitly closed.
+    fn evaluate_geo(
+        operator: &str,
+        value: serde_json::Value,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        filterable_fields: &HashSet<String>,
+    ) -> Result<RoaringBitmap> {
+        if !filterable_fields.contains("_geo") {
+            return Err(Error::UserError(UserError::InvalidFilter(FilterError::AttributeNotFilterable {
+                attribute: "_geo",
+                filterable_fields: filterable_fields.clone(),
+            }.to_string())));
+        }
+
+        match operator {
+            "_geoRadius" => {
+                let Some([lat, lng, radius]) = parse_numbers(&value) else {
+                    return Err(Error::UserError(UserError::InvalidFilterExpression(&["[Number, Number, Number]"], value)));
+                };
+                let base_point = validate_geo_point([lat, lng])?;
+                let rtree = match index.geo_rtree(rtxn)? {
+                    Some(rtree) => rtree,
+                    None => return Ok(RoaringBitmap::new()),
+                };
+
+                let xyz_base_point = lat_lng_to_xyz(&base_point);
+                let result = rtree
+                    .nearest_neighbor_iter(&xyz_base_point)
+                    .take_while(|point| {
+                        distance_between_two_points(&base_point, &point.data.1) <= radius + f64::EPSILON
+                    })
+                    .map(|point| point.data.0)
+                    .collect();
+
+                Ok(result)
+            }
+            "_geoBoundingBox" => {
+                let points = value
+                    .as_array()
+                    .filter(|points| points.len() == 2)
+                    .and_then(|points| Some([parse_numbers(&points[0])?, parse_numbers(&points[1])?]));
+                let Some([top_right, bottom_left]) = points else {
+                    return Err(Error::UserError(UserError::InvalidFilterExpression(&["[[Number, Number], [Number, Number]]"], value)));
+                };
+                let top_right = validate_geo_point(top_right)?;
+                let bottom_left = validate_geo_point(bottom_left)?;
+                if top_right[0] < bottom_left[0] {
+                    return Err(BadGeoError::BoundingBoxTopIsBelowBottom(top_right[0], bottom_left[0]).into());
+                }
+
+                let field_ids_map = index.fields_ids_map(rtxn)?;
+                let (Some(lat_fid), Some(lng_fid)) = (field_ids_map.id("_geo.lat"), field_ids_map.id("_geo.lng")) else {
+                    return Ok(RoaringBitmap::new());
+                };
+                let numbers_db = index.facet_id_f64_docids;
+
+                let mut selected_lat = RoaringBitmap::new();
+                Self::explore_facet_number_levels(rtxn, numbers_db, lat_fid, Included(bottom_left[0]), Included(top_right[0]), &mut selected_lat)?;
+
+                let mut selected_lng = RoaringBitmap::new();
+                if top_right[1] < bottom_left[1] {
+                    // The bounding box is wrapping around the earth (going from 180 to -180),
+                    // we select both sides of the antimeridian.
+                    Self::explore_facet_number_levels(rtxn, numbers_db, lng_fid, Included(bottom_left[1]), Included(180.0), &mut selected_lng)?;
+                    Self::explore_facet_number_levels(rtxn, numbers_db, lng_fid, Included(-180.0), Included(top_right[1]), &mut selected_lng)?;
+                } else {
+                    Self::explore_facet_number_levels(rtxn, numbers_db, lng_fid, Included(bottom_left[1]), Included(top_right[1]), &mut selected_lng)?;
+                }
+
+                Ok(selected_lat & selected_lng & index.geo_faceted_documents_ids(rtxn)?)
+            }
+            "_geoPolygon" => {
+                let points: Option<Vec<[f64; 2]>> = value
+                    .as_array()
+                    .and_then(|points| points.iter().map(parse_numbers).collect());
+                let Some(points) = points else {
+                    return Err(Error::UserError(UserError::InvalidFilterExpression(&["[[Number, Number], ...]"], value)));
+                };
+                if points.len() < 3 {
+                    return Err(BadGeoError::PolygonTooFewPoints(points.len()).into());
+                }
+                let points = points.into_iter().map(validate_geo_point).collect::<Result<Vec<_>>>()?;
+                let polygon = GeoPolygon::new(points);
+                let rtree = match index.geo_rtree(rtxn)? {
+                    Some(rtree) => rtree,
+                    None => return Ok(RoaringBitmap::new()),
+                };
+
+                // the envelope only narrows the candidates, the exact test is done on each point
+                let result = rtree
+                    .locate_in_envelope_intersecting(&polygon.envelope())
+                    .filter(|point| polygon.contains(point.data.1))
+                    .map(|point| point.data.0)
+                    .collect();
+
+                Ok(result)
+            }
+            _ => unreachable!(),
+        }
+    }
+
     fn inner_evaluate(
         predicate: Predicate,
         rtxn: &heed::RoTxn,
@@ -515,6 +722,132 @@ impl Filter {
     }
 }
 
+/// Returns `true` if the field name is one of the geo operators.
+fn is_geo_operator(name: &str) -> bool {
+    matches!(name, "_geoRadius" | "_geoBoundingBox" | "_geoPolygon")
+}
+
+const ENVELOPE_MARGIN: f64 = 1e-9;
+
+/// A polygon whose longitudes are unwrapped: every edge takes the shortest way between
+/// its two vertices, an edge crossing the antimeridian makes the following longitudes
+/// go beyond 180 or below -180 degrees.
+struct GeoPolygon {
+    points: Vec<[f64; 2]>,
+}
+
+impl GeoPolygon {
+    fn new(mut points: Vec<[f64; 2]>) -> Self {
+        for i in 1..points.len() {
+            let previous = points[i - 1][1];
+            let lng = &mut points[i][1];
+            while *lng - previous > 180.0 {
+                *lng -= 360.0;
+            }
+            while *lng - previous < -180.0 {
+                *lng += 360.0;
+            }
+        }
+        Self { points }
+    }
+
+    fn lat_lng_bounds(&self) -> ([f64; 2], [f64; 2]) {
+        let mut min = [f64::INFINITY; 2];
+        let mut max = [f64::NEG_INFINITY; 2];
+        for point in &self.points {
+            for i in 0..2 {
+                min[i] = min[i].min(point[i]);
+                max[i] = max[i].max(point[i]);
+            }
+        }
+        (min, max)
+    }
+
+    /// Returns the cartesian envelope containing every point of the sphere between
+    /// the latitude and longitude bounds of the polygon.
+    fn envelope(&self) -> AABB<[f64; 3]> {
+        let ([min_lat, min_lng], [max_lat, max_lng]) = self.lat_lng_bounds();
+        if max_lng - min_lng >= 360.0 {
+            return AABB::from_corners(
+                [-1.0, -1.0, min_lat.to_radians().sin() - ENVELOPE_MARGIN],
+                [1.0, 1.0, max_lat.to_radians().sin() + ENVELOPE_MARGIN],
+            );
+        }
+        let lat_cos = cos_range(min_lat, max_lat);
+        let lng_cos = cos_range(min_lng, max_lng);
+        let lng_sin = cos_range(min_lng - 90.0, max_lng - 90.0);
+        let [min_x, max_x] = multiply_ranges(lat_cos, lng_cos);
+        let [min_y, max_y] = multiply_ranges(lat_cos, lng_sin);
+
+        // the envelope is slightly enlarged to not lose the points on its border to rounding errors
+        AABB::from_corners(
+            [min_x - ENVELOPE_MARGIN, min_y - ENVELOPE_MARGIN, min_lat.to_radians().sin() - ENVELOPE_MARGIN],
+            [max_x + ENVELOPE_MARGIN, max_y + ENVELOPE_MARGIN, max_lat.to_radians().sin() + ENVELOPE_MARGIN],
+        )
+    }
+
+    /// Ray casting test of the point against the polygon, the point is tested with
+    /// its longitude shifted on each side of the antimeridian.
+    fn contains(&self, [lat, lng]: [f64; 2]) -> bool {
+        [lng, lng - 360.0, lng + 360.0].into_iter().any(|lng| {
+            let mut inside = false;
+            let mut previous = self.points[self.points.len() - 1];
+            for &point in &self.points {
+                let ([lat_a, lng_a], [lat_b, lng_b]) = (previous, point);
+                if (lat_a > lat) != (lat_b > lat)
+                    && lng < (lng_b - lng_a) * (lat - lat_a) / (lat_b - lat_a) + lng_a
+                {
+                    inside = !inside;
+                }
+                previous = point;
+            }
+            inside
+        })
+    }
+}
+
+/// Returns the range of the cosine over an interval of degrees.
+fn cos_range(min: f64, max: f64) -> [f64; 2] {
+    let (cos_min, cos_max) = (min.to_radians().cos(), max.to_radians().cos());
+    let mut range = [cos_min.min(cos_max), cos_min.max(cos_max)];
+    // the interval contains a multiple of 360 degrees: the cosine reaches 1
+    if (min / 360.0).ceil() <= (max / 360.0).floor() {
+        range[1] = 1.0;
+    }
+    // the interval contains an odd multiple of 180 degrees: the cosine reaches -1
+    if ((min - 180.0) / 360.0).ceil() <= ((max - 180.0) / 360.0).floor() {
+        range[0] = -1.0;
+    }
+    range
+}
+
+fn multiply_ranges([a_min, a_max]: [f64; 2], [b_min, b_max]: [f64; 2]) -> [f64; 2] {
+    let products = [a_min * b_min, a_min * b_max, a_max * b_min, a_max * b_max];
+    let min = products.into_iter().fold(f64::INFINITY, f64::min);
+    let max = products.into_iter().fold(f64::NEG_INFINITY, f64::max);
+    [min, max]
+}
+
+/// Reads an array of `N` finite numbers.
+fn parse_numbers<const N: usize>(value: &serde_json::Value) -> Option<[f64; N]> {
+    let values = value.as_array().filter(|values| values.len() == N)?;
+    let mut numbers = [0.0; N];
+    for (number, value) in numbers.iter_mut().zip(values) {
+        *number = value.as_f64().filter(|number| number.is_finite())?;
+    }
+    Some(numbers)
+}
+
+fn validate_geo_point([lat, lng]: [f64; 2]) -> Result<[f64; 2]> {
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err(BadGeoError::Lat(lat).into());
+    }
+    if !(-180.0..=180.0).contains(&lng) {
+        return Err(BadGeoError::Lng(lng).into());
+    }
+    Ok([lat, lng])
+}
+
 impl From<Predicate> for Filter {
     fn from(predicate: Predicate) -> Self {
         Self { condition: predicate }
@@ -999,3 +1332,54 @@ impl From<Predicate> for Filter {
 //         assert_eq!(result, RoaringBitmap::from_iter((0..100).filter(|x| x % 10 != 0)));
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
+    use crate::error::Error;
+    use crate::index::tests::TempIndex;
+    use crate::search::facet::Filter;
+    use crate::{Search, SearchResult, UserError};
+
+    #[test]
+    fn search_with_geo_polygon() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("_geo") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "paris", "_geo": { "lat": 48.8566, "lng": 2.3522 } },
+                { "id": 1, "name": "lyon", "_geo": { "lat": 45.764, "lng": 4.8357 } },
+                { "id": 2, "name": "suva", "_geo": { "lat": -18.1416, "lng": 178.4419 } },
+                { "id": 3, "name": "apia", "_geo": { "lat": -13.8507, "lng": -171.7514 } },
+                { "id": 4, "name": "nowhere" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_json(&serde_json::json!({ "_geoPolygon": [[50.0, 0.0], [50.0, 4.0], [47.0, 4.0], [47.0, 0.0]] }))
+            .unwrap()
+            .unwrap();
+        let SearchResult { documents_ids, .. } = Search::new(&rtxn, &index).filter(filter).execute().unwrap();
+        assert_eq!(documents_ids, [0]);
+
+        // the polygon is crossing the antimeridian
+        let filter = Filter::from_json(&serde_json::json!({
+            "_geoPolygon": [[-10.0, 170.0], [-10.0, -170.0], [-20.0, -170.0], [-20.0, 170.0]]
+        }))
+            .unwrap()
+            .unwrap();
+        let SearchResult { documents_ids, .. } = Search::new(&rtxn, &index).filter(filter).execute().unwrap();
+        assert_eq!(documents_ids, [2, 3]);
+
+        let error = Filter::from_json(&serde_json::json!({ "_geoPolygon": [[50.0, 0.0], [50.0, 4.0]] }))
+            .and_then(|filter| Search::new(&rtxn, &index).filter(filter.unwrap()).execute());
+        assert!(matches!(error, Err(Error::UserError(UserError::InvalidFilter(_)))));
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::score_details::ScoreDetails;
+use crate::search::matches::{FormatOptions, MatchBounds, MatcherBuilder};
+use crate::{all_obkv_to_json, is_faceted_by, DocumentId, Index, Object, Result, Search, SearchResult};
+
+const DEFAULT_CROP_LENGTH: usize = 10;
+
+/// The attributes to return and to format for each hit of a search.
+///
+/// An attribute selects its nested fields too, `*` selects every field.
+#[derive(Debug, Clone)]
+pub struct DocumentsOptions {
+    /// The attributes of the returned documents, every attribute when `None`.
+    pub attributes_to_retrieve: Option<Vec<String>>,
+    pub attributes_to_highlight: Vec<String>,
+    /// The attributes to crop with their own crop length, `None` uses `crop_length`.
+    pub attributes_to_crop: Vec<(String, Option<usize>)>,
+    /// The number of words kept around the matches of a cropped attribute.
+    pub crop_length: usize,
+    /// The number of crop windows kept in a cropped attribute, joined with the crop marker.
+    pub fragments: Option<usize>,
+    pub crop_marker: Option<String>,
+    pub highlight_pre_tag: Option<String>,
+    pub highlight_post_tag: Option<String>,
+    /// Whether the positions of the matches are returned in `_matchesPosition`.
+    pub show_matches_position: bool,
+}
+
+impl Default for DocumentsOptions {
+    fn default() -> Self {
+        Self {
+            attributes_to_retrieve: None,
+            attributes_to_highlight: Vec::new(),
+            attributes_to_crop: Vec::new(),
+            crop_length: DEFAULT_CROP_LENGTH,
+            fragments: None,
+            crop_marker: None,
+            highlight_pre_tag: None,
+            highlight_post_tag: None,
+            show_matches_position: false,
+        }
+    }
+}
+
+impl DocumentsOptions {
+    /// Returns how the field at the given path must be formatted.
+    fn format_options(&self, path: &str) -> FormatOptions {
+        let selects = |attribute: &str| attribute == "*" || is_faceted_by(path, attribute);
+        let highlight = self.attributes_to_highlight.iter().any(|attribute| selects(attribute));
+        let crop = self
+            .attributes_to_crop
+            .iter()
+            .find(|(attribute, _)| selects(attribute))
+            .map(|(_, length)| length.unwrap_or(self.crop_length));
+
+        FormatOptions { highlight, crop, fragments: self.fragments }
+    }
+
+    fn should_format(&self) -> bool {
+        !self.attributes_to_highlight.is_empty() || !self.attributes_to_crop.is_empty()
+    }
+
+    /// Returns the attributes of the formatted documents, the retrieved attributes with the
+    /// highlighted and cropped ones, every attribute when `None`.
+    fn attributes_to_format(&self) -> Option<Vec<String>> {
+        let attributes = self.attributes_to_retrieve.as_ref()?;
+        let mut attributes = attributes.clone();
+        attributes.extend(self.attributes_to_highlight.iter().cloned());
+        attributes.extend(self.attributes_to_crop.iter().map(|(attribute, _)| attribute.clone()));
+        Some(attributes)
+    }
+}
+
+/// A document returned by a search with its formatted version and the positions of its matches.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    #[serde(skip)]
+    pub docid: DocumentId,
+    #[serde(flatten)]
+    pub document: Object,
+    #[serde(rename = "_formatted", skip_serializing_if = "Object::is_empty")]
+    pub formatted: Object,
+    #[serde(rename = "_matchesPosition", skip_serializing_if = "BTreeMap::is_empty")]
+    pub matches_position: BTreeMap<String, Vec<MatchBounds>>,
+    #[serde(skip)]
+    pub score: Vec<ScoreDetails>,
+}
+
+impl<'a> Search<'a> {
+    /// Executes the search and returns the documents of the hits, formatted according to the options.
+    pub fn execute_with_documents(&self, options: &DocumentsOptions) -> Result<(SearchResult, Vec<SearchHit>)> {
+        let result = self.execute()?;
+        let hits = hydrate_hits(self.index, self.rtxn, &self.analyzer, &result, options)?;
+        Ok((result, hits))
+    }
+}
+
+/// Fetches the documents of the hits of a search and formats them.
+pub(crate) fn hydrate_hits(
+    index: &Index,
+    rtxn: &heed::RoTxn,
+    analyzer: &Option<String>,
+    result: &SearchResult,
+    options: &DocumentsOptions,
+) -> Result<Vec<SearchHit>> {
+    let fields_ids_map = index.fields_ids_map(rtxn)?;
+    let analyzer = index.analyzer(rtxn, analyzer)?;
+    let mut builder = MatcherBuilder::new(result.matching_words.clone(), analyzer);
+    if let Some(marker) = &options.crop_marker {
+        builder.crop_marker(marker.clone());
+    }
+    if let Some(tag) = &options.highlight_pre_tag {
+        builder.highlight_prefix(tag.clone());
+    }
+    if let Some(tag) = &options.highlight_post_tag {
+        builder.highlight_suffix(tag.clone());
+    }
+
+    let documents = index.documents(rtxn, result.documents_ids.iter().copied())?;
+    let mut hits = Vec::with_capacity(documents.len());
+    for ((docid, obkv), score) in documents.into_iter().zip(&result.document_scores) {
+        let all_fields = all_obkv_to_json(obkv, &fields_ids_map)?;
+
+        let mut formatted = Object::new();
+        let mut matches_position = BTreeMap::new();
+        if options.should_format() || options.show_matches_position {
+            // the highlighted and cropped attributes are formatted even when they aren't retrieved
+            let to_format = match options.attributes_to_format() {
+                Some(attributes) => select_fields(all_fields.clone(), "", &attributes),
+                None => all_fields.clone(),
+            };
+            for (key, value) in &to_format {
+                let value = format_value(&builder, options, key, value, &mut matches_position);
+                formatted.insert(key.clone(), value);
+            }
+        }
+        let document = match &options.attributes_to_retrieve {
+            Some(attributes) => select_fields(all_fields, "", attributes),
+            None => all_fields,
+        };
+        if !options.should_format() {
+            formatted.clear();
+        }
+        if !options.show_matches_position {
+            matches_position.clear();
+        }
+
+        hits.push(SearchHit { docid, document, formatted, matches_position, score: score.clone() });
+    }
+
+    Ok(hits)
+}
+
+/// Keeps the fields of the object selected by one of the attributes,
+/// the objects nested in an unselected field are traversed to find the selected fields.
+fn select_fields(object: Object, path: &str, attributes: &[String]) -> Object {
+    object
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let path = if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+            if attributes.iter().any(|attribute| attribute == "*" || is_faceted_by(&path, attribute)) {
+                return Some((key, value));
+            }
+            if !attributes.iter().any(|attribute| is_faceted_by(attribute, &path)) {
+                return None;
+            }
+            match value {
+                Value::Object(object) => Some((key, Value::Object(select_fields(object, &path, attributes)))),
+                Value::Array(values) => {
+                    let values = values
+                        .into_iter()
+                        .filter_map(|value| match value {
+                            Value::Object(object) => Some(Value::Object(select_fields(object, &path, attributes))),
+                            _ => None,
+                        })
+                        .collect();
+                    Some((key, Value::Array(values)))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Formats the strings of the value at the given path, the nested objects and arrays are traversed.
+///
+/// Only the highlighted and cropped strings are formatted, the other ones are kept as is.
+/// The matches of every string are gathered under the path of the string.
+fn format_value(
+    builder: &MatcherBuilder,
+    options: &DocumentsOptions,
+    path: &str,
+    value: &Value,
+    matches_position: &mut BTreeMap<String, Vec<MatchBounds>>,
+) -> Value {
+    match value {
+        Value::String(text) => {
+            let format_options = options.format_options(path);
+            if !format_options.should_format() && !options.show_matches_position {
+                return value.clone();
+            }
+            let mut matcher = builder.build(text);
+            if options.show_matches_position {
+                let matches = matcher.matches();
+                if !matches.is_empty() {
+                    matches_position.entry(path.to_string()).or_default().extend(matches);
+                }
+            }
+            if format_options.should_format() {
+                Value::String(matcher.format(format_options).into_owned())
+            } else {
+                value.clone()
+            }
+        }
+        Value::Array(values) => Value::Array(
+            values.iter().map(|value| format_value(builder, options, path, value, matches_position)).collect(),
+        ),
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| {
+                    let path = format!("{path}.{key}");
+                    (key.clone(), format_value(builder, options, &path, value, matches_position))
+                })
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use crate::index::tests::TempIndex;
+    use crate::{DocumentsOptions, MatchBounds, Search, SearchResult};
+
+    #[test]
+    fn search_with_formatted_documents() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                {
+                    "id": 0,
+                    "title": "the quick brown fox",
+                    "details": { "tags": ["fox", "dog"], "note": "a lazy dog sleeps" },
+                },
+                { "id": 1, "title": "an unrelated document" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let options = DocumentsOptions {
+            attributes_to_retrieve: Some(vec![S("title"), S("details")]),
+            attributes_to_highlight: vec![S("title"), S("details.tags")],
+            attributes_to_crop: vec![(S("details.note"), Some(2))],
+            show_matches_position: true,
+            ..Default::default()
+        };
+        let (SearchResult { documents_ids, .. }, hits) =
+            Search::new(&rtxn, &index).query("fox").execute_with_documents(&options).unwrap();
+        assert_eq!(documents_ids, [0]);
+        assert_eq!(hits.len(), 1);
+
+        let hit = &hits[0];
+        assert_eq!(hit.docid, 0);
+        assert!(!hit.document.contains_key("id"));
+        assert_eq!(hit.formatted["title"], "the quick brown <em>fox</em>");
+        assert_eq!(hit.formatted["details"]["tags"], serde_json::json!(["<em>fox</em>", "dog"]));
+        let note = hit.formatted["details"]["note"].as_str().unwrap();
+        assert!(note.starts_with("a lazy") && note.ends_with('…'));
+        assert_eq!(hit.matches_position["title"], [MatchBounds { start: 16, length: 3 }]);
+        assert_eq!(hit.matches_position["details.tags"], [MatchBounds { start: 0, length: 3 }]);
+
+        let hit = serde_json::to_value(hit).unwrap();
+        assert_eq!(hit["title"], "the quick brown fox");
+        assert!(hit["_formatted"].is_object());
+        assert!(hit["_matchesPosition"].is_object());
+
+        // the highlighted attributes are formatted even when they aren't retrieved,
+        // the retrieved attributes that aren't highlighted nor cropped are kept as is.
+        let options = DocumentsOptions {
+            attributes_to_retrieve: Some(vec![S("details")]),
+            attributes_to_highlight: vec![S("title")],
+            ..Default::default()
+        };
+        let (_, hits) = Search::new(&rtxn, &index).query("fox").execute_with_documents(&options).unwrap();
+        let hit = &hits[0];
+        assert!(!hit.document.contains_key("title"));
+        assert_eq!(hit.formatted["title"], "the quick brown <em>fox</em>");
+        assert_eq!(hit.formatted["details"]["tags"], serde_json::json!(["fox", "dog"]));
+        assert!(!hit.formatted.contains_key("id"));
+        assert!(hit.matches_position.is_empty());
+    }
+}
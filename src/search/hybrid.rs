@@ -1,21 +1,68 @@
 use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 
 use itertools::Itertools;
 use roaring::RoaringBitmap;
 
-use crate::score_details::{ScoreDetails, ScoreValue};
-use crate::search::SemanticSearch;
-use crate::{MatchingWords, Result, Search, SearchResult};
+use crate::score_details::{self, ScoreDetails, ScoreValue};
+use crate::search::search::{execute_search, execute_vector_search, SearchContext};
+use crate::search::{QueryExplanation, SemanticSearch};
+use crate::vector::Embedder;
+use crate::{MatchingWords, Result, Search, SearchResult, UserError};
 
 struct ScoreWithRatioResult {
     matching_words: MatchingWords,
     candidates: RoaringBitmap,
     document_scores: Vec<(u32, ScoreWithRatio)>,
-    query_graph: Option<String>
+    query_graph: Option<String>,
+    explanation: Option<QueryExplanation>,
+    degraded: bool,
+    fired_rules: Vec<String>,
+    suggested_query: Option<String>,
 }
 
 type ScoreWithRatio = (Vec<ScoreDetails>, f32);
 
+/// How the hits of the keyword search and of the semantic search of a hybrid search are merged.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HybridMergeStrategy {
+    /// The scores of the hits, weighted by the semantic ratio, are compared.
+    #[default]
+    Ratio,
+    /// The hits are sorted by the sum of their reciprocal ranks in each search, weighted by the semantic ratio.
+    ///
+    /// A greater `k` reduces the advantage of the first ranked hits.
+    ReciprocalRankFusion { k: u32 },
+}
+
+/// How a hit of a hybrid search was ranked.
+#[derive(Debug, Clone)]
+pub struct HybridHit {
+    /// The score details of the keyword search, `None` when the document doesn't match the query.
+    pub keyword_scores: Option<Vec<ScoreDetails>>,
+    /// The score details of the semantic search, `None` when the document has no vector.
+    pub semantic_scores: Option<Vec<ScoreDetails>>,
+    /// The searches that found the document among their hits before the merge,
+    /// the missing scores are computed afterwards.
+    pub source: HybridHitSource,
+    /// The score the hits were merged by, the weighted ranking score or the reciprocal rank fusion score.
+    pub fused_score: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HybridHitSource {
+    Keyword,
+    Semantic,
+    Both,
+}
+
+#[derive(Clone, Copy)]
+enum ResultSource {
+    Semantic,
+    Keyword,
+}
+
 fn compare_scores(
     &(ref left_scores, left_ratio): &ScoreWithRatio,
     &(ref right_scores, right_ratio): &ScoreWithRatio,
@@ -45,6 +92,29 @@ fn compare_scores(
                     order => return order,
                 }
             }
+            (Some(ScoreValue::GeoSort(left)), Some(ScoreValue::GeoSort(right))) => {
+                match left.partial_cmp(right).unwrap_or(Ordering::Equal) {
+                    Ordering::Equal => continue,
+                    order => return order,
+                }
+            }
+            (Some(ScoreValue::Random(left)), Some(ScoreValue::Random(right))) => {
+                match left.partial_cmp(right).unwrap_or(Ordering::Equal) {
+                    Ordering::Equal => continue,
+                    order => return order,
+                }
+            }
+            (Some(ScoreValue::Expression(left)), Some(ScoreValue::Expression(right))) => {
+                match left.partial_cmp(right).unwrap_or(Ordering::Equal) {
+                    Ordering::Equal => continue,
+                    order => return order,
+                }
+            }
+            // values of different ranking rules can't be compared
+            (
+                Some(ScoreValue::Sort(_) | ScoreValue::GeoSort(_) | ScoreValue::Random(_) | ScoreValue::Expression(_)),
+                Some(ScoreValue::Sort(_) | ScoreValue::GeoSort(_) | ScoreValue::Random(_) | ScoreValue::Expression(_)),
+            ) => continue,
             (Some(ScoreValue::Score(x)), Some(_)) => {
                 return if x == 0. { Ordering::Less } else { Ordering::Greater }
             }
@@ -67,58 +137,64 @@ impl ScoreWithRatioResult {
             matching_words: results.matching_words,
             candidates: results.candidates,
             document_scores,
-            query_graph: results.query_graph
+            query_graph: results.query_graph,
+            explanation: results.explanation,
+            degraded: results.degraded,
+            fired_rules: results.fired_rules,
+            suggested_query: results.suggested_query,
         }
     }
 
     fn merge(
-        vector_results: Self,
-        keyword_results: Self,
+        mut vector_results: Self,
+        mut keyword_results: Self,
         from: u64,
         length: u64,
+        ranking_score_threshold: Option<f64>,
+        strategy: HybridMergeStrategy,
     ) -> (SearchResult, u32) {
-        #[derive(Clone, Copy)]
-        enum ResultSource {
-            Semantic,
-            Keyword,
-        }
-        let mut semantic_hit_count = 0;
+        let vector_hits = std::mem::take(&mut vector_results.document_scores);
+        let keyword_hits = std::mem::take(&mut keyword_results.document_scores);
+        let vector_scores: HashMap<_, _> =
+            vector_hits.iter().map(|(docid, (scores, _))| (*docid, scores.clone())).collect();
+        let keyword_scores: HashMap<_, _> =
+            keyword_hits.iter().map(|(docid, (scores, _))| (*docid, scores.clone())).collect();
 
-        let mut documents_ids = Vec::with_capacity(
-            vector_results.document_scores.len() + keyword_results.document_scores.len(),
-        );
-        let mut document_scores = Vec::with_capacity(
-            vector_results.document_scores.len() + keyword_results.document_scores.len(),
-        );
+        // remove documents whose own score is below the threshold
+        let above_threshold = |(_, (main_score, _)): &(u32, ScoreWithRatio)| {
+            ranking_score_threshold.map_or(true, |threshold| {
+                ScoreDetails::global_score(main_score.iter()) >= threshold
+            })
+        };
+        let vector_hits: Vec<_> = vector_hits.into_iter().filter(above_threshold).collect();
+        let keyword_hits: Vec<_> = keyword_hits.into_iter().filter(above_threshold).collect();
 
-        let mut documents_seen = RoaringBitmap::new();
-        for ((docid, (main_score, _sub_score)), source) in vector_results
-            .document_scores
-            .into_iter()
-            .zip(std::iter::repeat(ResultSource::Semantic))
-            .merge_by(
-                keyword_results
-                    .document_scores
-                    .into_iter()
-                    .zip(std::iter::repeat(ResultSource::Keyword)),
-                |((_, left), _), ((_, right), _)| {
-                    // the first value is the one with the greatest score
-                    compare_scores(left, right).is_ge()
-                },
-            )
-            // remove documents we already saw
-            .filter(|((docid, _), _)| documents_seen.insert(*docid))
-            // start skipping **after** the filter
-            .skip(from as usize)
-            // take **after** skipping
-            .take(length as usize)
-        {
+        let hits = match strategy {
+            HybridMergeStrategy::Ratio => Self::merge_by_ratio(vector_hits, keyword_hits),
+            HybridMergeStrategy::ReciprocalRankFusion { k } => {
+                Self::merge_by_rank_fusion(vector_hits, keyword_hits, k)
+            }
+        };
+
+        let mut semantic_hit_count = 0;
+        let mut documents_ids = Vec::with_capacity(length as usize);
+        let mut document_scores = Vec::with_capacity(length as usize);
+        let mut hybrid_hits = Vec::with_capacity(length as usize);
+        for (docid, scores, source, fused_score) in hits.into_iter().skip(from as usize).take(length as usize) {
             if let ResultSource::Semantic = source {
                 semantic_hit_count += 1;
             }
             documents_ids.push(docid);
-            // TODO: pass both scores to documents_score in some way?
-            document_scores.push(main_score);
+            document_scores.push(scores);
+
+            let keyword_scores = keyword_scores.get(&docid).cloned();
+            let semantic_scores = vector_scores.get(&docid).cloned();
+            let source = match (&keyword_scores, &semantic_scores) {
+                (Some(_), Some(_)) => HybridHitSource::Both,
+                (Some(_), None) => HybridHitSource::Keyword,
+                (None, _) => HybridHitSource::Semantic,
+            };
+            hybrid_hits.push(HybridHit { keyword_scores, semantic_scores, source, fused_score });
         }
 
         (
@@ -128,45 +204,148 @@ impl ScoreWithRatioResult {
                 documents_ids,
                 document_scores,
                 query_graph: keyword_results.query_graph.or(vector_results.query_graph),
+                explanation: keyword_results.explanation.or(vector_results.explanation),
+                degraded: vector_results.degraded | keyword_results.degraded,
+                cursor: None,
+                fired_rules: keyword_results.fired_rules,
+                suggested_query: keyword_results.suggested_query,
+                merge_strategy: Some(strategy),
+                hybrid_hits,
             },
             semantic_hit_count,
         )
     }
+
+    /// Merges the hits by comparing their scores weighted by the ratio of their search,
+    /// a document found by both searches keeps its best hit.
+    ///
+    /// The fused score of a hit is its ranking score weighted by the ratio of its search.
+    fn merge_by_ratio(
+        vector_hits: Vec<(u32, ScoreWithRatio)>,
+        keyword_hits: Vec<(u32, ScoreWithRatio)>,
+    ) -> Vec<(u32, Vec<ScoreDetails>, ResultSource, f64)> {
+        let mut documents_seen = RoaringBitmap::new();
+        vector_hits
+            .into_iter()
+            .zip(std::iter::repeat(ResultSource::Semantic))
+            .merge_by(
+                keyword_hits.into_iter().zip(std::iter::repeat(ResultSource::Keyword)),
+                |((_, left), _), ((_, right), _)| {
+                    // the first value is the one with the greatest score
+                    compare_scores(left, right).is_ge()
+                },
+            )
+            // remove documents we already saw
+            .filter(|((docid, _), _)| documents_seen.insert(*docid))
+            .map(|((docid, (main_score, ratio)), source)| {
+                let fused_score = ScoreDetails::global_score(main_score.iter()) * ratio as f64;
+                (docid, main_score, source, fused_score)
+            })
+            .collect()
+    }
+
+    /// Merges the hits by reciprocal rank fusion: a document scores `ratio / (k + rank)` for each search
+    /// it is found by, its rank starting at 1, and the documents are sorted by the sum of their scores.
+    ///
+    /// A document found by both searches keeps the score details of its best ranked hit.
+    fn merge_by_rank_fusion(
+        vector_hits: Vec<(u32, ScoreWithRatio)>,
+        keyword_hits: Vec<(u32, ScoreWithRatio)>,
+        k: u32,
+    ) -> Vec<(u32, Vec<ScoreDetails>, ResultSource, f64)> {
+        struct FusedHit {
+            fused_score: f64,
+            rank: usize,
+            scores: Vec<ScoreDetails>,
+            source: ResultSource,
+        }
+
+        let mut fused_hits: HashMap<u32, FusedHit> = HashMap::new();
+        for (hits, source) in [(keyword_hits, ResultSource::Keyword), (vector_hits, ResultSource::Semantic)] {
+            for (rank, (docid, (scores, ratio))) in hits.into_iter().enumerate() {
+                let fused_score = ratio as f64 / (k as f64 + rank as f64 + 1.0);
+                match fused_hits.entry(docid) {
+                    Entry::Occupied(mut entry) => {
+                        let hit = entry.get_mut();
+                        hit.fused_score += fused_score;
+                        if rank < hit.rank {
+                            *hit = FusedHit { fused_score: hit.fused_score, rank, scores, source };
+                        }
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(FusedHit { fused_score, rank, scores, source });
+                    }
+                }
+            }
+        }
+
+        let mut fused_hits: Vec<_> = fused_hits.into_iter().collect();
+        fused_hits.sort_by(|(left_docid, left), (right_docid, right)| {
+            right
+                .fused_score
+                .total_cmp(&left.fused_score)
+                .then(left.rank.cmp(&right.rank))
+                .then(left_docid.cmp(right_docid))
+        });
+        fused_hits.into_iter().map(|(docid, hit)| (docid, hit.scores, hit.source, hit.fused_score)).collect()
+    }
 }
 
 impl<'a> Search<'a> {
     pub fn execute_hybrid(&self, semantic_ratio: f32) -> Result<(SearchResult, Option<u32>)> {
+        // the merged hits don't follow the order of a single bucket sort
+        if self.after.is_some() {
+            return Err(UserError::InvalidSearchCursor.into());
+        }
 
         let mut search = Search {
             query: self.query.clone(),
             filter: self.filter.clone(),
             offset: 0,
             limit: self.limit + self.offset,
+            after: None,
             sort_criteria: self.sort_criteria.clone(),
+            distinct: self.distinct.clone(),
             analyzer: self.analyzer.clone(),
             searchable_attributes: self.searchable_attributes,
             terms_matching_strategy: self.terms_matching_strategy,
             output_query_graph: self.output_query_graph,
+            explain: self.explain,
+            ranking_score_threshold: self.ranking_score_threshold,
+            hybrid_merge_strategy: self.hybrid_merge_strategy,
+            did_you_mean: self.did_you_mean,
+            vector_search_options: self.vector_search_options,
+            time_budget: self.time_budget.clone(),
             rtxn: self.rtxn,
             index: self.index,
             semantic: self.semantic.clone(),
         };
 
         let semantic = search.semantic.take();
-        let keyword_results = search.execute()?;
+        let mut keyword_results = search.execute()?;
+        // the hits of the keyword search are scored by the semantic search when a vector is given
+        let given_vector = semantic.as_ref().and_then(|SemanticSearch { vector, embedder_name, embedder }| {
+            Some((vector.as_deref()?, embedder_name.as_str(), embedder.as_ref()))
+        });
 
         // completely skip semantic search if the results of the keyword search are good enough
         if self.results_good_enough(&keyword_results, semantic_ratio) {
-            return Ok((keyword_results, Some(0)));
+            return Ok((self.keyword_hits_only(keyword_results, semantic_ratio, given_vector)?, Some(0)));
+        }
+
+        // no time left for the semantic search, the keyword results are returned as is
+        if search.time_budget.exceeded() {
+            keyword_results.degraded = true;
+            return Ok((self.keyword_hits_only(keyword_results, semantic_ratio, None)?, Some(0)));
         }
 
         // no vector search against placeholder search
         let Some(query) = search.query.take() else {
-            return Ok((keyword_results, Some(0)));
+            return Ok((self.keyword_hits_only(keyword_results, semantic_ratio, given_vector)?, Some(0)));
         };
         // no embedder, no semantic search
         let Some(SemanticSearch { vector, embedder_name, embedder }) = semantic else {
-            return Ok((keyword_results, Some(0)));
+            return Ok((self.keyword_hits_only(keyword_results, semantic_ratio, None)?, Some(0)));
         };
 
         let vector_query = match vector {
@@ -177,14 +356,17 @@ impl<'a> Search<'a> {
                     Ok(embedding) => embedding,
                     Err(error) => {
                         tracing::error!(error=%error, "Embedding failed");
-                        return Ok((keyword_results, Some(0)));
+                        return Ok((self.keyword_hits_only(keyword_results, semantic_ratio, None)?, Some(0)));
                     }
                 }
             }
         };
 
-        search.semantic =
-            Some(SemanticSearch { vector: Some(vector_query), embedder_name, embedder });
+        search.semantic = Some(SemanticSearch {
+            vector: Some(vector_query.clone()),
+            embedder_name: embedder_name.clone(),
+            embedder: embedder.clone(),
+        });
 
         // TODO: would be better to have two distinct functions at this point
         let vector_results = search.execute()?;
@@ -192,12 +374,137 @@ impl<'a> Search<'a> {
         let keyword_results = ScoreWithRatioResult::new(keyword_results, 1.0 - semantic_ratio);
         let vector_results = ScoreWithRatioResult::new(vector_results, semantic_ratio);
 
-        let (merge_results, semantic_hit_count) =
-            ScoreWithRatioResult::merge(vector_results, keyword_results, self.offset, self.limit);
+        let (mut merge_results, semantic_hit_count) =
+            ScoreWithRatioResult::merge(
+                vector_results,
+                keyword_results,
+                self.offset,
+                self.limit,
+                self.ranking_score_threshold,
+                self.hybrid_merge_strategy,
+            );
         assert!((merge_results.documents_ids.len() as u64) <= self.limit);
+        self.fill_missing_scores(&vector_query, &embedder_name, &embedder, &mut merge_results)?;
         Ok((merge_results, Some(semantic_hit_count)))
     }
 
+    /// Returns the requested page of the keyword search alone, merged as if the semantic search had no hits,
+    /// the hits are scored by the semantic search when a vector is given.
+    fn keyword_hits_only(
+        &self,
+        keyword_results: SearchResult,
+        semantic_ratio: f32,
+        vector: Option<(&[f32], &str, &Embedder)>,
+    ) -> Result<SearchResult> {
+        let keyword_results = ScoreWithRatioResult::new(keyword_results, 1.0 - semantic_ratio);
+        let vector_results = ScoreWithRatioResult::new(SearchResult::default(), semantic_ratio);
+
+        let (mut result, _) = ScoreWithRatioResult::merge(
+            vector_results,
+            keyword_results,
+            self.offset,
+            self.limit,
+            self.ranking_score_threshold,
+            self.hybrid_merge_strategy,
+        );
+        if let Some((vector, embedder_name, embedder)) = vector {
+            self.fill_missing_scores(vector, embedder_name, embedder, &mut result)?;
+        }
+        Ok(result)
+    }
+
+    /// Scores the hits found by a single search with the other search, the hits that don't
+    /// match the query or that have no vector keep a missing score.
+    fn fill_missing_scores(
+        &self,
+        vector: &[f32],
+        embedder_name: &str,
+        embedder: &Embedder,
+        result: &mut SearchResult,
+    ) -> Result<()> {
+        let missing = |has_scores: fn(&HybridHit) -> bool| -> RoaringBitmap {
+            result
+                .documents_ids
+                .iter()
+                .zip(&result.hybrid_hits)
+                .filter(|(_, hit)| !has_scores(hit))
+                .map(|(docid, _)| *docid)
+                .collect()
+        };
+        let keyword_missing = missing(|hit| hit.keyword_scores.is_some());
+        let semantic_missing = missing(|hit| hit.semantic_scores.is_some());
+
+        let mut keyword_scores = HashMap::new();
+        if !keyword_missing.is_empty() {
+            let limit = keyword_missing.len();
+            let mut ctx = SearchContext::new(self.index, self.rtxn, self.terms_matching_strategy);
+            if let Some(searchable_attributes) = self.searchable_attributes {
+                ctx.searchable_attributes(searchable_attributes)?;
+            }
+            let results = execute_search(
+                &mut ctx,
+                &self.query,
+                keyword_missing,
+                &self.sort_criteria,
+                None,
+                &self.analyzer,
+                0,
+                limit,
+                None,
+                None,
+                &self.time_budget,
+                false,
+            )?;
+            keyword_scores.extend(results.documents_ids.into_iter().zip(results.document_scores));
+        }
+
+        let mut semantic_scores = HashMap::new();
+        if !semantic_missing.is_empty() {
+            let limit = semantic_missing.len();
+            let mut ctx = SearchContext::new(self.index, self.rtxn, self.terms_matching_strategy);
+            let results = execute_vector_search(
+                &mut ctx,
+                vector,
+                semantic_missing,
+                &self.sort_criteria,
+                None,
+                0,
+                limit,
+                embedder_name,
+                embedder,
+                self.vector_search_options,
+                None,
+                None,
+                &self.time_budget,
+                false,
+            )?;
+            // the documents without vector are ranked last without similarity
+            let has_similarity = |scores: &Vec<ScoreDetails>| {
+                scores.iter().any(|details| {
+                    matches!(details, ScoreDetails::Vector(score_details::Vector { similarity: Some(_), .. }))
+                })
+            };
+            semantic_scores.extend(
+                results
+                    .documents_ids
+                    .into_iter()
+                    .zip(results.document_scores)
+                    .filter(|(_, scores)| has_similarity(scores)),
+            );
+        }
+
+        for (docid, hit) in result.documents_ids.iter().zip(&mut result.hybrid_hits) {
+            if hit.keyword_scores.is_none() {
+                hit.keyword_scores = keyword_scores.remove(docid);
+            }
+            if hit.semantic_scores.is_none() {
+                hit.semantic_scores = semantic_scores.remove(docid);
+            }
+        }
+
+        Ok(())
+    }
+
     fn results_good_enough(&self, keyword_results: &SearchResult, semantic_ratio: f32) -> bool {
         // A result is good enough if its keyword score is > 0.9 with a semantic ratio of 0.5 => 0.9 * 0.5
         const GOOD_ENOUGH_SCORE: f64 = 0.45;
@@ -218,3 +525,123 @@ impl<'a> Search<'a> {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use crate::index::tests::TempIndex;
+    use crate::score_details::ScoreDetails;
+    use crate::{HybridHitSource, HybridMergeStrategy, Search};
+
+    #[test]
+    fn hybrid_search_with_reciprocal_rank_fusion() {
+        let index = TempIndex::new();
+
+        index.set_manual_embedder(|_| ()).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "text": "the fox", "_vectors": { "manual": [1.0, 0.0] } },
+                { "id": 1, "text": "a fox", "_vectors": { "manual": [0.0, 1.0] } },
+                { "id": 2, "text": "a dog", "_vectors": { "manual": [0.9, 0.1] } },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let embedder = index.manual_embedder();
+
+        let mut search = Search::new(&rtxn, &index);
+        search.query("fox").semantic(S("manual"), embedder, Some(vec![1.0, 0.0]));
+        let (result, _) = search.execute_hybrid(0.5).unwrap();
+        assert_eq!(result.merge_strategy, Some(HybridMergeStrategy::Ratio));
+
+        search.hybrid_merge_strategy(HybridMergeStrategy::ReciprocalRankFusion { k: 1 });
+        let (result, semantic_hit_count) = search.execute_hybrid(0.5).unwrap();
+        assert_eq!(result.merge_strategy, Some(HybridMergeStrategy::ReciprocalRankFusion { k: 1 }));
+        // the fox is first in both searches, the other fox is found by both searches.
+        assert_eq!(result.documents_ids, [0, 1, 2]);
+        assert_eq!(semantic_hit_count, Some(1));
+    }
+
+    #[test]
+    fn hybrid_search_with_sub_scores() {
+        let index = TempIndex::new();
+
+        index.set_manual_embedder(|_| ()).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "text": "the fox", "_vectors": { "manual": [1.0, 0.0] } },
+                { "id": 1, "text": "a fox", "_vectors": { "manual": [0.0, 1.0] } },
+                { "id": 2, "text": "a dog", "_vectors": { "manual": [0.9, 0.1] } },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let embedder = index.manual_embedder();
+
+        let mut search = Search::new(&rtxn, &index);
+        search
+            .query("fox")
+            .limit(2)
+            .semantic(S("manual"), embedder, Some(vec![1.0, 0.0]))
+            .hybrid_merge_strategy(HybridMergeStrategy::ReciprocalRankFusion { k: 1 });
+        let (result, _) = search.execute_hybrid(0.5).unwrap();
+        assert_eq!(result.documents_ids, [0, 1]);
+        assert_eq!(result.hybrid_hits.len(), 2);
+
+        let first = &result.hybrid_hits[0];
+        assert_eq!(first.source, HybridHitSource::Both);
+        assert!(first.keyword_scores.is_some() && first.semantic_scores.is_some());
+        assert!((first.fused_score - 0.5).abs() < f64::EPSILON);
+
+        // the other fox is only found by the keyword search, its semantic score is computed afterwards.
+        let second = &result.hybrid_hits[1];
+        assert_eq!(second.source, HybridHitSource::Keyword);
+        assert!(second.keyword_scores.is_some() && second.semantic_scores.is_some());
+        assert!(second.fused_score < first.fused_score);
+
+        // the other searches have no sub-scores.
+        let result = Search::new(&rtxn, &index).query("fox").execute().unwrap();
+        assert!(result.hybrid_hits.is_empty());
+    }
+
+    #[test]
+    fn hybrid_search_without_semantic_search_has_sub_scores() {
+        let index = TempIndex::new();
+
+        index.set_manual_embedder(|_| ()).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "text": "the fox", "_vectors": { "manual": [1.0, 0.0] } },
+                { "id": 1, "text": "a fox", "_vectors": { "manual": [0.0, 1.0] } },
+                { "id": 2, "text": "a dog", "_vectors": { "manual": [0.9, 0.1] } },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let embedder = index.manual_embedder();
+
+        // the keyword hits are good enough, the semantic search is skipped
+        let mut search = Search::new(&rtxn, &index);
+        search.query("fox").offset(1).limit(1).semantic(S("manual"), embedder.clone(), Some(vec![1.0, 0.0]));
+        let (result, semantic_hit_count) = search.execute_hybrid(0.1).unwrap();
+        assert_eq!(semantic_hit_count, Some(0));
+        assert_eq!(result.documents_ids, [1]);
+        assert_eq!(result.merge_strategy, Some(HybridMergeStrategy::Ratio));
+        assert_eq!(result.hybrid_hits.len(), 1);
+        let hit = &result.hybrid_hits[0];
+        assert_eq!(hit.source, HybridHitSource::Keyword);
+        assert!(hit.keyword_scores.is_some() && hit.semantic_scores.is_some());
+        let keyword_score = ScoreDetails::global_score(hit.keyword_scores.as_ref().unwrap().iter());
+        assert!((hit.fused_score - keyword_score * (1.0 - 0.1f32) as f64).abs() < f64::EPSILON);
+
+        // there is no vector search against a placeholder search
+        let mut search = Search::new(&rtxn, &index);
+        search.semantic(S("manual"), embedder, Some(vec![1.0, 0.0]));
+        let (result, semantic_hit_count) = search.execute_hybrid(0.5).unwrap();
+        assert_eq!(semantic_hit_count, Some(0));
+        assert_eq!(result.documents_ids, [0, 1, 2]);
+        assert_eq!(result.hybrid_hits.len(), 3);
+        assert!(result.hybrid_hits.iter().all(|hit| hit.source == HybridHitSource::Keyword && hit.semantic_scores.is_some()));
+    }
+}
@@ -10,12 +10,13 @@ use crate::search::context::Context;
 use crate::search::query_graph::{GraphNode, NodeData, QueryGraph};
 use crate::search::query_parser::{DerivativeTerm, OriginalTerm, Term, TermKind};
 
-
+#[derive(Clone)]
 pub struct LocatedMatchingPhrase {
     pub value: Vec<String>,
     pub positions: RangeInclusive<WordId>,
 }
 
+#[derive(Clone)]
 pub struct LocatedMatchingWord {
     pub value: String,
     pub positions: RangeInclusive<WordId>,
@@ -24,7 +25,7 @@ pub struct LocatedMatchingWord {
 
 /// Structure created from a query tree
 /// referencing words that match the given query tree.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct MatchingWords {
     phrases: Vec<LocatedMatchingPhrase>,
     words: Vec<LocatedMatchingWord>,
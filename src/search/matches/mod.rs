@@ -78,11 +78,17 @@ impl MatcherBuilder {
 pub struct FormatOptions {
     pub highlight: bool,
     pub crop: Option<usize>,
+    /// The number of non-overlapping crop windows to keep, a single window by default.
+    pub fragments: Option<usize>,
 }
 
 impl FormatOptions {
     pub fn merge(self, other: Self) -> Self {
-        Self { highlight: self.highlight || other.highlight, crop: self.crop.or(other.crop) }
+        Self {
+            highlight: self.highlight || other.highlight,
+            crop: self.crop.or(other.crop),
+            fragments: self.fragments.or(other.fragments),
+        }
     }
 
     pub fn should_format(&self) -> bool {
@@ -107,6 +113,17 @@ pub struct MatchBounds {
     pub length: usize,
 }
 
+/// A crop window of the text with the matches it contains.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Fragment<'t> {
+    pub text: Cow<'t, str>,
+    /// The bounds in byte index of the fragment in the original text.
+    pub start: usize,
+    pub end: usize,
+    /// The matches of the fragment, positioned in the original text.
+    pub matches: Vec<MatchBounds>,
+}
+
 /// Structure used to analize a string, compute words that match,
 /// and format the source string, returning a highlighted and cropped sub-string.
 pub struct Matcher<'t, 'm: 't> {
@@ -417,7 +434,120 @@ impl<'t, 'm: 't> Matcher<'t, 'm> {
         }
     }
 
+    /// Returns the bounds in byte index of the best non-overlapping crop windows, the best window first.
+    ///
+    /// The windows are ranked by match density, the number of matches they contain,
+    /// then by the score computed by match_interval_score.
+    fn fragments_bounds(
+        &self,
+        tokens: &[OwnedToken],
+        matches: &[Match],
+        crop_size: usize,
+        count: usize,
+    ) -> Vec<(usize, usize)> {
+        if matches.is_empty() || count <= 1 {
+            let matches = self.find_best_match_interval(matches, crop_size);
+            return vec![self.crop_bounds(tokens, matches, crop_size)];
+        }
+
+        // the longest matches interval fitting in a crop window, starting at each match.
+        let mut intervals: Vec<_> = (0..matches.len())
+            .map(|first| {
+                let len = matches[first..]
+                    .iter()
+                    .take_while(|m| m.word_position - matches[first].word_position < crop_size)
+                    .count();
+                let interval = &matches[first..first + len];
+                let (uniq_score, distance_score, order_score) = self.match_interval_score(interval);
+                ((len, uniq_score, distance_score, order_score), interval)
+            })
+            .collect();
+        // the sort is stable, the first interval of the text wins on equal scores.
+        intervals.sort_by(|(score, _), (other, _)| other.cmp(score));
+
+        let mut bounds: Vec<(usize, usize)> = Vec::with_capacity(count);
+        for (_, interval) in intervals {
+            if bounds.len() == count {
+                break;
+            }
+            let (start, end) = self.crop_bounds(tokens, interval, crop_size);
+            if bounds.iter().all(|&(other_start, other_end)| end <= other_start || other_end <= start) {
+                bounds.push((start, end));
+            }
+        }
+
+        bounds
+    }
+
+    /// Returns the bounds in byte index of the windows of the text to format.
+    fn format_bounds(
+        &self,
+        tokens: &[OwnedToken],
+        matches: &[Match],
+        format_options: FormatOptions,
+    ) -> Vec<(usize, usize)> {
+        match format_options.crop {
+            Some(crop_size) if crop_size > 0 => {
+                let count = format_options.fragments.unwrap_or(1);
+                self.fragments_bounds(tokens, matches, crop_size, count)
+            }
+            _ => vec![(0, self.text.len())],
+        }
+    }
+
+    /// Pushes the slices of the text between the bounds,
+    /// with highlight markers around the matches if requested.
+    fn push_window(
+        &self,
+        tokens: &[OwnedToken],
+        matches: &[Match],
+        (byte_start, byte_end): (usize, usize),
+        highlight: bool,
+        formatted: &mut Vec<&'t str>,
+    ) {
+        let text = self.text;
+        let mut byte_index = byte_start;
+
+        if highlight {
+            // insert highlight markers around matches.
+            for m in matches {
+                let token = &tokens[m.token_position];
+
+                // skip matches out of the crop window.
+                if token.offset_from < byte_start || token.offset_to > byte_end {
+                    continue;
+                }
+
+                if byte_index < token.offset_from {
+                    formatted.push(&text[byte_index..token.offset_from]);
+                }
+
+                let highlight_byte_index = text[token.offset_from..]
+                    .char_indices()
+                    .enumerate()
+                    .find(|(i, _)| *i == m.match_len)
+                    .map_or(token.offset_to, |(_, (i, _))| i + token.offset_from);
+                formatted.push(self.highlight_prefix);
+                formatted.push(&text[token.offset_from..highlight_byte_index]);
+                formatted.push(self.highlight_suffix);
+                // if it's a prefix highlight, we put the end of the word after the highlight marker.
+                if highlight_byte_index < token.offset_to {
+                    formatted.push(&text[highlight_byte_index..token.offset_to]);
+                }
+
+                byte_index = token.offset_to;
+            }
+        }
+
+        // push the rest of the text between last match and the end of crop.
+        if byte_index < byte_end {
+            formatted.push(&text[byte_index..byte_end]);
+        }
+    }
+
     // Returns the formatted version of the original text.
+    //
+    // When several fragments are requested, the crop windows are joined with the crop marker.
     pub fn format(&mut self, format_options: FormatOptions) -> Cow<'t, str> {
         if !format_options.highlight && format_options.crop.is_none() {
             // compute matches is not needed if no highlight nor crop is requested.
@@ -426,68 +556,28 @@ impl<'t, 'm: 't> Matcher<'t, 'm> {
             match &self.matches {
                 Some((tokens, matches)) => {
                     // If the text has to be cropped,
-                    // crop around the best interval.
-                    let (byte_start, byte_end) = match format_options.crop {
-                        Some(crop_size) if crop_size > 0 => {
-                            let matches = self.find_best_match_interval(matches, crop_size);
-                            self.crop_bounds(tokens, matches, crop_size)
-                        }
-                        _ => (0, self.text.len()),
-                    };
+                    // crop around the best intervals.
+                    let bounds = self.format_bounds(tokens, matches, format_options);
 
                     let mut formatted = Vec::new();
-
-                    // push crop marker if it's not the start of the text.
-                    if byte_start > 0 && !self.crop_marker.is_empty() {
-                        formatted.push(self.crop_marker);
-                    }
-
-                    let mut byte_index = byte_start;
-
-                    if format_options.highlight {
-                        // insert highlight markers around matches.
-                        for m in matches {
-                            let token = &tokens[m.token_position];
-
-                            // skip matches out of the crop window.
-                            if token.offset_from < byte_start || token.offset_to > byte_end {
-                                continue;
-                            }
-
-                            if byte_index < token.offset_from {
-                                formatted.push(&self.text[byte_index..token.offset_from]);
-                            }
-
-                            let highlight_byte_index = self.text[token.offset_from..]
-                                .char_indices()
-                                .enumerate()
-                                .find(|(i, _)| *i == m.match_len)
-                                .map_or(token.offset_to, |(_, (i, _))| i + token.offset_from);
-                            formatted.push(self.highlight_prefix);
-                            formatted.push(&self.text[token.offset_from..highlight_byte_index]);
-                            formatted.push(self.highlight_suffix);
-                            // if it's a prefix highlight, we put the end of the word after the highlight marker.
-                            if highlight_byte_index < token.offset_to {
-                                formatted.push(&self.text[highlight_byte_index..token.offset_to]);
-                            }
-
-                            byte_index = token.offset_to;
+                    for (i, &(byte_start, byte_end)) in bounds.iter().enumerate() {
+                        // push crop marker if it's not the start of the text or between two fragments.
+                        if (i > 0 || byte_start > 0) && !self.crop_marker.is_empty() {
+                            formatted.push(self.crop_marker);
                         }
-                    }
-
-                    // push the rest of the text between last match and the end of crop.
-                    if byte_index < byte_end {
-                        formatted.push(&self.text[byte_index..byte_end]);
+                        let highlight = format_options.highlight;
+                        self.push_window(tokens, matches, (byte_start, byte_end), highlight, &mut formatted);
                     }
 
                     // push crop marker if it's not the end of the text.
-                    if byte_end < self.text.len() && !self.crop_marker.is_empty() {
+                    let last_byte_end = bounds.last().map_or(self.text.len(), |&(_, byte_end)| byte_end);
+                    if last_byte_end < self.text.len() && !self.crop_marker.is_empty() {
                         formatted.push(self.crop_marker);
                     }
 
                     if formatted.len() == 1 {
                         // avoid concatenating if there is already 1 slice.
-                        Cow::Borrowed(&self.text[byte_start..byte_end])
+                        Cow::Borrowed(formatted[0])
                     } else {
                         Cow::Owned(formatted.concat())
                     }
@@ -496,6 +586,39 @@ impl<'t, 'm: 't> Matcher<'t, 'm> {
             }
         }
     }
+
+    /// Returns the crop windows of the text individually, the best fragment first,
+    /// the text of a fragment doesn't contain the crop markers.
+    ///
+    /// The whole text is a single fragment when no crop is requested.
+    pub fn fragments(&mut self, format_options: FormatOptions) -> Vec<Fragment<'t>> {
+        match &self.matches {
+            Some((tokens, matches)) => self
+                .format_bounds(tokens, matches, format_options)
+                .into_iter()
+                .map(|(byte_start, byte_end)| {
+                    let mut formatted = Vec::new();
+                    let highlight = format_options.highlight;
+                    self.push_window(tokens, matches, (byte_start, byte_end), highlight, &mut formatted);
+                    let text = match formatted.len() {
+                        0 => Cow::Borrowed(""),
+                        1 => Cow::Borrowed(formatted[0]),
+                        _ => Cow::Owned(formatted.concat()),
+                    };
+
+                    let matches = matches
+                        .iter()
+                        .map(|m| (&tokens[m.token_position], m))
+                        .filter(|(token, _)| token.offset_from >= byte_start && token.offset_to <= byte_end)
+                        .map(|(token, m)| MatchBounds { start: token.offset_from, length: m.match_len })
+                        .collect();
+
+                    Fragment { text, start: byte_start, end: byte_end, matches }
+                })
+                .collect(),
+            None => self.compute_matches().fragments(format_options),
+        }
+    }
 }
 
 // #[cfg(test)]
@@ -892,3 +1015,43 @@ impl<'t, 'm: 't> Matcher<'t, 'm> {
 //         );
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use crate::index::tests::TempIndex;
+    use crate::{FormatOptions, MatchBounds, MatcherBuilder, Search, SearchResult};
+
+    #[test]
+    fn format_with_fragments() {
+        let index = TempIndex::new();
+        let text = "fox one two three four five six seven eight nine fox fox ten eleven twelve fox";
+
+        index.add_documents(documents!([{ "id": 0, "text": text }])).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { matching_words, .. } = Search::new(&rtxn, &index).query("fox").execute().unwrap();
+        let builder = MatcherBuilder::new(matching_words, index.analyzer(&rtxn, &None).unwrap());
+
+        let options = FormatOptions { highlight: true, crop: Some(3), fragments: Some(2) };
+        let mut matcher = builder.build(text);
+        assert_eq!(matcher.format(options), "…nine <em>fox</em> <em>fox</em>…<em>fox</em> one two…");
+
+        let fragments = matcher.fragments(options);
+        assert_eq!(fragments.len(), 2);
+        assert_eq!(fragments[0].text, "nine <em>fox</em> <em>fox</em>");
+        assert_eq!((fragments[0].start, fragments[0].end), (44, 56));
+        assert_eq!(
+            fragments[0].matches,
+            [MatchBounds { start: 49, length: 3 }, MatchBounds { start: 53, length: 3 }]
+        );
+        assert_eq!(fragments[1].text, "<em>fox</em> one two");
+        assert_eq!((fragments[1].start, fragments[1].end), (0, 11));
+        assert_eq!(fragments[1].matches, [MatchBounds { start: 0, length: 3 }]);
+
+        // a single fragment is the usual crop window.
+        let options = FormatOptions { highlight: true, crop: Some(3), fragments: None };
+        let mut matcher = builder.build(text);
+        assert_eq!(matcher.format(options), "…nine <em>fox</em> <em>fox</em>…");
+        assert_eq!(matcher.fragments(options).len(), 1);
+    }
+}
@@ -1,13 +1,24 @@
 use std::fmt;
+use std::num::NonZeroUsize;
 use std::sync::Arc;
 use roaring::RoaringBitmap;
-use crate::{AscDesc, DocumentId, Index};
+use crate::{AscDesc, DocumentId, Index, TimeBudget};
 use crate::score_details::{ScoreDetails};
 pub use crate::search::facet::Filter;
-use crate::search::search::{execute_search, execute_vector_search, filtered_universe, PartialSearchResult, SearchContext};
+pub use crate::search::cursor::SearchCursor;
+pub use crate::search::hits::{DocumentsOptions, SearchHit};
+pub use crate::search::hybrid::{HybridHit, HybridHitSource, HybridMergeStrategy};
+pub use crate::search::similar::{SimilarOptions, SimilarResult};
+pub use crate::search::suggest::{SuggestOptions, Suggestion};
+pub use crate::search::explanation::{BucketExplanation, NodeExplanation, NodeKind, QueryExplanation, RankingRuleExplanation, VectorSearchStrategy};
+pub use crate::search::query_parser::{DerivativeTerm, OriginalTerm, TermKind};
+use crate::search::curation::Curation;
+use crate::search::spelling::correct_query;
+use crate::search::search::{distinct_field_id, execute_search, execute_vector_search, filtered_universe, PartialSearchResult, SearchContext};
 use crate::vector::Embedder;
 use crate::Result;
 use crate::search::matches::MatchingWords;
+use crate::search::ranking::vector_sort::VectorSearchOptions;
 
 pub mod utils;
 pub mod ranking;
@@ -23,6 +34,14 @@ mod search;
 mod db_cache;
 mod query_cache;
 mod hybrid;
+mod cursor;
+mod curation;
+mod query_rewrite;
+mod explanation;
+mod hits;
+mod suggest;
+mod spelling;
+mod similar;
 
 
 #[derive(Debug, Clone)]
@@ -37,11 +56,19 @@ pub struct Search<'a> {
     filter: Option<Filter>,
     offset: u64,
     limit: u64,
+    after: Option<SearchCursor>,
     sort_criteria: Option<Vec<AscDesc>>,
+    distinct: Option<String>,
     analyzer: Option<String>,
     searchable_attributes: Option<&'a [String]>,
     terms_matching_strategy: TermsMatchingStrategy,
     output_query_graph: bool,
+    explain: bool,
+    ranking_score_threshold: Option<f64>,
+    hybrid_merge_strategy: HybridMergeStrategy,
+    did_you_mean: Option<u64>,
+    vector_search_options: VectorSearchOptions,
+    time_budget: TimeBudget,
     rtxn: &'a heed::RoTxn<'a>,
     index: &'a Index,
     semantic: Option<SemanticSearch>,
@@ -54,11 +81,19 @@ impl<'a> Search<'a> {
             filter: None,
             offset: 0,
             limit: 20,
+            after: None,
             sort_criteria: None,
+            distinct: None,
             analyzer: None,
             searchable_attributes: None,
             terms_matching_strategy: TermsMatchingStrategy::default(),
             output_query_graph: false,
+            explain: false,
+            ranking_score_threshold: None,
+            hybrid_merge_strategy: HybridMergeStrategy::default(),
+            did_you_mean: None,
+            vector_search_options: VectorSearchOptions::default(),
+            time_budget: TimeBudget::max(),
             rtxn,
             index,
             semantic: None,
@@ -95,11 +130,21 @@ impl<'a> Search<'a> {
         self
     }
 
+    pub fn after(&mut self, cursor: SearchCursor) -> &mut Search<'a> {
+        self.after = Some(cursor);
+        self
+    }
+
     pub fn sort_criteria(&mut self, criteria: Vec<AscDesc>) -> &mut Search<'a> {
         self.sort_criteria = Some(criteria);
         self
     }
 
+    pub fn distinct(&mut self, distinct: String) -> &mut Search<'a> {
+        self.distinct = Some(distinct);
+        self
+    }
+
     pub fn searchable_attributes(&mut self, searchable: &'a [String]) -> &mut Search<'a> {
         self.searchable_attributes = Some(searchable);
         self
@@ -110,6 +155,11 @@ impl<'a> Search<'a> {
         self
     }
 
+    pub fn explain(&mut self, explain: bool) -> &mut Search<'a> {
+        self.explain = explain;
+        self
+    }
+
     pub fn terms_matching_strategy(&mut self, value: TermsMatchingStrategy) -> &mut Search<'a> {
         self.terms_matching_strategy = value;
         self
@@ -120,6 +170,41 @@ impl<'a> Search<'a> {
         self
     }
 
+    pub fn ranking_score_threshold(&mut self, ranking_score_threshold: f64) -> &mut Search<'a> {
+        self.ranking_score_threshold = Some(ranking_score_threshold);
+        self
+    }
+
+    /// How the keyword hits and the semantic hits are merged by `Search::execute_hybrid`.
+    pub fn hybrid_merge_strategy(&mut self, strategy: HybridMergeStrategy) -> &mut Search<'a> {
+        self.hybrid_merge_strategy = strategy;
+        self
+    }
+
+    /// Suggests a corrected query when the search matches at most `max_hits` documents.
+    pub fn did_you_mean(&mut self, max_hits: u64) -> &mut Search<'a> {
+        self.did_you_mean = Some(max_hits);
+        self
+    }
+
+    /// Compares the vectors of all the candidates of a vector search when a filter leaves at most `threshold`
+    /// of them, instead of looking for the nearest ones in the arroy trees.
+    pub fn exact_vector_search_threshold(&mut self, threshold: u64) -> &mut Search<'a> {
+        self.vector_search_options.exact_threshold = threshold;
+        self
+    }
+
+    /// The number of nodes inspected by the approximate vector search, the more the more accurate.
+    pub fn vector_search_k(&mut self, search_k: NonZeroUsize) -> &mut Search<'a> {
+        self.vector_search_options.search_k = Some(search_k);
+        self
+    }
+
+    pub fn time_budget(&mut self, time_budget: TimeBudget) -> &mut Search<'a> {
+        self.time_budget = time_budget;
+        self
+    }
+
     pub fn execute_for_candidates(&self, has_vector_search: bool) -> Result<RoaringBitmap> {
         if has_vector_search {
             let ctx = SearchContext::new(self.index, self.rtxn, self.terms_matching_strategy);
@@ -136,12 +221,33 @@ impl<'a> Search<'a> {
             ctx.searchable_attributes(searchable_attributes)?;
         }
 
-        let universe = filtered_universe(&ctx, &self.filter)?;
+        // hits that can't be reached because of the pagination limit are never sorted, they are still counted
+        let position = self.after.as_ref().map_or(0, |cursor| cursor.position) + self.offset;
+        let limit = match self.index.pagination_max_total_hits(self.rtxn)? {
+            Some(max_total_hits) => self.limit.min(max_total_hits.saturating_sub(position)),
+            None => self.limit,
+        };
+
+        let mut universe = filtered_universe(&ctx, &self.filter)?;
+        let distinct = distinct_field_id(&ctx, &self.distinct)?;
+
+        // the curated documents are removed from the ranked documents, the pinned ones
+        // take the place of ranked documents in the pages of their positions
+        let curation = Curation::new(self.index, self.rtxn, self.query.as_deref(), &self.analyzer, &universe)?;
+        universe -= curation.curated_documents();
+        let start = position - self.offset;
+        let ranked_offset = self.offset - curation.pinned_count(start..position);
+        let ranked_limit = limit - curation.pinned_count(position..position.saturating_add(limit));
+        let spelling_universe = self.did_you_mean.map(|_| universe.clone());
+
         let PartialSearchResult {
-            candidates,
+            mut candidates,
             documents_ids,
             document_scores,
-            query_graph
+            query_graph,
+            degraded,
+            explanation,
+            fired_rules,
         } = match self.semantic.as_ref() {
             Some(SemanticSearch { vector: Some(vector), embedder_name, embedder }) => {
                 execute_vector_search(
@@ -149,10 +255,16 @@ impl<'a> Search<'a> {
                     vector,
                     universe,
                     &self.sort_criteria,
-                    self.offset,
-                    self.limit,
+                    distinct,
+                    ranked_offset,
+                    ranked_limit,
                     embedder_name,
                     embedder,
+                    self.vector_search_options,
+                    self.after.as_ref(),
+                    self.ranking_score_threshold,
+                    &self.time_budget,
+                    self.explain,
                 )?
             }
             _ => execute_search(
@@ -160,21 +272,64 @@ impl<'a> Search<'a> {
                 &self.query,
                 universe,
                 &self.sort_criteria,
+                distinct,
                 &self.analyzer,
-                self.offset,
-                self.limit,
+                ranked_offset,
+                ranked_limit,
+                self.after.as_ref(),
+                self.ranking_score_threshold,
+                &self.time_budget,
+                self.explain,
             )?,
         };
 
+        // the candidates are all the hits of the search, not only the returned page
+        candidates |= curation.pinned_documents();
+
+        // a corrected query is only looked for when the search has few hits
+        let suggested_query = match (self.did_you_mean, &self.query, spelling_universe) {
+            (Some(max_hits), Some(query), Some(universe)) if candidates.len() <= max_hits => {
+                let analyzer = self.index.analyzer(self.rtxn, &self.analyzer)?;
+                correct_query(&mut ctx, &analyzer, query, &universe)?
+            }
+            _ => None,
+        };
+
         let query_graph_d2 = self.output_query_graph.then(|| query_graph.to_string());
         let matching_words = MatchingWords::new(ctx, query_graph);
 
+        // the pinned hits aren't ranked, the search is resumed from the last ranked hit
+        let last_ranked = match (documents_ids.last(), document_scores.last()) {
+            (Some(&docid), Some(scores)) => Some((docid, scores.clone())),
+            _ => self.after.as_ref().map(|cursor| (cursor.docid, cursor.scores.clone())),
+        };
+        let (documents_ids, document_scores) =
+            curation.merge(position, limit, documents_ids, document_scores);
+
+        // the scores of a degraded search don't go through all the ranking rules,
+        // the search can't be resumed from them.
+        let cursor = match last_ranked {
+            Some((docid, scores)) if !degraded && !documents_ids.is_empty() => Some(SearchCursor {
+                docid,
+                scores,
+                position: position + documents_ids.len() as u64,
+            }),
+            _ => None,
+        };
+
         Ok(SearchResult {
             matching_words,
             candidates,
             document_scores,
             documents_ids,
             query_graph: query_graph_d2,
+            explanation,
+            degraded,
+            cursor,
+            fired_rules,
+            suggested_query,
+            merge_strategy: None,
+            hybrid_hits: Vec::new(),
         })
     }
 }
@@ -186,14 +341,22 @@ impl fmt::Debug for Search<'_> {
             filter,
             offset,
             limit,
+            after,
             sort_criteria,
+            distinct,
             searchable_attributes,
             terms_matching_strategy,
             analyzer,
+            ranking_score_threshold,
+            hybrid_merge_strategy,
+            did_you_mean,
+            vector_search_options,
+            time_budget,
             rtxn: _,
             index: _,
             semantic,
-            output_query_graph
+            output_query_graph,
+            explain,
         } = self;
         f.debug_struct("Search")
             .field("query", query)
@@ -201,11 +364,19 @@ impl fmt::Debug for Search<'_> {
             .field("filter", filter)
             .field("offset", offset)
             .field("limit", limit)
+            .field("after", after)
             .field("sort_criteria", sort_criteria)
+            .field("distinct", distinct)
             .field("searchable_attributes", searchable_attributes)
             .field("terms_matching_strategy", terms_matching_strategy)
             .field("output_query_graph", output_query_graph)
+            .field("explain", explain)
             .field("analyzer", analyzer)
+            .field("ranking_score_threshold", ranking_score_threshold)
+            .field("hybrid_merge_strategy", hybrid_merge_strategy)
+            .field("did_you_mean", did_you_mean)
+            .field("vector_search_options", vector_search_options)
+            .field("time_budget", time_budget)
             .field(
                 "semantic.embedder_name",
                 &semantic.as_ref().map(|semantic| &semantic.embedder_name),
@@ -220,7 +391,19 @@ pub struct SearchResult {
     pub candidates: RoaringBitmap,
     pub documents_ids: Vec<DocumentId>,
     pub document_scores: Vec<Vec<ScoreDetails>>,
-    pub query_graph: Option<String>
+    pub query_graph: Option<String>,
+    pub explanation: Option<QueryExplanation>,
+    pub degraded: bool,
+    pub cursor: Option<SearchCursor>,
+    /// The ids of the query rewrite rules that fired, in the order they fired.
+    pub fired_rules: Vec<String>,
+    /// A corrected query matching more documents, only looked for when requested by `Search::did_you_mean`.
+    pub suggested_query: Option<String>,
+    /// The strategy used to merge the keyword hits and the semantic hits,
+    /// `None` when the hits of a single search are returned.
+    pub merge_strategy: Option<HybridMergeStrategy>,
+    /// The keyword and semantic scores of each hit of a hybrid search, empty for the other searches.
+    pub hybrid_hits: Vec<HybridHit>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -229,6 +412,10 @@ pub enum TermsMatchingStrategy {
     Last,
     // all words are mandatory
     All,
+    // remove the most frequent word first
+    Frequency,
+    // any word is enough
+    Any,
 }
 
 impl Default for TermsMatchingStrategy {
@@ -267,3 +454,60 @@ impl Default for TermsMatchingStrategy {
 //         assert_eq!(documents_ids, vec![1]);
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use crate::index::tests::TempIndex;
+    use crate::{Search, SearchResult};
+
+    #[test]
+    fn search_enforces_pagination_max_total_hits() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_pagination_max_total_hits(3);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "hello" },
+                { "id": 1, "name": "hello" },
+                { "id": 2, "name": "hello" },
+                { "id": 3, "name": "hello" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, .. } =
+            Search::new(&rtxn, &index).query("hello").offset(2).limit(20).execute().unwrap();
+        assert_eq!(documents_ids.len(), 1);
+
+        let SearchResult { documents_ids, candidates, .. } =
+            Search::new(&rtxn, &index).query("hello").offset(3).execute().unwrap();
+        assert!(documents_ids.is_empty());
+        assert_eq!(candidates.len(), 4);
+    }
+
+    #[test]
+    fn search_with_limit_zero_counts_the_hits() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "hello" },
+                { "id": 1, "name": "hello world" },
+                { "id": 2, "name": "world" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, candidates, .. } =
+            Search::new(&rtxn, &index).query("hello").limit(0).execute().unwrap();
+        assert!(documents_ids.is_empty());
+        assert_eq!(candidates.len(), 2);
+
+        let candidates = Search::new(&rtxn, &index).query("hello").limit(0).execute_for_candidates(false).unwrap();
+        assert_eq!(candidates.len(), 2);
+    }
+}
@@ -43,6 +43,8 @@ pub struct QueryGraph{
     pub query_max_typos: usize,
     pub query_terms: usize,
     pub query_words: usize,
+    /// The sets of original terms a path is allowed to match, `None` when every set is allowed.
+    pub allowed_terms: Option<Vec<BitSet>>,
 }
 
 #[derive(Error, Debug)]
@@ -134,24 +136,121 @@ impl QueryGraph {
 
     fn short_circuit(mut self, context: &mut impl Context) -> Result<QueryGraph>{
         let matching_strategy = context.term_matching_strategy();
-        if let TermsMatchingStrategy::Last = matching_strategy{
-            let nodes = (0..self.nodes.len());
-            for mut node_id in nodes{
-                let Ok([node, end_node]) = self.nodes.get_many_mut([node_id, self.end]) else { continue };
-                match &node.data {
-                    NodeData::Term(_) => {
-                        node.successors.insert(self.end);
-                        end_node.predecessors.insert(node_id);
-                    },
-                    _ => continue
+        match matching_strategy {
+            TermsMatchingStrategy::Last => {
+                let nodes = (0..self.nodes.len());
+                for mut node_id in nodes{
+                    let Ok([node, end_node]) = self.nodes.get_many_mut([node_id, self.end]) else { continue };
+                    match &node.data {
+                        NodeData::Term(_) => {
+                            node.successors.insert(self.end);
+                            end_node.predecessors.insert(node_id);
+                        },
+                        _ => continue
+                    }
                 }
             }
-        }
+            TermsMatchingStrategy::Frequency | TermsMatchingStrategy::Any => {
+                let terms = (0..self.nodes.len())
+                    .filter_map(|node_id| match &self.nodes[node_id].data {
+                        NodeData::Term(term) => Some((node_id, term.position.clone())),
+                        _ => None
+                    })
+                    .collect::<Vec<_>>();
+
+                for (node_id, position) in &terms{
+                    self.connect(self.root, *node_id);
+                    self.connect(*node_id, self.end);
+                    for (successor_id, successor_position) in &terms{
+                        if position.end() < successor_position.start(){
+                            self.connect(*node_id, *successor_id);
+                        }
+                    }
+                }
 
+                if let TermsMatchingStrategy::Frequency = matching_strategy{
+                    self.allowed_terms = Some(self.frequency_allowed_terms(context)?);
+                }
+            }
+            TermsMatchingStrategy::All => {}
+        }
 
         Ok(self)
     }
 
+    fn connect(&mut self, from: usize, to: usize){
+        self.nodes[from].successors.insert(to);
+        self.nodes[to].predecessors.insert(from);
+    }
+
+    fn frequency_allowed_terms(&self, context: &mut impl Context) -> Result<Vec<BitSet>>{
+        let mut frequencies = Vec::with_capacity(self.query_terms);
+        for node_id in self.root+1..self.end{
+            let NodeData::Term(term) = &self.nodes[node_id].data else { continue };
+            let frequency = match &term.term_kind {
+                TermKind::Normal(OriginalTerm::Word(text)) | TermKind::Exact(OriginalTerm::Word(text)) => {
+                    context.word_documents_count(text)?
+                },
+                TermKind::Normal(OriginalTerm::Prefix(text)) | TermKind::Exact(OriginalTerm::Prefix(text)) => {
+                    context.prefix_docids(text)?.len()
+                },
+                TermKind::Normal(OriginalTerm::Phrase(words)) | TermKind::Exact(OriginalTerm::Phrase(words)) => {
+                    let mut frequency = u64::MAX;
+                    for word in words{
+                        frequency = frequency.min(context.word_documents_count(word)?);
+                    }
+                    if words.is_empty() { 0 } else { frequency }
+                },
+                TermKind::Derivative(..) => continue
+            };
+            frequencies.push((node_id, frequency));
+        }
+        // the most frequent terms are removed first, ties are broken by removing the last term first
+        frequencies.sort_by(|(left_id, left), (right_id, right)| right.cmp(left).then(right_id.cmp(left_id)));
+
+        let mut allowed_terms = BitSet::from_iter(frequencies.iter().map(|(node_id, _)| *node_id));
+        let mut allowed = Vec::with_capacity(frequencies.len());
+        allowed.push(allowed_terms);
+        for (node_id, _) in frequencies.iter().take(frequencies.len().saturating_sub(1)){
+            allowed_terms.remove(*node_id);
+            allowed.push(allowed_terms);
+        }
+
+        Ok(allowed)
+    }
+
+    /// Returns the original terms matched by the nodes of a path, a derivative term matches
+    /// all the original terms it has been built from.
+    pub fn path_terms(&self, path: &BitSet) -> BitSet{
+        let mut terms = BitSet::new();
+        for node_id in path.iter(){
+            let Some(GraphNode{ data: NodeData::Term(term), .. }) = self.nodes.get(node_id) else { continue };
+            match term.term_kind {
+                TermKind::Derivative(..) => {
+                    for original_id in self.root+1..self.end{
+                        let NodeData::Term(original) = &self.nodes[original_id].data else { continue };
+                        if term.position.start() <= original.position.start() && original.position.end() <= term.position.end(){
+                            terms.insert(original_id);
+                        }
+                    }
+                }
+                _ => { terms.insert(node_id); }
+            }
+        }
+
+        terms
+    }
+
+    pub fn is_allowed_path(&self, path: &BitSet) -> bool{
+        match &self.allowed_terms {
+            Some(allowed_terms) => {
+                let terms = self.path_terms(path);
+                allowed_terms.iter().any(|allowed| *allowed == terms)
+            }
+            None => true
+        }
+    }
+
     fn prefixes(mut self, _: &mut impl Context) -> Result<QueryGraph>{
         let mut nodes = Vec::new();
         for node in &self.nodes{
@@ -487,6 +586,7 @@ impl QueryGraph {
             query_max_typos,
             query_terms,
             query_words,
+            allowed_terms: None,
         })
     }
 
@@ -515,8 +615,9 @@ impl QueryGraph {
 #[cfg(test)]
 pub mod tests {
     use std::num::NonZeroUsize;
-    use arroy::distances::Angular;
-    use arroy::{ItemId, Reader};
+    use arroy::ItemId;
+    use crate::vector::distance::VectorReader;
+    use crate::vector::VectorDistance;
     use fst::Set;
     use heed::RoTxn;
     use crate::search::query_parser::parse_query;
@@ -525,12 +626,15 @@ pub mod tests {
     use rand::prelude::StdRng;
     use rand::{Rng, SeedableRng};
     use roaring::RoaringBitmap;
-    use crate::{Criterion, FieldId, FieldsIdsMap};
+    use rstar::RTree;
     use crate::search::context::{Fid, Position};
     use crate::search::facet::{AscendingSortIter, DescendingSortIter};
     use crate::search::resolve_query_graph::{resolve_node_docids, resolve_path_docids};
     use crate::update::split_config::SplitJoinConfig;
+    use crate::{Criterion, DocumentId, FieldId, FieldsIdsMap, GeoPoint};
     use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::{Search, SearchResult, TermsMatchingStrategy};
 
     #[derive(Debug)]
     pub struct TestContext {
@@ -593,6 +697,10 @@ pub mod tests {
             Ok(Some(vec![1,2,3,4,5]))
         }
 
+        fn searchable_attribute_weights(&mut self) -> Result<HashMap<FieldId, u16>> {
+            Ok(HashMap::new())
+        }
+
         fn word_pair_frequency(
             &mut self,
             left_word: &str,
@@ -707,8 +815,13 @@ pub mod tests {
             todo!()
         }
 
-        fn vector_reader(&self, index: u16) -> arroy::Result<Reader<Angular>> {
-            todo!()
+        fn vector_reader(&self, index: u16, distance: VectorDistance, quantization: Option<crate::vector::VectorQuantization>) -> arroy::Result<VectorReader> {
+            // there are no vectors in the test context.
+            Err(arroy::Error::MissingMetadata)
+        }
+
+        fn embedding_config(&self, embedder_name: &str) -> Result<Option<crate::vector::EmbeddingConfig>> {
+            Ok(None)
         }
 
         fn ascending_number_sort<'a>(&self, tnx: &'a RoTxn<'a>, fid: Fid, candidates: RoaringBitmap) -> Result<AscendingSortIter<'a>> {
@@ -727,6 +840,20 @@ pub mod tests {
             todo!()
         }
 
+        fn distinct_docids(&mut self, fid: Fid, docid: DocumentId) -> Result<RoaringBitmap> {
+            // there are no facets in the test context.
+            Ok(RoaringBitmap::new())
+        }
+
+        fn distinct_universe(&mut self, fid: Fid, candidates: &RoaringBitmap) -> Result<RoaringBitmap> {
+            // there are no facets in the test context.
+            Ok(candidates.clone())
+        }
+
+        fn geo_rtree(&self) -> Result<Option<RTree<GeoPoint>>> {
+            Ok(None)
+        }
+
         fn descending_string_sort<'a>(&self, tnx: &'a RoTxn<'a>, fid: Fid, candidates: RoaringBitmap) -> Result<DescendingSortIter<'a>> {
             todo!()
         }
@@ -851,4 +978,54 @@ pub mod tests {
         let query_graph = QueryGraph::from_query(parsed_query, &mut context);
         println!("{:#?}", query_graph.unwrap());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn search_with_frequency_terms_matching_strategy() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "the quick fox" },
+                { "id": 1, "name": "the quick" },
+                { "id": 2, "name": "quick fox" },
+                { "id": 3, "name": "the cat" },
+                { "id": 4, "name": "the dog" },
+                { "id": 5, "name": "the end" },
+                { "id": 6, "name": "fox" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, .. } = Search::new(&rtxn, &index)
+            .query("the quick fox")
+            .terms_matching_strategy(TermsMatchingStrategy::Frequency)
+            .execute()
+            .unwrap();
+        // "the" is the most frequent term and is removed first, then "fox" which is
+        // as frequent as "quick" but comes last in the query.
+        assert_eq!(documents_ids, [0, 2, 1]);
+    }
+
+    #[test]
+    fn search_with_any_terms_matching_strategy() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "hello world" },
+                { "id": 1, "name": "hello" },
+                { "id": 2, "name": "world" },
+                { "id": 3, "name": "goodbye" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, .. } = Search::new(&rtxn, &index)
+            .query("hello world")
+            .terms_matching_strategy(TermsMatchingStrategy::Any)
+            .execute()
+            .unwrap();
+        // the documents matching both terms come first, then the documents matching any of them
+        assert_eq!(documents_ids, [0, 1, 2]);
+    }
+}
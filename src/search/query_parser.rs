@@ -1,4 +1,5 @@
 use std::ops::RangeInclusive;
+use serde::{Deserialize, Serialize};
 use analyzer::token::{SeparatorKind, TokenFlags, TokenKind};
 use analyzer::tokenizer::token_stream::TokenStream;
 use crate::search::utils::bit_set::BitSet;
@@ -89,14 +90,14 @@ pub fn parse_query<'a>(mut token_stream: impl TokenStream<'a>) -> Vec<Term>{
     query_terms
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Term{
     pub term_kind: TermKind,
     pub is_negative: bool,
     pub position: RangeInclusive<usize>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum DerivativeTerm {
     Ngram(String, u8),
     Synonym(Vec<String>),
@@ -106,14 +107,14 @@ pub enum DerivativeTerm {
     Split(Vec<(String, String)>)
 }
 
-#[derive(Clone,Debug)]
+#[derive(Clone,Debug, Serialize, Deserialize)]
 pub enum OriginalTerm{
     Word(String),
     Prefix(String),
     Phrase(Vec<String>),
 }
 
-#[derive(Clone,Debug)]
+#[derive(Clone,Debug, Serialize, Deserialize)]
 pub enum TermKind{
     Derivative(DerivativeTerm, usize),
     Exact(OriginalTerm),
@@ -0,0 +1,198 @@
+use analyzer::analyzer::BoxAnalyzer;
+use roaring::RoaringBitmap;
+
+use crate::search::query_parser::{OriginalTerm, Term, TermKind};
+use crate::search::search::SearchContext;
+use crate::{AscDesc, Result};
+
+/// The query terms once rewritten by the rules of the index.
+pub struct RewrittenQuery {
+    pub terms: Vec<Term>,
+    /// The documents allowed by the filters of the fired rules, `None` when no rule has a filter.
+    pub universe: Option<RoaringBitmap>,
+    /// The sort of the last fired rule changing the sort.
+    pub sort_criteria: Option<Vec<AscDesc>>,
+    /// The ids of the fired rules, in the order they fired.
+    pub fired_rules: Vec<String>,
+}
+
+/// Applies the query rewrite rules in order, each rule sees the terms rewritten by the previous ones.
+pub fn rewrite_query(ctx: &SearchContext, analyzer: &BoxAnalyzer, mut terms: Vec<Term>) -> Result<RewrittenQuery> {
+    let mut universe: Option<RoaringBitmap> = None;
+    let mut sort_criteria = None;
+    let mut fired_rules = Vec::new();
+
+    for rule in ctx.index.query_rewrite_rules(ctx.txn)? {
+        let pattern = words(analyzer, &rule.pattern);
+        if pattern.is_empty() || find_pattern(&terms, &pattern, 0).is_none() {
+            continue;
+        }
+
+        if let Some(replacement) = &rule.replacement {
+            let replacement = words(analyzer, replacement);
+            let mut from = 0;
+            while let Some(start) = find_pattern(&terms, &pattern, from) {
+                replace_terms(&mut terms, start..start + pattern.len(), &replacement);
+                from = start + replacement.len();
+            }
+        }
+        if let Some(filter) = rule.filter()? {
+            let allowed = filter.evaluate(ctx.txn, ctx.index)?;
+            universe = Some(match universe {
+                Some(universe) => universe & allowed,
+                None => allowed,
+            });
+        }
+        if let Some(sort) = rule.sort()? {
+            sort_criteria = Some(sort);
+        }
+        fired_rules.push(rule.id);
+    }
+
+    Ok(RewrittenQuery { terms, universe, sort_criteria, fired_rules })
+}
+
+/// Returns the words of the text once analyzed like the query.
+fn words(analyzer: &BoxAnalyzer, text: &str) -> Vec<String> {
+    analyzer
+        .analyze(text)
+        .as_iter()
+        .filter_map(|token| (token.is_word() && !token.text.is_empty()).then(|| token.text.to_string()))
+        .collect()
+}
+
+/// Returns the word of a term that can be rewritten, the phrases and the negative terms can't.
+fn term_word(term: &Term) -> Option<&str> {
+    if term.is_negative {
+        return None;
+    }
+    match &term.term_kind {
+        TermKind::Normal(OriginalTerm::Word(word) | OriginalTerm::Prefix(word))
+        | TermKind::Exact(OriginalTerm::Word(word) | OriginalTerm::Prefix(word)) => Some(word),
+        _ => None,
+    }
+}
+
+/// Returns the index of the first term, starting at `from`, of a sequence of terms matching the pattern.
+fn find_pattern(terms: &[Term], pattern: &[String], from: usize) -> Option<usize> {
+    (from..=terms.len().checked_sub(pattern.len())?).find(|&start| {
+        terms[start..start + pattern.len()]
+            .iter()
+            .zip(pattern)
+            .all(|(term, word)| term_word(term) == Some(word.as_str()))
+    })
+}
+
+/// Replaces a range of terms by words at the same position,
+/// the positions of the following terms are shifted accordingly.
+fn replace_terms(terms: &mut Vec<Term>, range: std::ops::Range<usize>, words: &[String]) {
+    let start = *terms[range.start].position.start();
+    let end = *terms[range.end - 1].position.end();
+    let shift = words.len() as isize - (end - start + 1) as isize;
+
+    let replacement = words.iter().enumerate().map(|(i, word)| Term {
+        term_kind: TermKind::Normal(OriginalTerm::Word(word.clone())),
+        is_negative: false,
+        position: start + i..=start + i,
+    });
+    let end_of_replacement = range.start + words.len();
+    terms.splice(range, replacement);
+
+    for term in &mut terms[end_of_replacement..] {
+        let (start, end) = (*term.position.start(), *term.position.end());
+        term.position = start.wrapping_add_signed(shift)..=end.wrapping_add_signed(shift);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
+    use crate::error::Error;
+    use crate::index::tests::TempIndex;
+    use crate::update::query_rewrite_rules::QueryRewriteRule;
+    use crate::{Search, SearchResult, UserError};
+
+    #[test]
+    fn search_with_query_rewrite_rules() {
+        let index = TempIndex::new();
+
+        let rule = |id: &str, pattern: &str, replacement: Option<&str>| QueryRewriteRule {
+            id: S(id),
+            pattern: S(pattern),
+            replacement: replacement.map(S),
+            filter: None,
+            sort: None,
+        };
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("tier") });
+                settings.set_sortable_fields(hashset! { S("year") });
+                settings.set_query_rewrite_rules(vec![
+                    QueryRewriteRule {
+                        filter: Some(serde_json::json!({ "tier": "budget" })),
+                        ..rule("cheap", "cheap", Some(""))
+                    },
+                    rule("tv", "tv", Some("television")),
+                    QueryRewriteRule { sort: Some(vec![S("year:desc")]), ..rule("newest", "newest", Some("")) },
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "television stand", "tier": "premium", "year": 2020 },
+                { "id": 1, "name": "television", "tier": "budget", "year": 2018 },
+                { "id": 2, "name": "tv remote", "tier": "budget", "year": 2022 },
+                { "id": 3, "name": "television", "tier": "budget", "year": 2023 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, fired_rules, .. } =
+            Search::new(&rtxn, &index).query("cheap tv").execute().unwrap();
+        assert_eq!(documents_ids, [1, 3]);
+        assert_eq!(fired_rules, [S("cheap"), S("tv")]);
+
+        let SearchResult { documents_ids, fired_rules, .. } =
+            Search::new(&rtxn, &index).query("newest television").execute().unwrap();
+        assert_eq!(documents_ids, [3, 0, 1]);
+        assert_eq!(fired_rules, [S("newest")]);
+
+        let SearchResult { documents_ids, fired_rules, .. } =
+            Search::new(&rtxn, &index).query("remote").execute().unwrap();
+        assert_eq!(documents_ids, [2]);
+        assert!(fired_rules.is_empty());
+
+        let error = index
+            .update_settings(|settings| settings.set_query_rewrite_rules(vec![rule("blank", "  ", None)]))
+            .unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::InvalidQueryRewriteRule { .. })));
+
+        // the filters and the sorts of the rules only use filterable and sortable fields
+        let error = index
+            .update_settings(|settings| {
+                settings.set_query_rewrite_rules(vec![QueryRewriteRule {
+                    filter: Some(serde_json::json!({ "name": "television" })),
+                    ..rule("named", "named", None)
+                }])
+            })
+            .unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::InvalidQueryRewriteRule { ref id, .. }) if id == "named"));
+        let error = index
+            .update_settings(|settings| {
+                settings.set_query_rewrite_rules(vec![QueryRewriteRule {
+                    sort: Some(vec![S("tier:asc")]),
+                    ..rule("tiered", "tiered", None)
+                }])
+            })
+            .unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::InvalidQueryRewriteRule { ref id, .. }) if id == "tiered"));
+
+        // the stored rules are checked again when the filterable fields change
+        let error = index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("year") }))
+            .unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::InvalidQueryRewriteRule { ref id, .. }) if id == "cheap"));
+    }
+}
@@ -11,14 +11,15 @@ use crate::search::resolve_query_graph::{resolve_docids, resolve_docids_proximit
 use crate::search::utils::bit_set::BitSet;
 use crate::{FieldId, Result};
 use crate::score_details::{Attribute, ScoreDetails};
-use crate::search::ranking::attribute_cost::{MAX_ATTRIBUTE, paths_cost};
+use crate::search::ranking::attribute_cost::{MAX_ATTRIBUTE, attribute_ranks, paths_cost};
 use crate::search::ranking::dead_ends_cache::DeadEndsCache;
 use crate::search::utils::vec_map::VecMap;
 
 pub struct AttributeRule<'graph> {
     edge_docids: HashMap<Edge, RoaringBitmap>,
     path_visitor: PathVisitor<'graph, AttributeCost, SmallVec<[BitSet; MAX_ATTRIBUTE]>>,
-    cost_field_id_mapping: VecMap<FieldId>,
+    cost_field_id_mapping: Vec<Vec<FieldId>>,
+    rank_weights: Option<Vec<u16>>,
     cur_cost: RangeInclusive<usize>,
     candidates: RoaringBitmap,
     max_cost: usize
@@ -29,24 +30,19 @@ impl<'graph> AttributeRule<'graph> {
         let costs = paths_cost(graph, context)?;
         let max_cost = Self::max_cost(context, graph)?;
         let path_visitor = PathVisitor::new(graph, costs, None, DeadEndsCache::new(100));
-        let cost_field_id_mapping = if let Some(searchable_field) = context.searchable_fields_ids()?{
-            (0..).zip(searchable_field).collect::<VecMap<_>>()
-        } else {
-            (0..).zip(0..=max_cost as FieldId).collect::<VecMap<_>>()
-        };
-        Ok(AttributeRule{ edge_docids: HashMap::new(), cur_cost: max_cost+1..=max_cost, candidates: RoaringBitmap::new(), max_cost, path_visitor, cost_field_id_mapping })
+        let cost_field_id_mapping = attribute_ranks(context)?;
+        let weights = context.searchable_attribute_weights()?;
+        // the fields of a rank share the same weight
+        let rank_weights = (!weights.is_empty()).then(|| {
+            cost_field_id_mapping
+                .iter()
+                .map(|fields| fields.first().and_then(|field| weights.get(field).copied()).unwrap_or_default())
+                .collect()
+        });
+        Ok(AttributeRule{ edge_docids: HashMap::new(), cur_cost: max_cost+1..=max_cost, candidates: RoaringBitmap::new(), max_cost, path_visitor, cost_field_id_mapping, rank_weights })
     }
     pub fn max_cost(context: &mut impl Context, graph: &QueryGraph) -> Result<usize>{
-        let max_cost: usize = {
-            if let Some(attributes) = context
-                .searchable_fields_ids()?
-                .map(|x| x.len())
-            {
-                attributes.saturating_sub(1)
-            } else {
-                context.field_ids()?.ids().max().unwrap_or_default() as usize
-            }
-        };
+        let max_cost = attribute_ranks(context)?.len().saturating_sub(1);
 
         Ok((graph.query_words) * max_cost)
     }
@@ -54,6 +50,10 @@ impl<'graph> AttributeRule<'graph> {
 
 
 impl<'graph> RankingRule for AttributeRule<'graph> {
+    fn id(&self) -> String {
+        "attribute".to_string()
+    }
+
     fn start_iteration(&mut self, _ctx: &mut dyn Context, candidates: RoaringBitmap, allowed_paths: Option<HashSet<BitSet>>) -> Result<()> {
         self.candidates = candidates;
         self.path_visitor.set_allowed_paths(allowed_paths);
@@ -67,6 +67,7 @@ impl<'graph> RankingRule for AttributeRule<'graph> {
             let mut subpaths_docids: Vec<(Edge, RoaringBitmap)> = vec![];
             let mut good_paths = HashSet::new();
             let mut bucket = RoaringBitmap::new();
+            let mut bucket_weight = None;
             let query_graph = self.path_visitor.query_graph();
             let mut time = Instant::now();
             self.path_visitor.visit_paths(cost, |path, dead_ends_cache|{
@@ -74,6 +75,16 @@ impl<'graph> RankingRule for AttributeRule<'graph> {
                     return Ok(ControlFlow::Break(()));
                 }
 
+                let mut path_bitset = BitSet::new();
+                for edge in path{
+                    path_bitset.insert(edge.from);
+                }
+                path.last().map(|last| path_bitset.insert(last.to));
+                // the terms matching strategy may forbid the paths skipping some terms of the query
+                if !query_graph.is_allowed_path(&path_bitset) {
+                    return Ok(ControlFlow::Continue(()));
+                }
+
                 let idx_of_first_different_condition = {
                     let mut idx = 0;
                     for (&last_c, cur_c) in path.iter().zip(subpaths_docids.iter().map(|x| x.0)) {
@@ -110,12 +121,11 @@ impl<'graph> RankingRule for AttributeRule<'graph> {
                     return Ok(ControlFlow::Continue(()));
                 }
 
-                let mut path_bitset = BitSet::new();
-                for edge in path{
-                    path_bitset.insert(edge.from);
-                }
-                path.last().map(|last| path_bitset.insert(last.to));
                 good_paths.insert(path_bitset);
+                if let Some(rank_weights) = &self.rank_weights {
+                    let path_weight = path_weight(query_graph, path, rank_weights);
+                    bucket_weight = Some(bucket_weight.map_or(path_weight, |weight: u32| weight.max(path_weight)));
+                }
 
                 bucket |= &path_docids;
 
@@ -136,7 +146,8 @@ impl<'graph> RankingRule for AttributeRule<'graph> {
             Ok(RankingRuleOutput{
                 score: ScoreDetails::Attribute(Attribute{
                     attribute: cost as u32,
-                    max_attribute: self.max_cost as u32
+                    max_attribute: self.max_cost as u32,
+                    weight: bucket_weight,
                 }),
                 allowed_path: Some(good_paths),
                 candidates: bucket
@@ -148,6 +159,16 @@ impl<'graph> RankingRule for AttributeRule<'graph> {
 }
 
 
+/// Returns the sum of the weights of the attributes matched by each query word of a path.
+fn path_weight(graph: &QueryGraph, path: &[Edge], rank_weights: &[u16]) -> u32 {
+    path.iter()
+        .map(|edge| match &graph.nodes[edge.to].data {
+            NodeData::Term(term) => rank_weights[edge.cost] as u32 * term.position.clone().count() as u32,
+            NodeData::Start | NodeData::End => 0,
+        })
+        .sum()
+}
+
 fn visit_path_edge(
     ctx: &mut (impl Context + ?Sized),
     graph: &QueryGraph,
@@ -155,7 +176,7 @@ fn visit_path_edge(
     dead_ends_cache: &mut DeadEndsCache,
     edge_docids: &mut HashMap<Edge, RoaringBitmap>,
     subpath: &mut Vec<(Edge, RoaringBitmap)>,
-    mapping: &VecMap<Fid>,
+    mapping: &[Vec<Fid>],
     latest_edge: Edge,
 ) -> Result<bool> {
     let edge_docids = get_edge_docids(edge_docids, ctx, latest_edge, graph, mapping)?;
@@ -195,11 +216,14 @@ fn visit_path_edge(
     Ok(false)
 }
 
-fn resolve_edge(ctx: &mut (impl Context + ?Sized), edge: Edge, graph: &QueryGraph, mapping: &VecMap<Fid>) -> Result<RoaringBitmap>{
+fn resolve_edge(ctx: &mut (impl Context + ?Sized), edge: Edge, graph: &QueryGraph, mapping: &[Vec<Fid>]) -> Result<RoaringBitmap>{
     let right = &graph.nodes[edge.to].data;
     return match right {
         NodeData::Term(term) => {
-            let docids = resolve_fid_docids(term, ctx, mapping[edge.cost])?;
+            let mut docids = RoaringBitmap::new();
+            for &fid in &mapping[edge.cost] {
+                docids |= resolve_fid_docids(term, ctx, fid)?;
+            }
             Ok(docids)
         }
         (NodeData::Start | NodeData::End) => {
@@ -214,7 +238,7 @@ pub fn get_edge_docids<'s>(
     ctx: &mut (impl Context + ?Sized),
     edge: Edge,
     graph: &QueryGraph,
-    mapping: &VecMap<Fid>
+    mapping: &[Vec<Fid>]
 ) -> Result<&'s RoaringBitmap> {
     if cache.contains_key(&edge) {
         let docids = cache.get_mut(&edge).unwrap();
@@ -236,4 +260,86 @@ impl EdgeToCost for AttributeCost {
             NodeData::Term(term) => edge * term.position.clone().count()
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::index::tests::TempIndex;
+    use crate::score_details::ScoreDetails;
+    use crate::{Search, SearchResult};
+
+    #[test]
+    fn search_with_searchable_attribute_weights() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec!["body".into(), "title".into()]);
+                settings.set_searchable_attribute_weights(BTreeMap::from([
+                    ("title".to_string(), 5),
+                    ("body".to_string(), 1),
+                ]));
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "nothing", "body": "hello" },
+                { "id": 1, "title": "hello", "body": "nothing" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, document_scores, .. } =
+            Search::new(&rtxn, &index).query("hello").execute().unwrap();
+        assert_eq!(documents_ids, [1, 0]);
+        let weight = document_scores[0].iter().find_map(|score| match score {
+            ScoreDetails::Attribute(attribute) => attribute.weight,
+            _ => None,
+        });
+        assert_eq!(weight, Some(5));
+    }
+
+    #[test]
+    fn search_with_sparse_searchable_attribute_weights() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec!["body".into(), "tags".into(), "title".into()]);
+                settings.set_searchable_attribute_weights(BTreeMap::from([
+                    ("title".to_string(), 60000),
+                    ("tags".to_string(), 2),
+                    ("body".to_string(), 2),
+                ]));
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "nothing", "tags": "hello", "body": "nothing" },
+                { "id": 1, "title": "hello", "tags": "nothing", "body": "nothing" },
+                { "id": 2, "title": "nothing", "tags": "nothing", "body": "hello" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, document_scores, .. } =
+            Search::new(&rtxn, &index).query("hello").execute().unwrap();
+        assert_eq!(documents_ids, [1, 0, 2]);
+        let attributes: Vec<_> = document_scores
+            .iter()
+            .map(|scores| {
+                scores.iter().find_map(|score| match score {
+                    ScoreDetails::Attribute(attribute) => Some((attribute.max_attribute, attribute.weight)),
+                    _ => None,
+                })
+            })
+            .collect();
+        // the two distinct weights give two ranks, whatever the gap between them
+        assert_eq!(
+            attributes,
+            [Some((1, Some(60000))), Some((1, Some(2))), Some((1, Some(2)))]
+        );
+    }
+}
@@ -24,16 +24,7 @@ pub fn paths_cost(
 ) -> Result<VecMap<VecMap<SmallVec<[BitSet; MAX_ATTRIBUTE]>>>> {
     let mut visited = VecMap::with_capacity(graph.nodes.len());
     let time = Instant::now();
-    let max_cost: usize = {
-        if let Some(attributes) = context
-            .searchable_fields_ids()?
-            .map(|x| x.len())
-        {
-            attributes.saturating_sub(1)
-        } else {
-            context.field_ids()?.ids().max().unwrap_or_default() as usize
-        }
-    };
+    let max_cost = attribute_ranks(context)?.len().saturating_sub(1);
 
     let res = graph_traverse(graph.root, graph, context, max_cost, &mut visited)?;
 
@@ -92,6 +83,35 @@ fn graph_traverse<'search, 'cost, 'visited>(
     Ok(paths)
 }
 
+/// Returns the fields grouped by rank, the fields of the first rank are the most important ones.
+///
+/// Without weights, every field has its own rank given by its order in the searchable fields.
+/// Otherwise, a field is ranked by the number of distinct weights greater than its own,
+/// the fields sharing a weight share a rank and the fields without weight are the least important ones.
+pub fn attribute_ranks(context: &mut impl Context) -> Result<Vec<Vec<FieldId>>> {
+    let fields = match context.searchable_fields_ids()? {
+        Some(fields) => fields,
+        None => (0..=context.field_ids()?.ids().max().unwrap_or_default()).collect(),
+    };
+    let weights = context.searchable_attribute_weights()?;
+    if weights.is_empty() {
+        return Ok(fields.into_iter().map(|field| vec![field]).collect());
+    }
+
+    let weight = |field: &FieldId| weights.get(field).copied().unwrap_or_default();
+    let mut distinct_weights: Vec<u16> = fields.iter().map(weight).collect();
+    distinct_weights.sort_unstable_by(|left, right| right.cmp(left));
+    distinct_weights.dedup();
+    let mut ranks = vec![Vec::new(); distinct_weights.len()];
+    for field in fields {
+        let field_weight = weight(&field);
+        let rank = distinct_weights.binary_search_by(|probe| field_weight.cmp(probe)).unwrap_or_default();
+        ranks[rank].push(field);
+    }
+
+    Ok(ranks)
+}
+
 pub fn cost(node_id: usize, graph: &QueryGraph, attribute: usize) -> usize{
 
     match &graph.nodes[node_id].data {
@@ -1,81 +1,188 @@
+use std::cmp::Ordering;
 use std::ops::{BitAnd, BitXor};
 use std::time::Instant;
 use itertools::Itertools;
 use roaring::RoaringBitmap;
 use crate::Criterion;
-use crate::search::context::Context;
+use crate::search::context::{Context, Fid};
 use crate::search::query_graph::QueryGraph;
 use crate::search::ranking::proximity::ProximityRule;
 use crate::search::ranking::ranking_rule::{RankingRule, RankingRuleOutput};
-use crate::Result;
+use crate::{Result, TimeBudget, UserError};
+use crate::search::cursor::SearchCursor;
+use crate::search::explanation::{BucketExplanation, RankingRuleExplanation};
 use crate::score_details::ScoreDetails;
 use crate::search::ranking::attribute::AttributeRule;
 use crate::search::ranking::exactness::ExactnessRule;
 use crate::search::ranking::typos::TypoRule;
 use crate::search::ranking::words::WordsRule;
 
-pub fn bucket_sort(ctx: &mut impl Context, mut ranking_rules: Vec<Box<dyn RankingRule + '_>>, limit: u64, skip: u64, candidates: RoaringBitmap) -> Result<BucketSortOutput>{
+pub fn bucket_sort(ctx: &mut impl Context, mut ranking_rules: Vec<Box<dyn RankingRule + '_>>, distinct: Option<Fid>, limit: u64, skip: u64, candidates: RoaringBitmap, after: Option<&SearchCursor>, ranking_score_threshold: Option<f64>, time_budget: &TimeBudget, explain: bool) -> Result<BucketSortOutput>{
+    if after.is_some_and(|cursor| cursor.scores.len() != ranking_rules.len()) {
+        return Err(UserError::InvalidSearchCursor.into());
+    }
+    if limit == 0 && ranking_score_threshold.is_none() {
+        // no document is returned, the hits are only counted
+        let candidates = match distinct {
+            Some(fid) => ctx.distinct_universe(fid, &candidates)?,
+            None => candidates,
+        };
+        return Ok(BucketSortOutput{
+            candidates,
+            docids: Vec::new(),
+            scores: Vec::new(),
+            degraded: false,
+            explanation: explain.then(Vec::new),
+        })
+    }
     if ranking_rules.is_empty(){
+        let mut candidates = candidates;
+        if let Some(cursor) = after {
+            candidates.remove_range(..=cursor.docid);
+        }
+        let candidates = match distinct {
+            Some(fid) => distinct_bucket(ctx, fid, &candidates, None)?.0,
+            None => candidates,
+        };
         return Ok(BucketSortOutput{
             docids: candidates.iter().collect(),
             scores: vec![Vec::new(); candidates.len() as usize],
-            candidates
+            candidates,
+            degraded: false,
+            explanation: explain.then(Vec::new),
         })
     }
     let time = Instant::now();
 
     let mut limit = limit;
     let mut skip = skip;
+    let universe = candidates.clone();
     let mut visited = RoaringBitmap::new();
+    let mut excluded = RoaringBitmap::new();
     let mut score = Vec::new();
     let mut output = Vec::new();
+    let mut degraded = false;
+    let mut after = after;
+    let mut explanation = explain.then(|| {
+        ranking_rules.iter().map(|rule| RankingRuleExplanation{ id: rule.id(), buckets: Vec::new(), vector_strategies: Vec::new() }).collect()
+    });
 
     ranking_rules[0].start_iteration(ctx, candidates, None)?;
-    recursive_sort(0, ctx, &mut score, &mut ranking_rules, &mut limit, &mut skip, &mut output, &mut visited)?;
+    recursive_sort(0, ctx, &mut score, &mut ranking_rules, distinct, &mut limit, &mut skip, &mut output, &mut visited, &mut excluded, &mut after, ranking_score_threshold, time_budget, &mut degraded, &mut explanation)?;
+    if let Some(explanation) = &mut explanation {
+        for (rule_explanation, rule) in explanation.iter_mut().zip(&ranking_rules) {
+            rule_explanation.vector_strategies = rule.vector_strategies();
+        }
+    }
     println!("Sorted {:?} Docs {}", time.elapsed(), output.len());
 
     let (docids, scores) = output.into_iter().unzip();
 
-    let candidates = RoaringBitmap::from_iter(&docids);
+    // the documents sharing a distinct value with a hit aren't hits, with a threshold only the documents
+    // of the buckets that reached it are hits.
+    let mut candidates = match ranking_score_threshold {
+        Some(_) => visited,
+        None => universe,
+    };
+    candidates -= excluded;
+    // the buckets that weren't returned are deduplicated as a whole, a single document
+    // is counted for each distinct value.
+    if let Some(fid) = distinct {
+        candidates = ctx.distinct_universe(fid, &candidates)?;
+    }
 
-    Ok(BucketSortOutput{ candidates, docids, scores, })
+    Ok(BucketSortOutput{ candidates, docids, scores, degraded, explanation })
 
 }
 
-pub fn recursive_sort(mut current_rule: usize, ctx: &mut dyn Context, mut score: &mut Vec<ScoreDetails>, ranking_rule: &mut Vec<Box<dyn RankingRule + '_>>, mut limit: &mut u64, mut skip:&mut u64, output: &mut Vec<(u32, Vec<ScoreDetails>)>, mut visited: &mut RoaringBitmap) -> Result<()>{
+pub fn recursive_sort(mut current_rule: usize, ctx: &mut dyn Context, mut score: &mut Vec<ScoreDetails>, ranking_rule: &mut Vec<Box<dyn RankingRule + '_>>, distinct: Option<Fid>, mut limit: &mut u64, mut skip:&mut u64, output: &mut Vec<(u32, Vec<ScoreDetails>)>, mut visited: &mut RoaringBitmap, excluded: &mut RoaringBitmap, after: &mut Option<&SearchCursor>, ranking_score_threshold: Option<f64>, time_budget: &TimeBudget, degraded: &mut bool, explanation: &mut Option<Vec<RankingRuleExplanation>>) -> Result<()>{
 
     while let Some(mut bucket) = ranking_rule[current_rule].next_bucket(ctx)?{
         println!("{:?}", bucket);
-        if *limit == 0 {
+        // with a threshold the buckets after a full page are still scored, the hits are
+        // the documents reaching the threshold and not only the ones returned.
+        if *limit == 0 && ranking_score_threshold.is_none() {
             return Ok(());
         }
         let mut docids = &mut bucket.candidates;
         *docids -= &*visited;
+        if let Some(explanation) = explanation {
+            explanation[current_rule].buckets.push(BucketExplanation{ score: bucket.score.clone(), candidates: docids.len() });
+        }
+        if let Some(cursor) = *after {
+            match bucket.score.partial_cmp_bucket(&cursor.scores[current_rule]) {
+                // the whole bucket was returned before the cursor
+                Some(Ordering::Greater) => {
+                    *visited |= &*docids;
+                    continue
+                }
+                // the documents of the last bucket are returned by increasing ids
+                Some(Ordering::Equal) if current_rule == ranking_rule.len() - 1 => {
+                    *visited |= &*docids;
+                    docids.remove_range(..=cursor.docid);
+                    *after = None;
+                }
+                // the cursor is somewhere in the next ranking rules
+                Some(Ordering::Equal) => (),
+                _ => *after = None,
+            }
+        }
         let len = docids.len();
         if len == 0{
             continue
         }
+        if let Some(threshold) = ranking_score_threshold {
+            // The next ranking rules can only lower the score, none of the documents
+            // of this bucket can reach the threshold.
+            let bucket_score = ScoreDetails::global_score(score.iter().chain(std::iter::once(&bucket.score)));
+            if bucket_score < threshold {
+                continue
+            }
+        }
+        if time_budget.exceeded() {
+            // No time left to ask the next ranking rules, the bucket is returned as is.
+            *degraded = true;
+            score.push(bucket.score);
+            push_bucket(ctx, docids, score, distinct, limit, skip, output, visited, excluded)?;
+            // the parent ranking rules push the rest of their buckets with their own scores only
+            score.pop();
+            return Ok(());
+        }
         if *skip > 0 && len < *skip{
-            *visited |= &*docids;
-            *skip -= len;
+            match distinct {
+                Some(fid) => {
+                    let (kept, bucket_excluded) = distinct_bucket(ctx, fid, docids, None)?;
+                    *visited |= &*docids;
+                    *visited |= &bucket_excluded;
+                    *excluded |= bucket_excluded;
+                    *skip -= kept.len();
+                }
+                None => {
+                    *visited |= &*docids;
+                    *skip -= len;
+                }
+            }
 
             continue
         }
 
         score.push(bucket.score);
         if current_rule == ranking_rule.len() - 1{
-            *visited |= &*docids;
-            let docids_count = docids.len();
-            for docids in docids.iter().skip(*skip as usize).take(*limit as usize){
-                output.push((docids, score.clone()));
-            }
-            *limit = limit.saturating_sub(if docids_count < *skip { 0 } else {  docids_count - *skip });
-            *skip = if docids_count < *skip { *skip - docids_count } else { 0 };
+            push_bucket(ctx, docids, score, distinct, limit, skip, output, visited, excluded)?;
 
             score.pop();
         } else {
+            let mut universe = bucket.candidates.clone();
             ranking_rule[current_rule + 1].start_iteration(ctx, bucket.candidates, bucket.allowed_path)?;
-            recursive_sort(current_rule + 1, ctx, score, ranking_rule, limit, skip, output,visited)?;
+            recursive_sort(current_rule + 1, ctx, score, ranking_rule, distinct, limit, skip, output, visited, excluded, after, ranking_score_threshold, time_budget, degraded, explanation)?;
+            if *degraded {
+                // The next ranking rules ran out of time, what is left of this bucket
+                // is returned in the order of the document ids.
+                universe -= &*visited;
+                push_bucket(ctx, &mut universe, score, distinct, limit, skip, output, visited, excluded)?;
+                score.pop();
+                return Ok(());
+            }
         }
     }
     score.pop();
@@ -83,11 +190,55 @@ pub fn recursive_sort(mut current_rule: usize, ctx: &mut dyn Context, mut score:
     Ok(())
 }
 
+/// Appends the documents of a bucket to the output, in the order of their ids,
+/// honoring the distinct field, the number of documents to skip and the limit.
+fn push_bucket(ctx: &mut dyn Context, docids: &mut RoaringBitmap, score: &[ScoreDetails], distinct: Option<Fid>, limit: &mut u64, skip: &mut u64, output: &mut Vec<(u32, Vec<ScoreDetails>)>, visited: &mut RoaringBitmap, excluded: &mut RoaringBitmap) -> Result<()> {
+    *visited |= &*docids;
+    if let Some(fid) = distinct {
+        let (kept, bucket_excluded) = distinct_bucket(ctx, fid, docids, Some(*skip + *limit))?;
+        *visited |= &bucket_excluded;
+        *excluded |= bucket_excluded;
+        *docids = kept;
+    }
+    let docids_count = docids.len();
+    for docids in docids.iter().skip(*skip as usize).take(*limit as usize){
+        output.push((docids, score.to_vec()));
+    }
+    *limit = limit.saturating_sub(if docids_count < *skip { 0 } else {  docids_count - *skip });
+    *skip = if docids_count < *skip { *skip - docids_count } else { 0 };
+
+    Ok(())
+}
+
+/// Keeps only the first document of the bucket for each value of the distinct field.
+///
+/// Returns the kept documents and the documents that share a value with one of them,
+/// the latter must never be returned afterward. Stops once `max` documents are kept.
+fn distinct_bucket(ctx: &mut dyn Context, fid: Fid, docids: &RoaringBitmap, max: Option<u64>) -> Result<(RoaringBitmap, RoaringBitmap)> {
+    let mut kept = RoaringBitmap::new();
+    let mut excluded = RoaringBitmap::new();
+    for docid in docids {
+        if max.is_some_and(|max| kept.len() >= max) {
+            break
+        }
+        if excluded.contains(docid) {
+            continue
+        }
+        kept.insert(docid);
+        excluded |= ctx.distinct_docids(fid, docid)?;
+    }
+    excluded -= &kept;
+
+    Ok((kept, excluded))
+}
+
 #[derive(Debug)]
 pub struct BucketSortOutput {
     pub candidates: RoaringBitmap,
     pub docids: Vec<u32>,
     pub scores: Vec<Vec<ScoreDetails>>,
+    pub degraded: bool,
+    pub explanation: Option<Vec<RankingRuleExplanation>>,
 }
 
 
@@ -100,6 +251,13 @@ mod tests {
     use analyzer::analyzer::Analyzer;
     use crate::search::ranking::ranking_rule::get_ranking_rules_for_query_graph_search;
     use super::*;
+    use big_s::S;
+    use roaring::RoaringBitmap;
+
+    use crate::error::Error;
+    use crate::index::tests::TempIndex;
+    use crate::score_details::ScoreDetails;
+    use crate::{Criterion, Search, SearchResult, TimeBudget};
 
     #[test]
     fn bucket_sort_test() {
@@ -117,10 +275,250 @@ mod tests {
         println!("Graph build {:?}", elapsed);
         let time = Instant::now();
         let candidates = context.all_docids().unwrap();
-        let ranking_rules = get_ranking_rules_for_query_graph_search(&mut context, &None, &query_graph).unwrap();
-        let mut costs = bucket_sort(&mut context, ranking_rules,  10000, 600, candidates).unwrap();
+        let ranking_rules = get_ranking_rules_for_query_graph_search(&mut context, &None, &query_graph, &TimeBudget::max()).unwrap();
+        let mut costs = bucket_sort(&mut context, ranking_rules, None, 10000, 600, candidates, None, None, &TimeBudget::max(), false).unwrap();
         let elapsed = time.elapsed();
         println!("Graph cost {:?}", elapsed);
         //println!("{:#?}", costs);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn search_with_distinct_field() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "red shirt", "product_group": "shirt" },
+                { "id": 1, "name": "blue shirt", "product_group": "shirt" },
+                { "id": 2, "name": "red hat", "product_group": "hat" },
+                { "id": 3, "name": "red scarf" },
+            ]))
+            .unwrap();
+
+        // the distinct field must exist or be filterable
+        let err = index.update_settings(|settings| settings.set_distinct_field(S("unknown"))).unwrap_err();
+        assert!(matches!(err, Error::UserError(crate::UserError::InvalidDistinctAttribute { .. })));
+        index.update_settings(|settings| settings.set_distinct_field(S("product_group"))).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.distinct_field(&rtxn).unwrap(), Some("product_group"));
+        assert!(index.user_defined_faceted_fields(&rtxn).unwrap().contains("product_group"));
+
+        let SearchResult { documents_ids, candidates, .. } =
+            Search::new(&rtxn, &index).execute().unwrap();
+        assert_eq!(documents_ids.len(), 3);
+        assert_eq!(candidates.len(), 3);
+
+        // the total hits are deduplicated beyond the returned page
+        let SearchResult { documents_ids, candidates, .. } =
+            Search::new(&rtxn, &index).limit(1).execute().unwrap();
+        assert_eq!(documents_ids.len(), 1);
+        assert_eq!(candidates.len(), 3);
+
+        // with a query, only the documents matching it are counted
+        let SearchResult { documents_ids, candidates, .. } =
+            Search::new(&rtxn, &index).query("red").limit(1).execute().unwrap();
+        assert_eq!(documents_ids.len(), 1);
+        assert_eq!(candidates, RoaringBitmap::from_iter([0, 2, 3]));
+        let SearchResult { documents_ids, candidates, .. } =
+            Search::new(&rtxn, &index).query("shirt").execute().unwrap();
+        assert_eq!(documents_ids, [0]);
+        assert_eq!(candidates, RoaringBitmap::from_iter([0]));
+
+        // the distinct attribute given at search time must be filterable
+        let err = Search::new(&rtxn, &index).distinct(S("name")).execute().unwrap_err();
+        assert!(matches!(err, Error::UserError(crate::UserError::InvalidDistinctAttribute { .. })));
+    }
+
+    #[test]
+    fn search_with_distinct_field_counts_the_hits_of_every_bucket() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_distinct_field(S("product_group"));
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "red shirt", "product_group": "shirt" },
+                { "id": 1, "name": "blue shirt", "product_group": "shirt" },
+                { "id": 2, "name": "red hat", "product_group": "hat" },
+                { "id": 3, "name": "red cap", "product_group": "hat" },
+                { "id": 4, "name": "red scarf" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { candidates: all_candidates, .. } =
+            Search::new(&rtxn, &index).query("red shirt").execute().unwrap();
+        assert_eq!(all_candidates, RoaringBitmap::from_iter([0, 2, 4]));
+
+        // the bucket of the documents matching only `red` is never sorted,
+        // the hats are still counted once.
+        let SearchResult { documents_ids, candidates, .. } =
+            Search::new(&rtxn, &index).query("red shirt").limit(1).execute().unwrap();
+        assert_eq!(documents_ids, [0]);
+        assert_eq!(candidates, all_candidates);
+
+        let SearchResult { documents_ids, candidates, .. } =
+            Search::new(&rtxn, &index).query("red shirt").limit(0).execute().unwrap();
+        assert!(documents_ids.is_empty());
+        assert_eq!(candidates.len(), 3);
+    }
+
+    #[test]
+    fn search_degrades_when_time_budget_is_exceeded() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "hello world" },
+                { "id": 1, "name": "hello" },
+                { "id": 2, "name": "world hello" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, degraded, .. } =
+            Search::new(&rtxn, &index).query("hello world").execute().unwrap();
+        assert!(!degraded);
+        assert_eq!(documents_ids.len(), 3);
+
+        let SearchResult { documents_ids, degraded, .. } = Search::new(&rtxn, &index)
+            .query("hello world")
+            .time_budget(TimeBudget::max().with_stop_after(0))
+            .execute()
+            .unwrap();
+        assert!(degraded);
+        assert!(!documents_ids.is_empty());
+    }
+
+    #[test]
+    fn degraded_search_keeps_the_scores_of_each_ranking_rule() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "hello world" },
+                { "id": 1, "name": "hallo world" },
+                { "id": 2, "name": "hello" },
+            ]))
+            .unwrap();
+
+        // the budget runs out on the first bucket of the typo rule, inside the first bucket of the words rule.
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, document_scores, degraded, .. } = Search::new(&rtxn, &index)
+            .query("hello world")
+            .time_budget(TimeBudget::max().with_stop_after(1))
+            .execute()
+            .unwrap();
+        assert!(degraded);
+        assert_eq!(documents_ids, [0, 1]);
+
+        // the document of the typo bucket is scored by both rules, the rest of the words bucket by the words rule only.
+        assert_eq!(document_scores[0].len(), 2);
+        assert!(matches!(document_scores[0][0], ScoreDetails::Words(_)));
+        assert!(matches!(document_scores[0][1], ScoreDetails::Typo(_)));
+        assert_eq!(document_scores[1].len(), 1);
+        assert!(matches!(document_scores[1][0], ScoreDetails::Words(_)));
+    }
+
+    #[test]
+    fn search_with_ranking_score_threshold() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "hello world" },
+                { "id": 1, "name": "hello" },
+                { "id": 2, "name": "world" },
+                { "id": 3, "name": "hello world" },
+                { "id": 4, "name": "hello world" },
+                { "id": 5, "name": "hello wrld" },
+            ]))
+            .unwrap();
+        index
+            .update_settings(|settings| {
+                settings.set_criteria(vec![Criterion::Words, Criterion::Typo]);
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, document_scores, candidates, .. } = Search::new(&rtxn, &index)
+            .query("hello world")
+            .ranking_score_threshold(0.9)
+            .execute()
+            .unwrap();
+        assert_eq!(documents_ids, [0, 3, 4]);
+        for scores in &document_scores {
+            assert!(ScoreDetails::global_score(scores.iter()) >= 0.9);
+        }
+        // the documents matching a single word are cut by the words rule, the last document
+        // matches both words but is cut by the typo rule.
+        assert_eq!(candidates, RoaringBitmap::from_iter([0, 3, 4]));
+
+        // the total counts all the documents above the threshold, not only the returned page
+        let SearchResult { documents_ids, candidates, .. } = Search::new(&rtxn, &index)
+            .query("hello world")
+            .ranking_score_threshold(0.9)
+            .limit(1)
+            .execute()
+            .unwrap();
+        assert_eq!(documents_ids, [0]);
+        assert_eq!(candidates.len(), 3);
+    }
+
+    #[test]
+    fn search_with_ranking_score_threshold_counts_the_hits_of_every_bucket() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "hello world" },
+                { "id": 1, "name": "hello" },
+                { "id": 2, "name": "hello big world" },
+                { "id": 3, "name": "hello big brave new world" },
+                { "id": 4, "name": "world" },
+            ]))
+            .unwrap();
+        index
+            .update_settings(|settings| {
+                settings.set_criteria(vec![Criterion::Words, Criterion::Proximity]);
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { candidates: all_candidates, .. } = Search::new(&rtxn, &index)
+            .query("hello world")
+            .ranking_score_threshold(0.55)
+            .execute()
+            .unwrap();
+        // the documents matching both words are in different proximity buckets
+        assert_eq!(all_candidates, RoaringBitmap::from_iter([0, 2, 3]));
+
+        // the page is full after the first bucket, the next ones are still counted
+        let SearchResult { documents_ids, candidates, .. } = Search::new(&rtxn, &index)
+            .query("hello world")
+            .ranking_score_threshold(0.55)
+            .limit(1)
+            .execute()
+            .unwrap();
+        assert_eq!(documents_ids, [0]);
+        assert_eq!(candidates, all_candidates);
+
+        let SearchResult { documents_ids, candidates, .. } = Search::new(&rtxn, &index)
+            .query("hello world")
+            .ranking_score_threshold(0.55)
+            .limit(0)
+            .execute()
+            .unwrap();
+        assert!(documents_ids.is_empty());
+        assert_eq!(candidates, all_candidates);
+    }
+}
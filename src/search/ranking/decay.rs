@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+
+use heed::types::Str;
+use heed::{BytesDecode, RoTxn};
+use roaring::RoaringBitmap;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{Date, OffsetDateTime};
+
+use crate::heed_codec::facet::OrderedF64Codec;
+use crate::score_details::{Decay, ScoreDetails};
+use crate::search::context::Context;
+use crate::search::ranking::ranking_rule::{RankingRule, RankingRuleOutput};
+use crate::search::utils::bit_set::BitSet;
+use crate::{DecayCriterion, FieldId, Result};
+
+/// Sorts the documents by the score a decay function gives to the distance between
+/// the value of a numeric facet and an origin.
+///
+/// The values with the same score are returned in the same bucket, the documents
+/// without a value are returned last. The string values are parsed as dates, either
+/// RFC 3339 date-times or `YYYY-MM-DD` dates, and compared as Unix timestamps in seconds,
+/// the strings that aren't dates are ignored.
+pub struct DecayRule<'ctx> {
+    txn: &'ctx RoTxn<'ctx>,
+    decay: DecayCriterion,
+    field_id: Option<FieldId>,
+    origin: f64,
+    allowed_paths: Option<HashSet<BitSet>>,
+    buckets: std::vec::IntoIter<(Option<f64>, f64, RoaringBitmap)>,
+}
+
+impl<'ctx> DecayRule<'ctx> {
+    pub fn new(ctx: &impl Context<'ctx>, decay: DecayCriterion) -> Result<Self> {
+        let field_id = ctx.field_ids()?.id(&decay.field);
+        let origin = decay.origin.unwrap_or_else(|| OffsetDateTime::now_utc().unix_timestamp() as f64);
+
+        Ok(Self {
+            txn: ctx.txn(),
+            decay,
+            field_id,
+            origin,
+            allowed_paths: None,
+            buckets: Vec::new().into_iter(),
+        })
+    }
+}
+
+impl<'ctx> RankingRule for DecayRule<'ctx> {
+    fn id(&self) -> String {
+        format!("decay({})", self.decay.field)
+    }
+
+    fn start_iteration(&mut self, ctx: &mut dyn Context, candidates: RoaringBitmap, allowed_paths: Option<HashSet<BitSet>>) -> Result<()> {
+        let mut values = Vec::new();
+        if let Some(field_id) = self.field_id {
+            for result in ctx.ascending_number_sort(self.txn, field_id, candidates.clone())? {
+                let (docids, bytes) = result?;
+                let value = OrderedF64Codec::bytes_decode(bytes).expect("some number");
+                values.push((self.decay.score(value, self.origin), (value - self.origin).abs(), value, docids));
+            }
+            for result in ctx.ascending_string_sort(self.txn, field_id, candidates.clone())? {
+                let (docids, bytes) = result?;
+                let Some(value) = Str::bytes_decode(bytes).ok().and_then(parse_date) else { continue };
+                values.push((self.decay.score(value, self.origin), (value - self.origin).abs(), value, docids));
+            }
+        }
+        // the best scores first and, for the same score, the values closest to the origin
+        values.sort_by(|(left_score, left_distance, ..), (right_score, right_distance, ..)| {
+            right_score.total_cmp(left_score).then(left_distance.total_cmp(right_distance))
+        });
+
+        let mut seen = RoaringBitmap::new();
+        let mut buckets: Vec<(Option<f64>, f64, RoaringBitmap)> = Vec::new();
+        for (score, _, value, mut docids) in values {
+            // a document with several values is returned with its best one
+            docids -= &seen;
+            seen |= &docids;
+            match buckets.last_mut() {
+                Some((_, last_score, last_docids)) if *last_score == score => *last_docids |= docids,
+                _ => buckets.push((Some(value), score, docids)),
+            }
+        }
+        let missing = candidates - seen;
+        if !missing.is_empty() {
+            buckets.push((None, 0.0, missing));
+        }
+
+        self.buckets = buckets.into_iter();
+        self.allowed_paths = allowed_paths;
+
+        Ok(())
+    }
+
+    fn next_bucket(&mut self, _ctx: &mut dyn Context) -> Result<Option<RankingRuleOutput>> {
+        Ok(self.buckets.next().map(|(value, score, candidates)| RankingRuleOutput {
+            score: ScoreDetails::Decay(Decay { field_name: self.decay.field.clone(), value, score }),
+            allowed_path: self.allowed_paths.clone(),
+            candidates,
+        }))
+    }
+}
+
+/// Returns the Unix timestamp in seconds of a date facet.
+///
+/// The facet strings are lowercased when indexed, the `T` and `Z` separators are restored before parsing.
+fn parse_date(value: &str) -> Option<f64> {
+    let value = value.to_uppercase();
+    if let Ok(datetime) = OffsetDateTime::parse(&value, &Rfc3339) {
+        return Some(datetime.unix_timestamp() as f64);
+    }
+    let date = Date::parse(&value, format_description!("[year]-[month]-[day]")).ok()?;
+    Some(date.midnight().assume_utc().unix_timestamp() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use crate::index::tests::TempIndex;
+    use crate::score_details::ScoreDetails;
+    use crate::{Criterion, DecayCriterion, DecayFunction, Search, SearchResult};
+
+    #[test]
+    fn search_with_decay_criterion() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_criteria(vec![
+                    Criterion::Words,
+                    Criterion::Decay(DecayCriterion {
+                        field: S("published_at"),
+                        function: DecayFunction::Gauss,
+                        scale: 10.0,
+                        offset: 1.0,
+                        decay: 0.5,
+                        origin: Some(100.0),
+                    }),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "hello", "published_at": 80 },
+                { "id": 1, "name": "hello" },
+                { "id": 2, "name": "hello", "published_at": 111 },
+                { "id": 3, "name": "hello", "published_at": 99 },
+                { "id": 4, "name": "hello", "published_at": 100 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, document_scores, .. } =
+            Search::new(&rtxn, &index).query("hello").execute().unwrap();
+        assert_eq!(documents_ids, [3, 4, 2, 0, 1]);
+        match &document_scores[2][1] {
+            ScoreDetails::Decay(decay) => {
+                assert_eq!(decay.value, Some(111.0));
+                assert!((decay.score - 0.5).abs() < 1e-9);
+            }
+            details => panic!("unexpected score details {details:?}"),
+        }
+    }
+
+    #[test]
+    fn search_with_decay_criterion_on_dates() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_criteria(vec![
+                    Criterion::Words,
+                    Criterion::Decay(DecayCriterion {
+                        field: S("published_at"),
+                        function: DecayFunction::Exp,
+                        scale: 86400.0,
+                        offset: 0.0,
+                        decay: 0.5,
+                        // 2024-01-10T00:00:00Z
+                        origin: Some(1704844800.0),
+                    }),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "hello", "published_at": "2024-01-01" },
+                { "id": 1, "name": "hello", "published_at": "2024-01-10T00:00:00Z" },
+                { "id": 2, "name": "hello", "published_at": "2024-01-09T12:00:00+00:00" },
+                { "id": 3, "name": "hello", "published_at": "yesterday" },
+                { "id": 4, "name": "hello" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, document_scores, .. } =
+            Search::new(&rtxn, &index).query("hello").execute().unwrap();
+        // the strings that aren't dates are considered missing
+        assert_eq!(documents_ids, [1, 2, 0, 3, 4]);
+        match &document_scores[1][1] {
+            ScoreDetails::Decay(decay) => {
+                assert_eq!(decay.value, Some(1704801600.0));
+                assert!((decay.score - 0.5f64.sqrt()).abs() < 1e-9);
+            }
+            details => panic!("unexpected score details {details:?}"),
+        }
+    }
+}
@@ -27,7 +27,7 @@ impl<'graph> ExactnessRule<'graph> {
         let paths = paths_cost::<ExactnessCost>(graph, context);
         let max_cost = Self::max_cost(context, graph)?;
         let mut costs = VecMap::with_capacity(max_cost as usize);
-        for (path, cost) in paths{
+        for (path, cost) in paths.into_iter().filter(|(path, _)| graph.is_allowed_path(path)){
             costs.get_or_insert_with(cost, || HashSet::new()).insert(path);
         }
 
@@ -40,6 +40,9 @@ impl<'graph> ExactnessRule<'graph> {
 
 
 impl RankingRule for ExactnessRule<'_> {
+    fn id(&self) -> String {
+        "exactness".to_string()
+    }
 
     fn start_iteration(&mut self, _ctx: &mut dyn Context, candidates: RoaringBitmap, allowed_paths: Option<HashSet<BitSet>>) -> Result<()> {
         self.candidates = candidates;
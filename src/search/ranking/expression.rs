@@ -0,0 +1,200 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+use heed::{BytesDecode, RoTxn};
+use ordered_float::OrderedFloat;
+use roaring::RoaringBitmap;
+
+use crate::heed_codec::facet::OrderedF64Codec;
+use crate::{FieldId, Result, TimeBudget};
+use crate::ranking_expression::RankingExpression;
+use crate::score_details::{Expression, ScoreDetails};
+use crate::search::context::Context;
+use crate::search::ranking::ranking_rule::{RankingRule, RankingRuleOutput};
+use crate::search::utils::bit_set::BitSet;
+use crate::CriterionError;
+
+/// Sorts the documents by the descending value of a ranking expression,
+/// the documents with the same value are returned in the same bucket.
+///
+/// The expression is evaluated once for each combination of the values of its fields,
+/// the documents are grouped by these values from the facet databases.
+pub struct ExpressionRule<'ctx> {
+    txn: &'ctx RoTxn<'ctx>,
+    expression: String,
+    formula: RankingExpression,
+    fields: Vec<(String, Option<FieldId>)>,
+    time_budget: TimeBudget,
+    allowed_paths: Option<HashSet<BitSet>>,
+    buckets: std::vec::IntoIter<(Option<f64>, RoaringBitmap)>,
+}
+
+impl<'ctx> ExpressionRule<'ctx> {
+    pub fn new(ctx: &impl Context<'ctx>, expression: String, time_budget: TimeBudget) -> Result<Self> {
+        let formula = RankingExpression::from_str(&expression)
+            .map_err(|error| CriterionError::InvalidExpression { expression: expression.clone(), error })?;
+        let fields_ids_map = ctx.field_ids()?;
+        let fields = formula
+            .fields()
+            .into_iter()
+            .map(|field| (field.to_string(), fields_ids_map.id(field)))
+            .collect();
+
+        Ok(Self {
+            txn: ctx.txn(),
+            expression,
+            formula,
+            fields,
+            time_budget,
+            allowed_paths: None,
+            buckets: Vec::new().into_iter(),
+        })
+    }
+
+    /// Returns the candidates grouped by the values of the fields of the expression,
+    /// or `None` when the time budget is exceeded.
+    fn group_by_values(&self, ctx: &mut dyn Context, candidates: &RoaringBitmap) -> Result<Option<Vec<(Vec<f64>, RoaringBitmap)>>> {
+        let mut groups = vec![(Vec::with_capacity(self.fields.len()), candidates.clone())];
+        for (_, field_id) in &self.fields {
+            let mut field_values = Vec::new();
+            let mut seen = RoaringBitmap::new();
+            if let Some(field_id) = field_id {
+                for result in ctx.ascending_number_sort(self.txn, *field_id, candidates.clone())? {
+                    if self.time_budget.exceeded() {
+                        return Ok(None);
+                    }
+                    let (mut docids, bytes) = result?;
+                    // a document with several values is evaluated with its smallest one
+                    docids -= &seen;
+                    seen |= &docids;
+                    if !docids.is_empty() {
+                        field_values.push((OrderedF64Codec::bytes_decode(bytes).expect("some number"), docids));
+                    }
+                }
+            }
+            // a document without a value for a field evaluates it as zero
+            let missing = candidates - &seen;
+            if !missing.is_empty() {
+                field_values.push((0.0, missing));
+            }
+
+            let mut refined = Vec::with_capacity(groups.len());
+            for (values, mut group) in groups {
+                for (value, docids) in &field_values {
+                    if group.is_empty() {
+                        break;
+                    }
+                    if self.time_budget.exceeded() {
+                        return Ok(None);
+                    }
+                    let docids = &group & docids;
+                    if !docids.is_empty() {
+                        group -= &docids;
+                        let mut values = values.clone();
+                        values.push(*value);
+                        refined.push((values, docids));
+                    }
+                }
+            }
+            groups = refined;
+        }
+
+        Ok(Some(groups))
+    }
+}
+
+impl<'ctx> RankingRule for ExpressionRule<'ctx> {
+    fn id(&self) -> String {
+        format!("expression({})", self.expression)
+    }
+
+    fn start_iteration(&mut self, ctx: &mut dyn Context, candidates: RoaringBitmap, allowed_paths: Option<HashSet<BitSet>>) -> Result<()> {
+        self.allowed_paths = allowed_paths;
+        let Some(groups) = self.group_by_values(ctx, &candidates)? else {
+            // the search is degraded, the candidates are returned unsorted
+            self.buckets = vec![(None, candidates)].into_iter();
+            return Ok(());
+        };
+
+        let mut values: BTreeMap<Reverse<OrderedFloat<f64>>, RoaringBitmap> = BTreeMap::new();
+        let mut not_a_number = RoaringBitmap::new();
+        for (field_values, docids) in groups {
+            let value = self.formula.evaluate(&|field| {
+                self.fields
+                    .iter()
+                    .position(|(name, _)| name == field)
+                    .map_or(0.0, |i| field_values[i])
+            });
+            if value.is_nan() {
+                not_a_number |= docids;
+            } else {
+                *values.entry(Reverse(OrderedFloat(value))).or_default() |= docids;
+            }
+        }
+
+        let mut buckets: Vec<_> = values.into_iter().map(|(Reverse(OrderedFloat(value)), docids)| (Some(value), docids)).collect();
+        if !not_a_number.is_empty() {
+            buckets.push((None, not_a_number));
+        }
+        self.buckets = buckets.into_iter();
+
+        Ok(())
+    }
+
+    fn next_bucket(&mut self, _ctx: &mut dyn Context) -> Result<Option<RankingRuleOutput>> {
+        Ok(self.buckets.next().map(|(value, candidates)| RankingRuleOutput {
+            score: ScoreDetails::Expression(Expression { expression: self.expression.clone(), value }),
+            allowed_path: self.allowed_paths.clone(),
+            candidates,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::index::tests::TempIndex;
+    use crate::score_details::ScoreDetails;
+    use crate::{Criterion, Search, SearchResult, UserError};
+
+    #[test]
+    fn search_with_ranking_expression() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_criteria(vec![
+                    Criterion::Words,
+                    Criterion::Expression("log(1 + popularity) * rating".to_string()),
+                ]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "hello", "popularity": 10, "rating": 1 },
+                { "id": 1, "name": "hello", "popularity": 100, "rating": 2 },
+                { "id": 2, "name": "hello", "popularity": 1000 },
+                { "id": 3, "name": "hello", "popularity": 0, "rating": 5 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, document_scores, .. } =
+            Search::new(&rtxn, &index).query("hello").execute().unwrap();
+        assert_eq!(documents_ids, [1, 0, 2, 3]);
+        match &document_scores[0][1] {
+            ScoreDetails::Expression(expression) => {
+                assert_eq!(expression.value, Some(101f64.ln() * 2.0))
+            }
+            details => panic!("unexpected score details {details:?}"),
+        }
+
+        let error = index
+            .update_settings(|settings| {
+                settings.set_criteria(vec![Criterion::Expression("log(popularity".to_string())]);
+            })
+            .unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::CriterionError(_))));
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+
+use roaring::RoaringBitmap;
+
+use crate::score_details::{self, ScoreDetails};
+use crate::search::context::Context;
+use crate::search::ranking::ranking_rule::{RankingRule, RankingRuleOutput};
+use crate::search::utils::bit_set::BitSet;
+use crate::{lat_lng_to_xyz, DocumentId, Result};
+
+/// Sorts the documents by the distance between their `_geo` point and a target point.
+///
+/// The documents sharing the same point are returned in the same bucket, the documents
+/// without a point are returned last.
+pub struct GeoSortRule {
+    point: [f64; 2],
+    ascending: bool,
+    /// The points of all the documents, sorted in the order of the rule the first time they are needed.
+    sorted_points: Option<Vec<(DocumentId, [f64; 2])>>,
+    allowed_paths: Option<HashSet<BitSet>>,
+    buckets: std::vec::IntoIter<(Option<[f64; 2]>, RoaringBitmap)>,
+}
+
+impl GeoSortRule {
+    pub fn new(point: [f64; 2], ascending: bool) -> Self {
+        Self { point, ascending, sorted_points: None, allowed_paths: None, buckets: Vec::new().into_iter() }
+    }
+
+    fn sorted_points(&mut self, ctx: &mut dyn Context) -> Result<&[(DocumentId, [f64; 2])]> {
+        if self.sorted_points.is_none() {
+            let mut points = Vec::new();
+            if let Some(rtree) = ctx.geo_rtree()? {
+                // the points are iterated from the closest to the farthest of the target point
+                points.extend(rtree.nearest_neighbor_iter(&lat_lng_to_xyz(&self.point)).map(|point| point.data));
+                if !self.ascending {
+                    points.reverse();
+                }
+            }
+            self.sorted_points = Some(points);
+        }
+
+        Ok(self.sorted_points.as_deref().unwrap_or_default())
+    }
+}
+
+impl RankingRule for GeoSortRule {
+    fn id(&self) -> String {
+        let [lat, lng] = self.point;
+        format!("_geoPoint({lat}, {lng}):{}", if self.ascending { "asc" } else { "desc" })
+    }
+
+    fn start_iteration(&mut self, ctx: &mut dyn Context, candidates: RoaringBitmap, allowed_paths: Option<HashSet<BitSet>>) -> Result<()> {
+        let mut buckets: Vec<(Option<[f64; 2]>, RoaringBitmap)> = Vec::new();
+        let mut seen = RoaringBitmap::new();
+        // the R-tree is only traversed once, the next iterations reuse its sorted points
+        for &(docid, point) in self.sorted_points(ctx)? {
+            if !candidates.contains(docid) {
+                continue;
+            }
+            seen.insert(docid);
+            match buckets.last_mut() {
+                Some((Some(last_point), docids)) if *last_point == point => {
+                    docids.insert(docid);
+                }
+                _ => buckets.push((Some(point), RoaringBitmap::from_iter([docid]))),
+            }
+            if seen.len() == candidates.len() {
+                break;
+            }
+        }
+        let missing = candidates - seen;
+        if !missing.is_empty() {
+            buckets.push((None, missing));
+        }
+
+        self.buckets = buckets.into_iter();
+        self.allowed_paths = allowed_paths;
+
+        Ok(())
+    }
+
+    fn next_bucket(&mut self, _ctx: &mut dyn Context) -> Result<Option<RankingRuleOutput>> {
+        Ok(self.buckets.next().map(|(value, candidates)| RankingRuleOutput {
+            score: ScoreDetails::GeoSort(score_details::GeoSort {
+                target_point: self.point,
+                ascending: self.ascending,
+                value,
+            }),
+            allowed_path: self.allowed_paths.clone(),
+            candidates,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+    use roaring::RoaringBitmap;
+
+    use crate::error::Error;
+    use crate::index::tests::TempIndex;
+    use crate::search::facet::Filter;
+    use crate::{AscDesc, Member, Search, SearchResult, UserError};
+
+    #[test]
+    fn search_with_geo() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("_geo") });
+                settings.set_sortable_fields(hashset! { S("_geo") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "paris", "_geo": { "lat": 48.8566, "lng": 2.3522 } },
+                { "id": 1, "name": "lyon", "_geo": { "lat": 45.764, "lng": "4.8357" } },
+                { "id": 2, "name": "london", "_geo": { "lat": 51.5074, "lng": -0.1278 } },
+                { "id": 3, "name": "nowhere" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.geo_faceted_documents_ids(&rtxn).unwrap(), RoaringBitmap::from_iter([0, 1, 2]));
+
+        let filter = Filter::from_json(&serde_json::json!({ "_geoRadius": [48.8566, 2.3522, 400000.0] }))
+            .unwrap()
+            .unwrap();
+        let SearchResult { documents_ids, .. } = Search::new(&rtxn, &index).filter(filter).execute().unwrap();
+        assert_eq!(documents_ids, [0, 1]);
+
+        let filter = Filter::from_json(&serde_json::json!({ "_geoBoundingBox": [[52.0, 3.0], [48.0, -1.0]] }))
+            .unwrap()
+            .unwrap();
+        let SearchResult { documents_ids, .. } = Search::new(&rtxn, &index).filter(filter).execute().unwrap();
+        assert_eq!(documents_ids, [0, 2]);
+
+        let SearchResult { documents_ids, .. } = Search::new(&rtxn, &index)
+            .sort_criteria(vec![AscDesc::Asc(Member::Geo([51.5074, -0.1278]))])
+            .execute()
+            .unwrap();
+        assert_eq!(documents_ids, [2, 0, 1, 3]);
+
+        let SearchResult { documents_ids, .. } = Search::new(&rtxn, &index)
+            .sort_criteria(vec![AscDesc::Desc(Member::Geo([51.5074, -0.1278]))])
+            .execute()
+            .unwrap();
+        assert_eq!(documents_ids, [1, 0, 2, 3]);
+
+        let error = Filter::from_json(&serde_json::json!({ "_geoRadius": [91.0, 2.3522, 10.0] }))
+            .and_then(|filter| Search::new(&rtxn, &index).filter(filter.unwrap()).execute());
+        assert!(matches!(error, Err(Error::UserError(UserError::InvalidFilter(_)))));
+    }
+}
@@ -8,8 +8,12 @@ mod attribute_cost;
 mod attribute;
 mod typos;
 mod exactness;
-mod words;
-mod vector_sort;
+pub mod words;
+pub mod vector_sort;
 mod sort;
+mod geo_sort;
+mod random_sort;
+mod expression;
+mod decay;
 mod dead_ends_cache;
 mod test;
@@ -36,6 +36,10 @@ impl<'graph> ProximityRule<'graph> {
 
 
 impl<'graph> RankingRule for ProximityRule<'graph> {
+    fn id(&self) -> String {
+        "proximity".to_string()
+    }
+
     fn start_iteration(&mut self, _ctx: &mut dyn Context, candidates: RoaringBitmap, allowed_paths: Option<HashSet<BitSet>>) -> Result<()> {
         self.candidates = candidates;
         self.path_visitor.set_allowed_paths(allowed_paths);
@@ -58,6 +62,16 @@ impl<'graph> RankingRule for ProximityRule<'graph> {
                     return Ok(ControlFlow::Break(()));
                 }
 
+                let mut path_bitset = BitSet::new();
+                for edge in path{
+                    path_bitset.insert(edge.from);
+                }
+                path.last().map(|last| path_bitset.insert(last.to));
+                // the terms matching strategy may forbid the paths skipping some terms of the query
+                if !query_graph.is_allowed_path(&path_bitset) {
+                    return Ok(ControlFlow::Continue(()));
+                }
+
                 let idx_of_first_different_condition = {
                     let mut idx = 0;
                     for (&last_c, cur_c) in path.iter().zip(subpaths_docids.iter().map(|x| x.0)) {
@@ -94,11 +108,6 @@ impl<'graph> RankingRule for ProximityRule<'graph> {
                     return Ok(ControlFlow::Continue(()));
                 }
 
-                let mut path_bitset = BitSet::new();
-                for edge in path{
-                    path_bitset.insert(edge.from);
-                }
-                path.last().map(|last| path_bitset.insert(last.to));
                 good_paths.insert(path_bitset);
 
                 bucket |= &path_docids;
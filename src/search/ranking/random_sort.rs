@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+
+use rand::{RngCore, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use roaring::RoaringBitmap;
+
+use crate::score_details::{Random, ScoreDetails};
+use crate::search::context::Context;
+use crate::search::ranking::ranking_rule::{RankingRule, RankingRuleOutput};
+use crate::search::utils::bit_set::BitSet;
+use crate::{DocumentId, Result};
+
+/// Sorts the documents by a pseudo-random value drawn from the seed and the document id.
+///
+/// The value of a document doesn't depend on the other candidates, the relative order
+/// of two documents is then the same for a given seed whatever the bucket they are in.
+/// Every document is returned in its own bucket.
+pub struct RandomSortRule {
+    seed: u64,
+    ascending: bool,
+    allowed_paths: Option<HashSet<BitSet>>,
+    buckets: std::vec::IntoIter<(u64, DocumentId)>,
+}
+
+impl RandomSortRule {
+    pub fn new(seed: u64, ascending: bool) -> Self {
+        Self { seed, ascending, allowed_paths: None, buckets: Vec::new().into_iter() }
+    }
+
+    fn value(&self, docid: DocumentId) -> u64 {
+        // the document id is spread over the whole seed before being mixed with it
+        let docid = (docid as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        Pcg64Mcg::seed_from_u64(self.seed ^ docid).next_u64()
+    }
+}
+
+impl RankingRule for RandomSortRule {
+    fn id(&self) -> String {
+        format!("_random({}):{}", self.seed, if self.ascending { "asc" } else { "desc" })
+    }
+
+    fn start_iteration(&mut self, _ctx: &mut dyn Context, candidates: RoaringBitmap, allowed_paths: Option<HashSet<BitSet>>) -> Result<()> {
+        let mut buckets: Vec<_> = candidates.into_iter().map(|docid| (self.value(docid), docid)).collect();
+        buckets.sort_unstable();
+        if !self.ascending {
+            buckets.reverse();
+        }
+
+        self.buckets = buckets.into_iter();
+        self.allowed_paths = allowed_paths;
+
+        Ok(())
+    }
+
+    fn next_bucket(&mut self, _ctx: &mut dyn Context) -> Result<Option<RankingRuleOutput>> {
+        Ok(self.buckets.next().map(|(value, docid)| RankingRuleOutput {
+            score: ScoreDetails::Random(Random { seed: self.seed, ascending: self.ascending, value }),
+            allowed_path: self.allowed_paths.clone(),
+            candidates: RoaringBitmap::from_iter([docid]),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::index::tests::TempIndex;
+    use crate::{Search, SearchResult};
+
+    #[test]
+    fn search_with_random_sort() {
+        let index = TempIndex::new();
+
+        let documents: Vec<_> = (0..20).map(|id| serde_json::json!({ "id": id, "name": "hello" })).collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let search = |sort: &str| {
+            let SearchResult { documents_ids, .. } = Search::new(&rtxn, &index)
+                .query("hello")
+                .sort_criteria(vec![sort.parse().unwrap()])
+                .limit(20)
+                .execute()
+                .unwrap();
+            documents_ids
+        };
+
+        let shuffled = search("_random(42):asc");
+        assert_eq!(shuffled, search("_random(42):asc"));
+        let mut reversed = search("_random(42):desc");
+        reversed.reverse();
+        assert_eq!(shuffled, reversed);
+
+        let mut sorted = shuffled.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+        assert_ne!(shuffled, sorted);
+    }
+}
@@ -1,22 +1,32 @@
 use std::collections::{HashMap, HashSet};
 use roaring::RoaringBitmap;
 use crate::search::context::Context;
+use crate::search::explanation::VectorSearchStrategy;
 use crate::search::query_graph::QueryGraph;
 use crate::search::utils::bit_set::BitSet;
-use crate::{AscDesc, Criterion, Member, Result, TermsMatchingStrategy};
+use crate::{AscDesc, Criterion, Member, Result, TermsMatchingStrategy, TimeBudget};
 use crate::score_details::ScoreDetails;
 use crate::search::ranking::attribute::AttributeRule;
+use crate::search::ranking::decay::DecayRule;
 use crate::search::ranking::exactness::ExactnessRule;
+use crate::search::ranking::expression::ExpressionRule;
+use crate::search::ranking::geo_sort::GeoSortRule;
+use crate::search::ranking::random_sort::RandomSortRule;
 use crate::search::ranking::proximity::ProximityRule;
 use crate::search::ranking::sort::SortRule;
 use crate::search::ranking::typos::TypoRule;
-use crate::search::ranking::vector_sort::VectorSort;
+use crate::search::ranking::vector_sort::{VectorSearchOptions, VectorSort};
 use crate::search::ranking::words::WordsRule;
 use crate::vector::Embedder;
 
 pub trait RankingRule{
+    fn id(&self) -> String;
     fn start_iteration(&mut self, ctx: &mut dyn Context, candidates: RoaringBitmap, allowed_paths: Option<HashSet<BitSet>>) -> Result<()>;
     fn next_bucket(&mut self, ctx: &mut dyn Context) -> Result<Option<RankingRuleOutput>>;
+    /// The strategies of the nearest neighbours searches run by the rule, reported in the explanation.
+    fn vector_strategies(&self) -> Vec<VectorSearchStrategy> {
+        Vec::new()
+    }
 }
 
 #[derive(Debug)]
@@ -30,6 +40,7 @@ pub fn get_ranking_rules_for_query_graph_search<'ctx: 'graph, 'graph>(
     ctx: &mut impl Context<'ctx>,
     sort_criteria: &Option<Vec<AscDesc>>,
     query_graph: &'graph QueryGraph,
+    time_budget: &TimeBudget,
 ) -> Result<Vec<Box<dyn RankingRule + 'graph>>> {
 
     let mut ranking_rules: Vec<Box<dyn RankingRule + 'graph>> = vec![];
@@ -65,6 +76,12 @@ pub fn get_ranking_rules_for_query_graph_search<'ctx: 'graph, 'graph>(
             Criterion::Desc(field_name) => {
                 ranking_rules.push(Box::new(SortRule::new(ctx, field_name, false)?));
             }
+            Criterion::Expression(expression) => {
+                ranking_rules.push(Box::new(ExpressionRule::new(ctx, expression, time_budget.clone())?));
+            }
+            Criterion::Decay(decay) => {
+                ranking_rules.push(Box::new(DecayRule::new(ctx, decay)?));
+            }
         }
     }
     Ok(ranking_rules)
@@ -79,6 +96,8 @@ pub fn get_ranking_rules_for_vector<'ctx: 'graph, 'graph>(
     target: &[f32],
     embedder_name: &str,
     embedder: &Embedder,
+    vector_search_options: VectorSearchOptions,
+    time_budget: &TimeBudget,
 ) -> Result<Vec<Box<dyn RankingRule + 'graph>>> {
     let mut ranking_rules: Vec<Box<dyn RankingRule + 'graph>> = vec![];
 
@@ -89,7 +108,8 @@ pub fn get_ranking_rules_for_vector<'ctx: 'graph, 'graph>(
         vector_candidates,
         limit_plus_offset as usize,
         embedder_name,
-        embedder,
+        embedder.distribution(),
+        vector_search_options,
     )?;
 
     ranking_rules.push(Box::new(vector_sort));
@@ -126,6 +146,12 @@ pub fn get_ranking_rules_for_vector<'ctx: 'graph, 'graph>(
             Criterion::Desc(field_name) => {
                 ranking_rules.push(Box::new(SortRule::new(ctx, field_name, false)?));
             }
+            Criterion::Expression(expression) => {
+                ranking_rules.push(Box::new(ExpressionRule::new(ctx, expression, time_budget.clone())?));
+            }
+            Criterion::Decay(decay) => {
+                ranking_rules.push(Box::new(DecayRule::new(ctx, decay)?));
+            }
         }
     }
     Ok(ranking_rules)
@@ -147,6 +173,18 @@ fn resolve_sort_criteria<'ctx:'graph, 'graph>(
                 AscDesc::Desc(Member::Field(field_name)) => {
                     ranking_rules.push(Box::new(SortRule::new(ctx, field_name, false)?));
                 }
+                AscDesc::Asc(Member::Geo(point)) => {
+                    ranking_rules.push(Box::new(GeoSortRule::new(point, true)));
+                }
+                AscDesc::Desc(Member::Geo(point)) => {
+                    ranking_rules.push(Box::new(GeoSortRule::new(point, false)));
+                }
+                AscDesc::Asc(Member::Random(seed)) => {
+                    ranking_rules.push(Box::new(RandomSortRule::new(seed, true)));
+                }
+                AscDesc::Desc(Member::Random(seed)) => {
+                    ranking_rules.push(Box::new(RandomSortRule::new(seed, false)));
+                }
             };
         }
     }
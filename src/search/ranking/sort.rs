@@ -63,6 +63,10 @@ impl<'ctx> SortRule<'ctx> {
 }
 
 impl<'ctx> RankingRule for SortRule<'ctx> {
+    fn id(&self) -> String {
+        format!("{}:{}", self.field_name, if self.is_ascending { "asc" } else { "desc" })
+    }
+
     fn start_iteration(&mut self, ctx: &mut dyn Context, candidates: RoaringBitmap, allowed_paths: Option<HashSet<BitSet>>) -> Result<()>{
         let iter: RankingRuleOutputIterWrapper = match self.field_id {
             Some(field_id) => {
@@ -27,7 +27,7 @@ impl<'graph> TypoRule<'graph> {
         let paths = paths_cost::<TypoCost>(graph, context);
         let max_cost = Self::max_cost(context, graph)?;
         let mut costs = VecMap::with_capacity(max_cost);
-        for (path, cost) in paths{
+        for (path, cost) in paths.into_iter().filter(|(path, _)| graph.is_allowed_path(path)){
             costs.get_or_insert_with(cost, || HashSet::new()).insert(path);
         }
 
@@ -40,6 +40,9 @@ impl<'graph> TypoRule<'graph> {
 
 
 impl RankingRule for TypoRule<'_> {
+    fn id(&self) -> String {
+        "typo".to_string()
+    }
 
     fn start_iteration(&mut self, _ctx: &mut dyn Context, candidates: RoaringBitmap, allowed_paths: Option<HashSet<BitSet>>) -> Result<()> {
         self.candidates = candidates;
@@ -1,26 +1,57 @@
 use std::collections::HashSet;
 use std::iter::FromIterator;
+use std::num::NonZeroUsize;
 
 use ordered_float::OrderedFloat;
 use roaring::RoaringBitmap;
 
 use crate::score_details::{self, ScoreDetails};
-use crate::vector::{DistributionShift, Embedder};
+use crate::vector::{DistributionShift, VectorAggregation, VectorDistance, VectorQuantization};
 use crate::{DocumentId, Result};
 use crate::search::context::Context;
+use crate::search::explanation::VectorSearchStrategy;
 use crate::search::query_graph::QueryGraph;
 use crate::search::ranking::ranking_rule::{RankingRule, RankingRuleOutput};
 use crate::search::utils::bit_set::BitSet;
+use crate::vector::distance::VectorReader;
+
+/// The default number of filtered candidates up to which the exact distances of the candidates are computed.
+pub const DEFAULT_EXACT_SEARCH_THRESHOLD: u64 = 1000;
+
+/// How a vector sort chooses between the exact and the approximate nearest neighbours search.
+#[derive(Debug, Clone, Copy)]
+pub struct VectorSearchOptions {
+    /// The vectors of the candidates are all compared to the target when a filter leaves at most this many
+    /// candidates, arroy's approximate search is used above and for the unfiltered searches.
+    pub exact_threshold: u64,
+    /// The number of nodes inspected by arroy's approximate search, arroy's default when `None`.
+    pub search_k: Option<NonZeroUsize>,
+}
+
+impl Default for VectorSearchOptions {
+    fn default() -> Self {
+        Self { exact_threshold: DEFAULT_EXACT_SEARCH_THRESHOLD, search_k: None }
+    }
+}
 
 pub struct VectorSort {
     allowed_paths: Option<HashSet<BitSet>>,
     target: Vec<f32>,
     candidates: RoaringBitmap,
     vector_candidates: RoaringBitmap,
-    cached_sorted_docids: std::vec::IntoIter<(DocumentId, f32)>,
+    /// The documents with their similarity and their best vector, the most similar first.
+    cached_sorted_docids: std::vec::IntoIter<(DocumentId, f32, u8)>,
     limit: usize,
     distribution_shift: Option<DistributionShift>,
     embedder_index: u8,
+    aggregation: VectorAggregation,
+    aggregation_top_k: usize,
+    distance: VectorDistance,
+    quantization: Option<VectorQuantization>,
+    rescore_factor: usize,
+    options: VectorSearchOptions,
+    /// The strategy of each nearest neighbours search, reported in the explanation.
+    strategies: Vec<VectorSearchStrategy>,
 }
 
 impl VectorSort {
@@ -30,10 +61,12 @@ impl VectorSort {
         vector_candidates: RoaringBitmap,
         limit: usize,
         embedder_name: &str,
-        embedder: &Embedder,
+        distribution_shift: Option<DistributionShift>,
+        options: VectorSearchOptions,
     ) -> Result<Self> {
         let embedder_index = ctx
             .embedder_category_id(embedder_name)?;
+        let config = ctx.embedding_config(embedder_name)?.unwrap_or_default();
 
         Ok(Self {
             allowed_paths: None,
@@ -42,11 +75,29 @@ impl VectorSort {
             vector_candidates,
             cached_sorted_docids: Default::default(),
             limit,
-            distribution_shift: embedder.distribution(),
+            distribution_shift,
             embedder_index,
+            aggregation: config.aggregation,
+            aggregation_top_k: config.aggregation_top_k(),
+            distance: config.distance,
+            quantization: config.quantization,
+            rescore_factor: config.rescore_factor(),
+            options,
+            strategies: Vec::new(),
         })
     }
 
+    /// Returns the candidates with their similarity to the target and their best vector, the most similar first,
+    /// the documents without vectors are left out.
+    pub fn similar_docids(
+        mut self,
+        ctx: &mut (impl Context + ?Sized),
+        candidates: &RoaringBitmap,
+    ) -> Result<Vec<(DocumentId, f32, u8)>> {
+        self.fill_buffer(ctx, candidates)?;
+        Ok(self.cached_sorted_docids.collect())
+    }
+
     fn fill_buffer(
         &mut self,
         ctx: &mut (impl Context + ?Sized),
@@ -55,7 +106,7 @@ impl VectorSort {
         let writer_index = (self.embedder_index as u16) << 8;
         let readers: std::result::Result<Vec<_>, _> = (0..=u8::MAX)
             .map_while(|k| {
-                ctx.vector_reader(writer_index | (k as u16))
+                ctx.vector_reader(writer_index | (k as u16), self.distance, self.quantization)
                     .map(Some)
                     .or_else(|e| match e {
                         arroy::Error::MissingMetadata => Ok(None),
@@ -68,14 +119,79 @@ impl VectorSort {
         let readers = readers?;
 
         let target = &self.target;
-        let mut results = Vec::new();
 
-        for reader in readers.iter() {
-            let nns_by_vector =
-                reader.nns_by_vector(ctx.txn(), target, self.limit, None, Some(vector_candidates))?;
-            results.extend(nns_by_vector.into_iter());
+        // arroy can miss the true neighbours of the few candidates left by a filter, they are all compared to
+        // the target instead. The search of all the documents is left to arroy, however small the index is,
+        // the chosen strategy is reported in the explanation.
+        let filtered = match readers.first() {
+            // uses invariant: the first reader holds a vector of every document with vectors.
+            Some(VectorReader::Arroy(reader)) => vector_candidates.len() < reader.n_items(),
+            // the quantized vectors are read in order by both strategies, the few candidates are all rescored.
+            _ => true,
+        };
+        let strategy = if filtered && vector_candidates.len() <= self.options.exact_threshold {
+            VectorSearchStrategy::Exact { candidates: vector_candidates.len() }
+        } else {
+            VectorSearchStrategy::Approximate {
+                candidates: vector_candidates.len(),
+                search_k: self.options.search_k,
+                quantization: self.quantization,
+            }
+        };
+        self.strategies.push(strategy);
+
+        // the documents having one of the nearest vectors of the target.
+        let selected = match strategy {
+            VectorSearchStrategy::Exact { .. } => vector_candidates.clone(),
+            VectorSearchStrategy::Approximate { .. } => {
+                let mut selected = RoaringBitmap::new();
+                for reader in &readers {
+                    let nearest: RoaringBitmap = match reader {
+                        VectorReader::Arroy(reader) => reader
+                            .nns_by_vector(ctx.txn(), target, self.limit, self.options.search_k, Some(vector_candidates))?
+                            .into_iter()
+                            .map(|(docid, _)| docid)
+                            .collect(),
+                        // the nearest quantized vectors are rescored with the full precision target below.
+                        VectorReader::Quantized(reader) => {
+                            reader.nearest(ctx.txn(), target, vector_candidates, self.limit * self.rescore_factor)?
+                        }
+                    };
+                    // uses invariant: vectors are packed in the first readers.
+                    if nearest.is_empty() {
+                        break;
+                    }
+                    selected |= nearest;
+                }
+                selected
+            }
+        };
+
+        let mut results = Vec::with_capacity(selected.len() as usize);
+        for docid in &selected {
+            // every vector of the document is aggregated, not only the ones returned by arroy.
+            let mut similarities = Vec::new();
+            for (vector_index, reader) in readers.iter().enumerate() {
+                // uses invariant: vectors are packed in the first readers.
+                let Some(vector) = reader.item_vector(ctx.txn(), docid)? else { break };
+                let similarity = self.distance.similarity(self.distance.distance(target, &vector));
+                similarities.push((similarity, vector_index as u8));
+            }
+            if similarities.is_empty() {
+                continue;
+            }
+
+            similarities.sort_unstable_by_key(|&(similarity, _)| std::cmp::Reverse(OrderedFloat(similarity)));
+            let best_vector = similarities[0].1;
+            let similarities: Vec<_> = similarities.into_iter().map(|(similarity, _)| similarity).collect();
+            let score = self.aggregation.aggregate(&similarities, self.aggregation_top_k);
+            let score = self
+                .distribution_shift
+                .map(|distribution| distribution.shift(score))
+                .unwrap_or(score);
+            results.push((docid, score, best_vector));
         }
-        results.sort_unstable_by_key(|(_, distance)| OrderedFloat(*distance));
+        results.sort_unstable_by_key(|&(docid, score, _)| (std::cmp::Reverse(OrderedFloat(score)), docid));
         self.cached_sorted_docids = results.into_iter();
 
         Ok(())
@@ -83,6 +199,13 @@ impl VectorSort {
 }
 
 impl<'ctx> RankingRule for VectorSort {
+    fn id(&self) -> String {
+        "vector_sort".to_string()
+    }
+
+    fn vector_strategies(&self) -> Vec<VectorSearchStrategy> {
+        self.strategies.clone()
+    }
 
     fn start_iteration(&mut self, ctx: &mut dyn Context, candidates: RoaringBitmap, allowed_paths: Option<HashSet<BitSet>>) -> Result<()>{
         self.allowed_paths = allowed_paths;
@@ -103,23 +226,21 @@ impl<'ctx> RankingRule for VectorSort {
             self.candidates = RoaringBitmap::new();
             return Ok(Some(RankingRuleOutput {
                 candidates: self.candidates.clone(),
-                score: ScoreDetails::Vector(score_details::Vector { similarity: None }),
+                score: ScoreDetails::Vector(score_details::Vector { similarity: None, best_vector: None }),
                 allowed_path: self.allowed_paths.clone(),
             }));
         }
 
-        for (docid, distance) in self.cached_sorted_docids.by_ref() {
+        for (docid, score, best_vector) in self.cached_sorted_docids.by_ref() {
             if vector_candidates.contains(docid) {
-                let score = 1.0 - distance;
-                let score = self
-                    .distribution_shift
-                    .map(|distribution| distribution.shift(score))
-                    .unwrap_or(score);
                 self.candidates.remove(docid);
                 return Ok(Some(RankingRuleOutput {
                     allowed_path: self.allowed_paths.clone(),
                     candidates: RoaringBitmap::from_iter([docid]),
-                    score: ScoreDetails::Vector(score_details::Vector { similarity: Some(score) }),
+                    score: ScoreDetails::Vector(score_details::Vector {
+                        similarity: Some(score),
+                        best_vector: Some(best_vector),
+                    }),
                 }));
             }
         }
@@ -136,10 +257,406 @@ impl<'ctx> RankingRule for VectorSort {
             return Ok(Some(RankingRuleOutput {
                 allowed_path: self.allowed_paths.clone(),
                 candidates: self.candidates.clone(),
-                score: ScoreDetails::Vector(score_details::Vector { similarity: None }),
+                score: ScoreDetails::Vector(score_details::Vector { similarity: None, best_vector: None }),
             }));
         }
 
         self.next_bucket(ctx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use heed::types::Bytes;
+    use maplit::hashset;
+
+    use crate::error::Error;
+    use crate::index::tests::TempIndex;
+    use crate::score_details::ScoreDetails;
+    use crate::search::facet::Filter;
+    use crate::update::Setting;
+    use crate::vector::settings::EmbeddingSettings;
+    use crate::vector::{VectorAggregation, VectorDistance, VectorQuantization};
+    use crate::{Search, SearchResult, UserError, VectorSearchStrategy};
+
+    #[test]
+    fn vector_search_aggregates_the_vectors_of_a_document() {
+        let index = TempIndex::new();
+
+        index.set_manual_embedder(|embedder| embedder.aggregation = Setting::Set(VectorAggregation::Max)).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "_vectors": { "manual": [1.0, 0.05] } },
+                { "id": 1, "_vectors": { "manual": [[0.5, 0.5], [0.9, 0.1], [0.8, 0.2]] } },
+            ]))
+            .unwrap();
+
+        let search_with = |index: &TempIndex, exact_threshold: u64, limit: u64| {
+            let rtxn = index.read_txn().unwrap();
+            let embedder = index.manual_embedder();
+            let mut search = Search::new(&rtxn, index);
+            search
+                .semantic(S("manual"), embedder, Some(vec![1.0, 0.0]))
+                .exact_vector_search_threshold(exact_threshold)
+                .limit(limit);
+            search.execute().unwrap()
+        };
+        let search = |index: &TempIndex| search_with(index, 1000, 20);
+
+        // each document is returned once, with the vector closest to the query.
+        let result = search(&index);
+        assert_eq!(result.documents_ids, [0, 1]);
+        let best_vectors: Vec<_> = result
+            .document_scores
+            .iter()
+            .map(|scores| match &scores[0] {
+                ScoreDetails::Vector(vector) => vector.best_vector,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(best_vectors, [Some(0), Some(1)]);
+
+        // the many close vectors of the second document outweigh the exact vector of the first one.
+        index.set_manual_embedder(|embedder| embedder.aggregation = Setting::Set(VectorAggregation::Sum)).unwrap();
+        let result = search(&index);
+        assert_eq!(result.documents_ids, [1, 0]);
+        // the sum stays a similarity.
+        for scores in &result.document_scores {
+            let score = ScoreDetails::global_score(scores.iter());
+            assert!((0.0..=1.0).contains(&score));
+        }
+
+        // the approximate search aggregates every vector of the document, even those arroy didn't return.
+        let approximate = search_with(&index, 0, 1);
+        assert_eq!(approximate.documents_ids, [1]);
+        assert_eq!(approximate.document_scores[0], result.document_scores[0]);
+    }
+
+    #[test]
+    fn vector_search_with_distance() {
+        let index = TempIndex::new();
+
+        index.set_manual_embedder(|embedder| embedder.distance = Setting::Set(VectorDistance::Angular)).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "_vectors": { "manual": [10.0, 0.0] } },
+                { "id": 1, "_vectors": { "manual": [0.9, 0.3] } },
+            ]))
+            .unwrap();
+
+        let search = |index: &TempIndex| {
+            let rtxn = index.read_txn().unwrap();
+            let embedder = index.manual_embedder();
+            let mut search = Search::new(&rtxn, index);
+            search.semantic(S("manual"), embedder, Some(vec![1.0, 0.0]));
+            search.execute().unwrap().documents_ids
+        };
+
+        // the first document has the direction of the query.
+        assert_eq!(search(&index), [0, 1]);
+
+        // the trees are rebuilt with the new distance, the second document is the nearest.
+        index.set_manual_embedder(|embedder| embedder.distance = Setting::Set(VectorDistance::Euclidean)).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.embedder_distance(&rtxn, "manual").unwrap(), VectorDistance::Euclidean);
+        drop(rtxn);
+        assert_eq!(search(&index), [1, 0]);
+
+        // the greatest dot product is the nearest, its similarity is still bounded.
+        index.set_manual_embedder(|embedder| embedder.distance = Setting::Set(VectorDistance::DotProduct)).unwrap();
+        assert_eq!(search(&index), [0, 1]);
+        let rtxn = index.read_txn().unwrap();
+        let embedder = index.manual_embedder();
+        let result =
+            Search::new(&rtxn, &index).semantic(S("manual"), embedder, Some(vec![1.0, 0.0])).execute().unwrap();
+        for scores in &result.document_scores {
+            let score = ScoreDetails::global_score(scores.iter());
+            assert!(score > 0.0 && score < 1.0);
+        }
+    }
+
+    #[test]
+    fn vector_search_with_quantization() {
+        let index = TempIndex::new();
+
+        index
+            .set_manual_embedder(|embedder| embedder.quantization = Setting::Set(VectorQuantization::Binary))
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "_vectors": { "manual": [-1.0, 0.5] } },
+                { "id": 1, "_vectors": { "manual": [0.9, -0.1] } },
+                { "id": 2, "_vectors": { "manual": [1.0, 0.1] } },
+            ]))
+            .unwrap();
+
+        let search = |index: &TempIndex| {
+            let rtxn = index.read_txn().unwrap();
+            let embedder = index.manual_embedder();
+            let mut search = Search::new(&rtxn, index);
+            // the approximate search is forced to go through the quantized vectors.
+            search.semantic(S("manual"), embedder, Some(vec![1.0, 0.2])).exact_vector_search_threshold(0);
+            search.execute().unwrap().documents_ids
+        };
+        let quantized_vectors = |index: &TempIndex| {
+            let rtxn = index.read_txn().unwrap();
+            index.vector_quantized.len(&rtxn).unwrap()
+        };
+
+        let arroy_items = |index: &TempIndex| {
+            let rtxn = index.read_txn().unwrap();
+            index.vector_arroy.len(&rtxn).unwrap()
+        };
+
+        // the quantized vectors are stored instead of the trees, the nearest are rescored with the query.
+        assert_eq!(quantized_vectors(&index), 3);
+        assert_eq!(arroy_items(&index), 0);
+        assert_eq!(search(&index), [2, 1, 0]);
+
+        // the full precision vectors are gone, the quantization can't be changed nor removed.
+        let error = index
+            .set_manual_embedder(|embedder| embedder.quantization = Setting::Set(VectorQuantization::Scalar))
+            .unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::InvalidSettingsQuantization { .. })));
+        let error = index.set_manual_embedder(|embedder| embedder.quantization = Setting::Reset).unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::InvalidSettingsQuantization { .. })));
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.embedder_quantization(&rtxn, "manual").unwrap(), Some(VectorQuantization::Binary));
+        drop(rtxn);
+
+        // the number of rescored vectors is set per embedder.
+        let rescore_settings = |rescore_factor| {
+            move |embedder: &mut EmbeddingSettings| {
+                embedder.quantization = Setting::Set(VectorQuantization::Binary);
+                embedder.rescore_factor = Setting::Set(rescore_factor);
+            }
+        };
+        index.set_manual_embedder(rescore_settings(1)).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let (_, config) = index.embedding_configs(&rtxn).unwrap().pop().unwrap();
+        assert_eq!(config.rescore_factor(), 1);
+        drop(rtxn);
+        assert_eq!(search(&index), [2, 1, 0]);
+
+        let error = index.set_manual_embedder(rescore_settings(0)).unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::InvalidSettingsRescoreFactor { .. })));
+    }
+
+    /// Deterministic pseudo random documents with vectors of `dimensions`.
+    fn random_vector_documents(count: usize, dimensions: usize) -> Vec<serde_json::Value> {
+        let vector = |id: usize| -> Vec<f32> {
+            (0..dimensions)
+                .map(|j| ((((id * dimensions + j) as f64 * 12.9898).sin() * 43758.5453).fract() - 0.5) as f32)
+                .collect()
+        };
+        (0..count).map(|id| serde_json::json!({ "id": id, "_vectors": { "manual": vector(id) } })).collect()
+    }
+
+    /// The bytes of the keys and values of the vectors of the index.
+    fn vector_storage(index: &TempIndex) -> usize {
+        let rtxn = index.read_txn().unwrap();
+        let arroy = index.vector_arroy.remap_types::<Bytes, Bytes>();
+        let quantized = index.vector_quantized.remap_types::<Bytes, Bytes>();
+        arroy
+            .iter(&rtxn)
+            .unwrap()
+            .chain(quantized.iter(&rtxn).unwrap())
+            .map(|entry| entry.map(|(key, value)| key.len() + value.len()).unwrap())
+            .sum()
+    }
+
+    #[test]
+    fn vector_search_with_quantization_takes_less_storage() {
+        let index = TempIndex::new();
+        let dimensions = |embedder: &mut EmbeddingSettings| embedder.dimensions = Setting::Set(64);
+
+        index.set_manual_embedder(dimensions).unwrap();
+        let documents = random_vector_documents(200, 64);
+        let query: Vec<f32> = serde_json::from_value(documents[17]["_vectors"]["manual"].clone()).unwrap();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let search = |index: &TempIndex| {
+            let rtxn = index.read_txn().unwrap();
+            let embedder = index.manual_embedder();
+            let mut search = Search::new(&rtxn, index);
+            search.semantic(S("manual"), embedder, Some(query.clone())).exact_vector_search_threshold(0).limit(1);
+            search.execute().unwrap().documents_ids
+        };
+        let full_precision = vector_storage(&index);
+        assert_eq!(search(&index), [17]);
+
+        // the vectors of the trees are quantized and the trees are removed.
+        index
+            .set_manual_embedder(|embedder| {
+                dimensions(embedder);
+                embedder.quantization = Setting::Set(VectorQuantization::Binary);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.vector_arroy.is_empty(&rtxn).unwrap());
+        assert_eq!(index.vector_quantized.len(&rtxn).unwrap(), 200);
+        drop(rtxn);
+
+        // 12 bytes for a 64 dimensions vector instead of 256 and the nodes of the trees.
+        let quantized = vector_storage(&index);
+        assert!(quantized * 10 < full_precision, "{quantized} bytes quantized, {full_precision} bytes in the trees");
+        assert_eq!(search(&index), [17]);
+    }
+
+    /// Compares the latency of the approximate search with and without quantization,
+    /// run with `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn vector_search_with_quantization_is_faster() {
+        let (count, dimensions) = (20_000, 256);
+        let documents = random_vector_documents(count, dimensions);
+        let queries: Vec<Vec<f32>> = documents
+            .iter()
+            .step_by(count / 100)
+            .map(|document| serde_json::from_value(document["_vectors"]["manual"].clone()).unwrap())
+            .collect();
+
+        let mean_latency = |quantization: Option<VectorQuantization>| {
+            let index = TempIndex::new_with_map_size(4096 * 1000 * 1000);
+            index
+                .set_manual_embedder(|embedder| {
+                    embedder.dimensions = Setting::Set(dimensions);
+                    if let Some(quantization) = quantization {
+                        embedder.quantization = Setting::Set(quantization);
+                    }
+                })
+                .unwrap();
+            index.add_documents(documents!(documents)).unwrap();
+
+            let rtxn = index.read_txn().unwrap();
+            let embedder = index.manual_embedder();
+            let timer = std::time::Instant::now();
+            for query in &queries {
+                let mut search = Search::new(&rtxn, &index);
+                search
+                    .semantic(S("manual"), embedder.clone(), Some(query.clone()))
+                    .exact_vector_search_threshold(0)
+                    .limit(10);
+                search.execute().unwrap();
+            }
+            let latency = timer.elapsed() / queries.len() as u32;
+            drop(rtxn);
+            (latency, vector_storage(&index))
+        };
+
+        let (full_precision, full_precision_storage) = mean_latency(None);
+        let (quantized, quantized_storage) = mean_latency(Some(VectorQuantization::Binary));
+        println!("full precision: {full_precision:?} per search, {full_precision_storage} bytes");
+        println!("binary quantization: {quantized:?} per search, {quantized_storage} bytes");
+        assert!(quantized < full_precision);
+        assert!(quantized_storage < full_precision_storage);
+    }
+
+    #[test]
+    fn vector_search_strategy() {
+        let index = TempIndex::new();
+
+        index.update_settings(|settings| settings.set_filterable_fields(hashset! { S("kind") })).unwrap();
+        index.set_manual_embedder(|_| ()).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "kind": "a", "_vectors": { "manual": [1.0, 0.0] } },
+                { "id": 1, "kind": "a", "_vectors": { "manual": [0.0, 1.0] } },
+                { "id": 2, "kind": "b", "_vectors": { "manual": [0.9, 0.1] } },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let embedder = index.manual_embedder();
+        let vector_strategies = |result: &SearchResult| {
+            let explanation = result.explanation.as_ref().unwrap();
+            assert_eq!(explanation.ranking_rules[0].id, "vector_sort");
+            explanation.ranking_rules[0].vector_strategies.clone()
+        };
+
+        // the few filtered candidates are all compared to the query.
+        let filter = Filter::from_json(&serde_json::json!({ "kind": "a" })).unwrap().unwrap();
+        let result = Search::new(&rtxn, &index)
+            .semantic(S("manual"), embedder.clone(), Some(vec![1.0, 0.0]))
+            .filter(filter)
+            .explain(true)
+            .execute()
+            .unwrap();
+        assert_eq!(result.documents_ids, [0, 1]);
+        assert_eq!(vector_strategies(&result), [VectorSearchStrategy::Exact { candidates: 2 }]);
+        let json = serde_json::to_value(result.explanation.as_ref().unwrap()).unwrap();
+        assert_eq!(json["ranking_rules"][0]["vector_strategies"][0]["type"], "Exact");
+
+        // above the threshold the nearest documents are looked for in the arroy trees.
+        let search_k = std::num::NonZeroUsize::new(10).unwrap();
+        let result = Search::new(&rtxn, &index)
+            .semantic(S("manual"), embedder, Some(vec![1.0, 0.0]))
+            .exact_vector_search_threshold(2)
+            .vector_search_k(search_k)
+            .explain(true)
+            .execute()
+            .unwrap();
+        assert_eq!(result.documents_ids, [0, 2, 1]);
+        assert_eq!(
+            vector_strategies(&result),
+            [VectorSearchStrategy::Approximate { candidates: 3, search_k: Some(search_k), quantization: None }]
+        );
+    }
+
+    #[test]
+    fn exact_and_approximate_vector_searches_agree() {
+        let index = TempIndex::new();
+
+        index.update_settings(|settings| settings.set_filterable_fields(hashset! { S("kind") })).unwrap();
+        index.set_manual_embedder(|_| ()).unwrap();
+        // the documents are spread around the unit circle, one in twenty is rare.
+        let documents: Vec<_> = (0..200)
+            .map(|id| {
+                let angle = id as f64 * std::f64::consts::TAU / 200.0;
+                let kind = if id % 20 == 0 { "rare" } else { "common" };
+                serde_json::json!({ "id": id, "kind": kind, "_vectors": { "manual": [angle.cos(), angle.sin()] } })
+            })
+            .collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let embedder = index.manual_embedder();
+        let search = |exact_threshold: u64| {
+            let filter = Filter::from_json(&serde_json::json!({ "kind": "rare" })).unwrap().unwrap();
+            Search::new(&rtxn, &index)
+                .semantic(S("manual"), embedder.clone(), Some(vec![0.1f32.cos(), 0.1f32.sin()]))
+                .filter(filter)
+                .exact_vector_search_threshold(exact_threshold)
+                .vector_search_k(std::num::NonZeroUsize::new(100_000).unwrap())
+                .limit(3)
+                .explain(true)
+                .execute()
+                .unwrap()
+        };
+
+        // the few rare documents are compared to the query, whatever arroy would have visited.
+        let exact = search(1000);
+        assert_eq!(exact.documents_ids, [0, 20, 180]);
+        let strategies = &exact.explanation.as_ref().unwrap().ranking_rules[0].vector_strategies;
+        assert_eq!(strategies, &[VectorSearchStrategy::Exact { candidates: 10 }]);
+
+        // an exhaustive approximate search finds the same neighbours with the same scores.
+        let approximate = search(0);
+        let strategies = &approximate.explanation.as_ref().unwrap().ranking_rules[0].vector_strategies;
+        assert!(matches!(strategies[..], [VectorSearchStrategy::Approximate { candidates: 10, .. }]));
+        assert_eq!(approximate.documents_ids, exact.documents_ids);
+        assert_eq!(approximate.document_scores, exact.document_scores);
+
+        // the unfiltered searches are left to arroy.
+        let result = Search::new(&rtxn, &index)
+            .semantic(S("manual"), embedder, Some(vec![1.0, 0.0]))
+            .limit(1)
+            .explain(true)
+            .execute()
+            .unwrap();
+        assert_eq!(result.documents_ids, [0]);
+        let strategies = &result.explanation.as_ref().unwrap().ranking_rules[0].vector_strategies;
+        assert!(matches!(strategies[..], [VectorSearchStrategy::Approximate { candidates: 200, .. }]));
+    }
+}
@@ -25,7 +25,7 @@ impl<'graph> WordsRule<'graph> {
         let paths = paths_cost::<WordsCost>(graph, context);
         let max_cost = Self::max_cost(context, graph)?;
         let mut costs = VecMap::with_capacity(max_cost as usize);
-        for (path, cost) in paths{
+        for (path, cost) in paths.into_iter().filter(|(path, _)| graph.is_allowed_path(path)){
             costs.get_or_insert_with(cost, || HashSet::new()).insert(path);
         }
 
@@ -38,6 +38,9 @@ impl<'graph> WordsRule<'graph> {
 
 
 impl RankingRule for WordsRule<'_> {
+    fn id(&self) -> String {
+        "words".to_string()
+    }
 
     fn start_iteration(&mut self, _ctx: &mut dyn Context, candidates: RoaringBitmap, allowed_paths: Option<HashSet<BitSet>>) -> Result<()> {
         self.candidates = candidates;
@@ -90,4 +93,122 @@ impl Cost for WordsCost {
             _ => 0
         }
     }
-}
\ No newline at end of file
+}
+
+/// Returns the documents matching at least one of the allowed paths of the query graph.
+///
+/// The graph is walked once, the documents reaching a node are the ones of the node
+/// reaching one of its predecessors, the paths are never resolved one by one.
+pub fn query_graph_docids(context: &mut impl Context, graph: &QueryGraph) -> Result<RoaringBitmap> {
+    match &graph.allowed_terms {
+        None => reachable_docids(context, graph, None),
+        Some(allowed_terms) => {
+            let mut docids = RoaringBitmap::new();
+            for terms in allowed_terms {
+                docids |= reachable_docids(context, graph, Some(terms))?;
+            }
+            Ok(docids)
+        }
+    }
+}
+
+/// Returns the documents of the paths going from the root to the end of the graph,
+/// only the paths matching exactly the given original terms are kept.
+fn reachable_docids(context: &mut impl Context, graph: &QueryGraph, terms: Option<&BitSet>) -> Result<RoaringBitmap> {
+    let mut reached = vec![None; graph.nodes.len()];
+    reach_node(context, graph, terms, graph.end, &mut reached)
+}
+
+fn reach_node(context: &mut impl Context, graph: &QueryGraph, terms: Option<&BitSet>, node_id: usize, reached: &mut Vec<Option<RoaringBitmap>>) -> Result<RoaringBitmap> {
+    if let Some(docids) = &reached[node_id] {
+        return Ok(docids.clone());
+    }
+    let docids = if node_id == graph.root {
+        context.node_docids(node_id, graph)?.clone()
+    } else if terms.is_some_and(|terms| !graph.path_terms(&BitSet::from_iter([node_id])).is_subset(terms)) {
+        // the node matches a term that the path isn't allowed to match
+        RoaringBitmap::new()
+    } else {
+        let mut docids = RoaringBitmap::new();
+        for predecessor in graph.nodes[node_id].predecessors.iter() {
+            if terms.map_or(true, |terms| !skips_term(graph, terms, predecessor, node_id)) {
+                docids |= reach_node(context, graph, terms, predecessor, reached)?;
+            }
+        }
+        if node_id != graph.end && !docids.is_empty() {
+            docids &= context.node_docids(node_id, graph)?;
+        }
+        docids
+    };
+    reached[node_id] = Some(docids.clone());
+
+    Ok(docids)
+}
+
+/// Whether one of the terms lies between the two nodes, a path going
+/// from one to the other wouldn't match it.
+fn skips_term(graph: &QueryGraph, terms: &BitSet, from: usize, to: usize) -> bool {
+    let position = |node_id: usize| match &graph.nodes[node_id].data {
+        NodeData::Term(Term{ position, .. }) => Some(position.clone()),
+        _ => None,
+    };
+    let after = position(from).map(|position| *position.end());
+    let before = position(to).map(|position| *position.start());
+    terms.iter().filter_map(position).any(|position| {
+        after.map_or(true, |after| after < *position.start()) && before.map_or(true, |before| *position.end() < before)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use roaring::RoaringBitmap;
+    use crate::index::tests::TempIndex;
+    use crate::search::query_graph::QueryGraph;
+    use crate::search::query_parser::parse_query;
+    use crate::search::ranking::paths_cost::paths_cost;
+    use crate::search::search::SearchContext;
+    use crate::TermsMatchingStrategy;
+    use crate::search::context::Context;
+    use super::{query_graph_docids, WordsCost};
+
+    #[test]
+    fn query_graph_docids_is_the_union_of_the_allowed_paths() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "the quick fox" },
+                { "id": 1, "name": "the quick" },
+                { "id": 2, "name": "quick fox" },
+                { "id": 3, "name": "the quickfox" },
+                { "id": 4, "name": "fox" },
+                { "id": 5, "name": "the brown dog" },
+                { "id": 6, "name": "dog" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let analyzer = index.analyzer(&rtxn, &None).unwrap();
+        let strategies = [
+            TermsMatchingStrategy::Last,
+            TermsMatchingStrategy::All,
+            TermsMatchingStrategy::Frequency,
+            TermsMatchingStrategy::Any,
+        ];
+        for strategy in strategies {
+            for query in ["the quick fox", "quick fox", "the dog"] {
+                let mut ctx = SearchContext::new(&index, &rtxn, strategy);
+                let terms = parse_query(analyzer.analyze(query));
+                let graph = QueryGraph::from_query(terms, &mut ctx).unwrap();
+
+                let mut expected = RoaringBitmap::new();
+                for (path, _) in paths_cost::<WordsCost>(&graph, &ctx) {
+                    if graph.is_allowed_path(&path) {
+                        expected |= ctx.path_docids(path, &graph).unwrap();
+                    }
+                }
+                assert_eq!(query_graph_docids(&mut ctx, &graph).unwrap(), expected, "{query} with {strategy:?}");
+            }
+        }
+    }
+}
@@ -1,16 +1,22 @@
+use std::borrow::Cow;
 use std::collections::BTreeSet;
 use heed::RoTxn;
 use roaring::RoaringBitmap;
 use analyzer::analyzer::Analyzer;
-use crate::{AscDesc, DocumentId, Index, TermsMatchingStrategy, Result, Member, UserError, FieldId, FieldIdMapMissingEntry, Filter};
+use crate::{AscDesc, DocumentId, Index, TermsMatchingStrategy, Result, Member, UserError, FieldId, FieldIdMapMissingEntry, Filter, TimeBudget};
 use crate::score_details::{ScoreDetails};
 use crate::search::context::Context;
+use crate::search::cursor::SearchCursor;
 use crate::search::db_cache::DatabaseCache;
 use crate::search::query_cache::QueryCache;
+use crate::search::explanation::QueryExplanation;
 use crate::search::query_graph::QueryGraph;
 use crate::search::query_parser::parse_query;
+use crate::search::query_rewrite::rewrite_query;
 use crate::search::ranking::bucket_sort::{bucket_sort, BucketSortOutput};
 use crate::search::ranking::ranking_rule::{get_ranking_rules_for_query_graph_search, get_ranking_rules_for_vector};
+use crate::search::ranking::words::query_graph_docids;
+use crate::search::ranking::vector_sort::VectorSearchOptions;
 use crate::vector::Embedder;
 
 #[derive(Debug, Clone, Default)]
@@ -100,10 +106,16 @@ pub fn execute_vector_search(
     vector: &[f32],
     candidates: RoaringBitmap,
     sort_criteria: &Option<Vec<AscDesc>>,
+    distinct: Option<FieldId>,
     skip: u64,
     limit: u64,
     embedder_name: &str,
     embedder: &Embedder,
+    vector_search_options: VectorSearchOptions,
+    after: Option<&SearchCursor>,
+    ranking_score_threshold: Option<f64>,
+    time_budget: &TimeBudget,
+    explain: bool,
 ) -> Result<PartialSearchResult> {
     check_sort_criteria(ctx, sort_criteria.as_ref())?;
 
@@ -112,25 +124,38 @@ pub fn execute_vector_search(
         ctx,
         sort_criteria,
         &placeholder_graph,
-        skip + limit,
+        after.map_or(0, |cursor| cursor.position) + skip + limit,
         vector,
         embedder_name,
         embedder,
+        vector_search_options,
+        time_budget,
     )?;
 
-    let BucketSortOutput { docids, scores, candidates} = bucket_sort(
+    let BucketSortOutput { docids, scores, candidates, degraded, explanation } = bucket_sort(
         ctx,
         ranking_rules,
+        distinct,
         limit,
         skip,
         candidates,
+        after,
+        ranking_score_threshold,
+        time_budget,
+        explain,
     )?;
+    let explanation = explanation
+        .map(|ranking_rules| QueryExplanation::new(ctx, &placeholder_graph, ranking_rules))
+        .transpose()?;
 
     Ok(PartialSearchResult {
         query_graph: placeholder_graph,
         candidates: candidates,
         document_scores: scores,
         documents_ids: docids,
+        degraded,
+        explanation,
+        fired_rules: Vec::new(),
     })
 }
 
@@ -139,12 +164,18 @@ pub fn execute_search(
     query: &Option<String>,
     candidates: RoaringBitmap,
     sort_criteria: &Option<Vec<AscDesc>>,
+    distinct: Option<FieldId>,
     analyzer: &Option<String>,
     skip: u64,
     limit: u64,
+    after: Option<&SearchCursor>,
+    ranking_score_threshold: Option<f64>,
+    time_budget: &TimeBudget,
+    explain: bool,
 ) -> Result<PartialSearchResult> {
-    check_sort_criteria(ctx, sort_criteria.as_ref())?;
-
+    let mut candidates = candidates;
+    let mut sort_criteria = Cow::Borrowed(sort_criteria);
+    let mut fired_rules = Vec::new();
     let query_graph = if let Some(query) = query {
         let analyzer = ctx.index.analyzer(ctx.txn, analyzer)?;
 
@@ -154,21 +185,40 @@ pub fn execute_search(
         drop(entered);
         let query = parse_query(tokens);
 
-        QueryGraph::from_query(query, ctx)?
+        let rewritten = rewrite_query(ctx, &analyzer, query)?;
+        if let Some(universe) = rewritten.universe {
+            candidates &= universe;
+        }
+        if let Some(sort) = rewritten.sort_criteria {
+            sort_criteria = Cow::Owned(Some(sort));
+        }
+        fired_rules = rewritten.fired_rules;
+
+        let query_graph = QueryGraph::from_query(rewritten.terms, ctx)?;
+        // the hits are the documents matching the query, not every document of the universe
+        candidates &= query_graph_docids(ctx, &query_graph)?;
+        query_graph
     } else {
         QueryGraph::placeholder(ctx)?
     };
+    check_sort_criteria(ctx, (*sort_criteria).as_ref())?;
 
-    let ranking_rules = get_ranking_rules_for_query_graph_search(ctx, sort_criteria, &query_graph)?;
-    let bucket_sort_output= bucket_sort(ctx, ranking_rules, limit, skip, candidates)?;
+    let ranking_rules = get_ranking_rules_for_query_graph_search(ctx, &sort_criteria, &query_graph, time_budget)?;
+    let bucket_sort_output= bucket_sort(ctx, ranking_rules, distinct, limit, skip, candidates, after, ranking_score_threshold, time_budget, explain)?;
 
-    let BucketSortOutput { docids, scores, candidates} = bucket_sort_output;
+    let BucketSortOutput { docids, scores, candidates, degraded, explanation } = bucket_sort_output;
+    let explanation = explanation
+        .map(|ranking_rules| QueryExplanation::new(ctx, &query_graph, ranking_rules))
+        .transpose()?;
 
     Ok(PartialSearchResult {
         query_graph,
         candidates,
         document_scores: scores,
         documents_ids: docids,
+        degraded,
+        explanation,
+        fired_rules,
     })
 }
 
@@ -178,7 +228,9 @@ pub struct PartialSearchResult {
     pub candidates: RoaringBitmap,
     pub documents_ids: Vec<DocumentId>,
     pub document_scores: Vec<Vec<ScoreDetails>>,
-
+    pub degraded: bool,
+    pub explanation: Option<QueryExplanation>,
+    pub fired_rules: Vec<String>,
 }
 
 fn check_sort_criteria(ctx: &SearchContext, sort_criteria: Option<&Vec<AscDesc>>) -> Result<()> {
@@ -212,6 +264,13 @@ fn check_sort_criteria(ctx: &SearchContext, sort_criteria: Option<&Vec<AscDesc>>
                 }
                     .into());
             }
+            Member::Geo(_) if !sortable_fields.contains("_geo") => {
+                return Err(UserError::InvalidSortableAttribute {
+                    field: "_geo".to_string(),
+                    valid_fields: BTreeSet::from_iter(sortable_fields),
+                }
+                    .into());
+            }
             _ => (),
         }
     }
@@ -219,6 +278,30 @@ fn check_sort_criteria(ctx: &SearchContext, sort_criteria: Option<&Vec<AscDesc>>
     Ok(())
 }
 
+/// Returns the field id of the distinct attribute, the one given at search time
+/// takes precedence over the one defined in the settings.
+pub fn distinct_field_id(ctx: &SearchContext, distinct: &Option<String>) -> Result<Option<FieldId>> {
+    let distinct = match distinct {
+        Some(field) => {
+            let filterable_fields = ctx.index.filterable_fields(ctx.txn)?;
+            if !crate::is_faceted(field, &filterable_fields) {
+                return Err(UserError::InvalidDistinctAttribute {
+                    field: field.to_string(),
+                    valid_fields: BTreeSet::from_iter(filterable_fields),
+                }
+                    .into());
+            }
+            Some(field.as_str())
+        }
+        None => ctx.index.distinct_field(ctx.txn)?,
+    };
+
+    match distinct {
+        Some(field) => Ok(ctx.index.fields_ids_map(ctx.txn)?.id(field)),
+        None => Ok(None),
+    }
+}
+
 pub fn filtered_universe(ctx: &SearchContext, filters: &Option<Filter>) -> Result<RoaringBitmap> {
     if let Some(filters) = filters {
         filters.evaluate(ctx.txn, ctx.index)
@@ -0,0 +1,235 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use heed::RoTxn;
+use ordered_float::OrderedFloat;
+use roaring::RoaringBitmap;
+
+use crate::error::UserError;
+use crate::score_details::{self, ScoreDetails};
+use crate::search::ranking::vector_sort::{VectorSearchOptions, VectorSort};
+use crate::search::search::SearchContext;
+use crate::vector::distance::VectorReader;
+use crate::vector::{VectorDistance, VectorQuantization};
+use crate::{DocumentId, Filter, Index, Result, TermsMatchingStrategy};
+
+const DEFAULT_SIMILAR_LIMIT: usize = 20;
+
+/// The options of the similar documents returned by [`Index::similar`].
+#[derive(Debug, Clone)]
+pub struct SimilarOptions {
+    /// Restricts the similar documents to the documents matching the filter.
+    pub filter: Option<Filter>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Default for SimilarOptions {
+    fn default() -> Self {
+        Self { filter: None, offset: 0, limit: DEFAULT_SIMILAR_LIMIT }
+    }
+}
+
+/// The documents the most similar to a document, the most similar first.
+#[derive(Debug, Default)]
+pub struct SimilarResult {
+    /// The documents allowed by the filter, the document itself excluded.
+    pub candidates: RoaringBitmap,
+    pub documents_ids: Vec<DocumentId>,
+    pub document_scores: Vec<Vec<ScoreDetails>>,
+}
+
+impl Index {
+    /// Returns the documents whose vectors are the closest to the vectors of the given document,
+    /// scored like the hits of a semantic search by each vector of the document, a document keeps
+    /// its best similarity.
+    ///
+    /// A document without vectors for the embedder has no similar documents.
+    pub fn similar(
+        &self,
+        rtxn: &RoTxn,
+        external_id: &str,
+        embedder_name: &str,
+        options: &SimilarOptions,
+    ) -> Result<SimilarResult> {
+        let docid = self
+            .external_documents_ids()
+            .get(rtxn, external_id)?
+            .ok_or_else(|| UserError::UnknownExternalDocumentId { document_id: external_id.to_string() })?;
+        let embedder_index = self
+            .embedder_category_id
+            .get(rtxn, embedder_name)?
+            .ok_or_else(|| UserError::InvalidEmbedder(embedder_name.to_owned()))?;
+        let config = self.embedding_configs(rtxn)?.into_iter().find(|(name, _)| name == embedder_name);
+        let distribution_shift = config.as_ref().and_then(|(_, config)| config.embedder_options.distribution());
+        let quantization = config.as_ref().and_then(|(_, config)| config.quantization);
+        let metric = config.map(|(_, config)| config.distance).unwrap_or_default();
+
+        let mut candidates = match &options.filter {
+            Some(filter) => filter.evaluate(rtxn, self)?,
+            None => self.documents_ids(rtxn)?,
+        };
+        candidates.remove(docid);
+
+        let readers = self.vector_readers(rtxn, embedder_index, metric, quantization)?;
+        let mut vectors = Vec::new();
+        for reader in &readers {
+            if let Some(vector) = reader.item_vector(rtxn, docid)? {
+                vectors.push(vector);
+            }
+        }
+
+        // the documents are scored by the vector sort, with the aggregation and the quantization of the embedder,
+        // each document keeps its similarity to the closest vector of the document.
+        let mut ctx = SearchContext::new(self, rtxn, TermsMatchingStrategy::default());
+        let count = options.offset.saturating_add(options.limit);
+        let mut similarities: HashMap<DocumentId, (f32, u8)> = HashMap::new();
+        for vector in vectors {
+            let vector_sort = VectorSort::new(
+                &mut ctx,
+                vector,
+                candidates.clone(),
+                count,
+                embedder_name,
+                distribution_shift,
+                VectorSearchOptions::default(),
+            )?;
+            for (other, similarity, best_vector) in vector_sort.similar_docids(&mut ctx, &candidates)? {
+                let best = similarities.entry(other).or_insert((similarity, best_vector));
+                if similarity > best.0 {
+                    *best = (similarity, best_vector);
+                }
+            }
+        }
+        let mut similarities: Vec<_> = similarities.into_iter().collect();
+        similarities.sort_unstable_by_key(|&(docid, (similarity, _))| (Reverse(OrderedFloat(similarity)), docid));
+
+        let (documents_ids, document_scores) = similarities
+            .into_iter()
+            .skip(options.offset)
+            .take(options.limit)
+            .map(|(docid, (similarity, best_vector))| {
+                let score = score_details::Vector { similarity: Some(similarity), best_vector: Some(best_vector) };
+                (docid, vec![ScoreDetails::Vector(score)])
+            })
+            .unzip();
+
+        Ok(SimilarResult { candidates, documents_ids, document_scores })
+    }
+
+    /// Returns the readers of the vectors of the embedder, one for each vector of the documents.
+    pub(crate) fn vector_readers<'t>(
+        &self,
+        rtxn: &'t RoTxn,
+        embedder_index: u8,
+        distance: VectorDistance,
+        quantization: Option<VectorQuantization>,
+    ) -> Result<Vec<VectorReader<'t>>> {
+        let writer_index = (embedder_index as u16) << 8;
+        let readers: std::result::Result<Vec<_>, _> = (0..=u8::MAX)
+            .map_while(|k| {
+                let index = writer_index | (k as u16);
+                VectorReader::open(rtxn, index, self.vector_arroy, self.vector_quantized, distance, quantization)
+                    .map(Some)
+                    .or_else(|e| match e {
+                        arroy::Error::MissingMetadata => Ok(None),
+                        e => Err(e),
+                    })
+                    .transpose()
+            })
+            .collect();
+
+        Ok(readers?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
+    use crate::error::Error;
+    use crate::index::tests::TempIndex;
+    use crate::score_details::ScoreDetails;
+    use crate::search::facet::Filter;
+    use crate::update::Setting;
+    use crate::vector::VectorAggregation;
+    use crate::{Search, SimilarOptions, UserError};
+
+    #[test]
+    fn similar_documents() {
+        let index = TempIndex::new();
+
+        index.update_settings(|settings| settings.set_filterable_fields(hashset! { S("id") })).unwrap();
+        index.set_manual_embedder(|_| ()).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "_vectors": { "manual": [1.0, 0.0] } },
+                { "id": 1, "_vectors": { "manual": [0.9, 0.1] } },
+                { "id": 2, "_vectors": { "manual": [0.0, 1.0] } },
+                { "id": 3, "_vectors": { "manual": [-1.0, 0.1] } },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.similar(&rtxn, "0", "manual", &SimilarOptions::default()).unwrap();
+        assert_eq!(result.documents_ids, [1, 2, 3]);
+        assert!(!result.candidates.contains(0));
+        let similarities: Vec<_> = result
+            .document_scores
+            .iter()
+            .map(|scores| match scores.as_slice() {
+                [ScoreDetails::Vector(vector)] => vector.similarity.unwrap(),
+                scores => panic!("unexpected scores {scores:?}"),
+            })
+            .collect();
+        assert!(similarities.windows(2).all(|pair| pair[0] >= pair[1]));
+
+        let options = SimilarOptions { offset: 1, limit: 1, ..Default::default() };
+        let result = index.similar(&rtxn, "0", "manual", &options).unwrap();
+        assert_eq!(result.documents_ids, [2]);
+
+        let filter = Filter::from_json(&serde_json::json!({ "id": 3 })).unwrap();
+        let options = SimilarOptions { filter, ..Default::default() };
+        let result = index.similar(&rtxn, "0", "manual", &options).unwrap();
+        assert_eq!(result.documents_ids, [3]);
+
+        let error = index.similar(&rtxn, "42", "manual", &SimilarOptions::default()).unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::UnknownExternalDocumentId { .. })));
+        let error = index.similar(&rtxn, "0", "unknown", &SimilarOptions::default()).unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::InvalidEmbedder(_))));
+    }
+
+    #[test]
+    fn similar_documents_are_scored_like_a_semantic_search() {
+        let index = TempIndex::new();
+
+        index.set_manual_embedder(|_| ()).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "_vectors": { "manual": [1.0, 0.0] } },
+                { "id": 1, "_vectors": { "manual": [0.8, 0.2] } },
+                { "id": 2, "_vectors": { "manual": [[0.6, 0.4], [0.7, 0.3], [0.5, 0.5]] } },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.similar(&rtxn, "0", "manual", &SimilarOptions::default()).unwrap();
+        assert_eq!(result.documents_ids, [1, 2]);
+        drop(rtxn);
+
+        // the many vectors of the last document are aggregated like in a semantic search
+        index.set_manual_embedder(|embedder| embedder.aggregation = Setting::Set(VectorAggregation::Sum)).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let result = index.similar(&rtxn, "0", "manual", &SimilarOptions::default()).unwrap();
+        assert_eq!(result.documents_ids, [2, 1]);
+
+        let mut search = Search::new(&rtxn, &index);
+        search.semantic(S("manual"), index.manual_embedder(), Some(vec![1.0, 0.0]));
+        let search_result = search.execute().unwrap();
+        assert_eq!(search_result.documents_ids, [0, 2, 1]);
+        let similar_scores: Vec<_> = result.document_scores.iter().map(|scores| &scores[0]).collect();
+        let search_scores: Vec<_> = search_result.document_scores[1..].iter().map(|scores| &scores[0]).collect();
+        assert_eq!(similar_scores, search_scores);
+    }
+}
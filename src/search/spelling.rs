@@ -0,0 +1,309 @@
+use analyzer::analyzer::BoxAnalyzer;
+use fst::automaton::{Automaton, Str};
+use fst::{IntoStreamer, Streamer};
+use roaring::RoaringBitmap;
+
+use crate::search::context::Context;
+use crate::search::query_graph::{LEVDIST1, LEVDIST2};
+use crate::search::query_parser::{parse_query, OriginalTerm, Term, TermKind};
+use crate::Result;
+
+/// The number of dictionary words kept as alternatives of a word of the query.
+const MAX_ALTERNATIVES: usize = 5;
+/// The number of corrected queries kept while the alternatives of the words are combined.
+const BEAM_WIDTH: usize = 10;
+/// The score lost by an alternative for each of its typos.
+const TYPO_PENALTY: f64 = 2.0;
+/// How many times more documents than the query a corrected query must match to be suggested.
+const MIN_CANDIDATES_RATIO: u64 = 2;
+
+/// A word of the dictionary that may replace a word of the query.
+struct Alternative {
+    word: String,
+    typos: u8,
+    /// Whether the alternative is a prefix of the query completed by the words of the dictionary.
+    prefix: bool,
+    /// The logarithm of the number of documents containing the word.
+    frequency: f64,
+}
+
+impl Alternative {
+    fn score(&self) -> f64 {
+        self.frequency - TYPO_PENALTY * self.typos as f64
+    }
+}
+
+/// A term of the query, only the words and the prefixes can be corrected.
+enum Slot {
+    Fixed(String),
+    Word(Vec<Alternative>),
+}
+
+/// Returns a corrected version of the query when it matches significantly more documents of the universe.
+///
+/// Each word of the query is replaced by one of the closest words of the dictionary, the combinations of
+/// alternatives are scored by the frequency of their words and by how often consecutive words are adjacent.
+pub fn correct_query(
+    ctx: &mut impl Context,
+    analyzer: &BoxAnalyzer,
+    query: &str,
+    universe: &RoaringBitmap,
+) -> Result<Option<String>> {
+    let terms = parse_query(analyzer.analyze(query));
+    let mut slots = Vec::with_capacity(terms.len());
+    for term in &terms {
+        let slot = match &term.term_kind {
+            TermKind::Normal(OriginalTerm::Word(word)) if !term.is_negative => {
+                Slot::Word(alternatives(ctx, word, false)?)
+            }
+            TermKind::Normal(OriginalTerm::Prefix(word)) if !term.is_negative => {
+                Slot::Word(alternatives(ctx, word, true)?)
+            }
+            _ => Slot::Fixed(fixed_term(term)),
+        };
+        slots.push(slot);
+    }
+
+    // the best combinations of alternatives, each one is the index of the alternative chosen for each slot.
+    let mut beam: Vec<(Vec<usize>, f64)> = vec![(Vec::new(), 0.0)];
+    for (i, slot) in slots.iter().enumerate() {
+        let Slot::Word(alternatives) = slot else {
+            beam.iter_mut().for_each(|(choices, _)| choices.push(0));
+            continue;
+        };
+        if alternatives.is_empty() {
+            return Ok(None);
+        }
+
+        let mut next_beam = Vec::with_capacity(beam.len() * alternatives.len());
+        for (choices, score) in &beam {
+            let previous_word = match i.checked_sub(1).map(|previous| &slots[previous]) {
+                Some(Slot::Word(alternatives)) => Some(&alternatives[choices[i - 1]].word),
+                _ => None,
+            };
+            for (choice, alternative) in alternatives.iter().enumerate() {
+                let mut score = score + alternative.score();
+                if let Some(previous_word) = previous_word {
+                    let frequency = ctx.word_pair_frequency(previous_word, &alternative.word, 1)?;
+                    score += (frequency as f64).ln_1p();
+                }
+                let mut choices = choices.clone();
+                choices.push(choice);
+                next_beam.push((choices, score));
+            }
+        }
+        next_beam.sort_by(|(_, left), (_, right)| right.total_cmp(left));
+        next_beam.truncate(BEAM_WIDTH);
+        beam = next_beam;
+    }
+
+    let original = vec![0; slots.len()];
+    let original_count = if is_unchanged(&slots, &original) {
+        candidates_count(ctx, &slots, &original, universe)?
+    } else {
+        // the query contains unknown words.
+        0
+    };
+    for (choices, _) in beam {
+        if is_unchanged(&slots, &choices) {
+            continue;
+        }
+        let count = candidates_count(ctx, &slots, &choices, universe)?;
+        if count > original_count && count >= original_count * MIN_CANDIDATES_RATIO {
+            let words: Vec<_> = slots
+                .iter()
+                .zip(&choices)
+                .map(|(slot, &choice)| match slot {
+                    Slot::Fixed(text) => text.as_str(),
+                    Slot::Word(alternatives) => alternatives[choice].word.as_str(),
+                })
+                .collect();
+            return Ok(Some(words.join(" ")));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the best words of the dictionary within the typos allowed for the word, the word itself first
+/// when it is part of the dictionary.
+///
+/// A prefix completed by some words of the dictionary is never corrected.
+fn alternatives(ctx: &mut impl Context, word: &str, prefix: bool) -> Result<Vec<Alternative>> {
+    if prefix {
+        let completions = completions(ctx, word)?;
+        if !completions.is_empty() {
+            let frequency = (prefix_docids(ctx, word, completions)?.len() as f64).ln_1p();
+            return Ok(vec![Alternative { word: word.to_string(), typos: 0, prefix: true, frequency }]);
+        }
+    }
+
+    let dfa = match ctx.typo_config()?.allowed_typos(word) {
+        // a word too short to have typos is kept as is when it is part of the dictionary.
+        0 => {
+            if !ctx.exact_words()?.contains(word) {
+                return Ok(Vec::new());
+            }
+            let frequency = (ctx.word_documents_count(word)? as f64).ln_1p();
+            return Ok(vec![Alternative { word: word.to_string(), typos: 0, prefix: false, frequency }]);
+        }
+        1 => LEVDIST1.build_dfa(word),
+        _ => LEVDIST2.build_dfa(word),
+    };
+    let mut derived_words = Vec::new();
+    {
+        let words = ctx.exact_words()?;
+        let mut stream = words.search_with_state(&dfa).into_stream();
+        while let Some((derived_word, state)) = stream.next() {
+            let Ok(derived_word) = std::str::from_utf8(derived_word) else { continue };
+            derived_words.push((derived_word.to_string(), dfa.distance(state).to_u8()));
+        }
+    }
+
+    let mut alternatives = Vec::with_capacity(derived_words.len());
+    for (word, typos) in derived_words {
+        let frequency = (ctx.word_documents_count(&word)? as f64).ln_1p();
+        alternatives.push(Alternative { word, typos, prefix: false, frequency });
+    }
+
+    // the word itself comes first even if it is rarer than its alternatives.
+    let (mut alternatives, mut derived): (Vec<_>, Vec<_>) =
+        alternatives.into_iter().partition(|alternative| alternative.typos == 0);
+    derived.sort_by(|left, right| right.score().total_cmp(&left.score()));
+    alternatives.extend(derived);
+    alternatives.truncate(MAX_ALTERNATIVES);
+
+    Ok(alternatives)
+}
+
+/// Whether every word of the combination is the original word of the query.
+fn is_unchanged(slots: &[Slot], choices: &[usize]) -> bool {
+    slots.iter().zip(choices).all(|(slot, &choice)| match slot {
+        Slot::Fixed(_) => true,
+        Slot::Word(alternatives) => choice == 0 && alternatives[0].typos == 0,
+    })
+}
+
+/// Returns the number of documents of the universe containing every word of the combination.
+fn candidates_count(
+    ctx: &mut impl Context,
+    slots: &[Slot],
+    choices: &[usize],
+    universe: &RoaringBitmap,
+) -> Result<u64> {
+    let mut candidates = universe.clone();
+    for (slot, &choice) in slots.iter().zip(choices) {
+        let Slot::Word(alternatives) = slot else { continue };
+        let alternative = &alternatives[choice];
+        candidates &= if alternative.prefix {
+            let completions = completions(ctx, &alternative.word)?;
+            prefix_docids(ctx, &alternative.word, completions)?
+        } else {
+            ctx.word_docids(&alternative.word)?
+        };
+    }
+    Ok(candidates.len())
+}
+
+/// Returns the words of the dictionary starting with the prefix.
+fn completions(ctx: &mut impl Context, prefix: &str) -> Result<Vec<String>> {
+    let words = ctx.exact_words()?;
+    let mut stream = words.search(Str::new(prefix).starts_with()).into_stream();
+    let mut completions = Vec::new();
+    while let Some(word) = stream.next() {
+        if let Ok(word) = std::str::from_utf8(word) {
+            completions.push(word.to_string());
+        }
+    }
+    Ok(completions)
+}
+
+/// Returns the documents containing one of the completions of the prefix.
+fn prefix_docids(ctx: &mut impl Context, prefix: &str, completions: Vec<String>) -> Result<RoaringBitmap> {
+    let docids = ctx.prefix_docids(prefix)?;
+    if !docids.is_empty() {
+        return Ok(docids);
+    }
+
+    // the prefixes with few completions aren't part of the prefixes database.
+    let mut docids = RoaringBitmap::new();
+    for word in completions {
+        docids |= ctx.word_docids(&word)?;
+    }
+    Ok(docids)
+}
+
+/// Returns the text of a term that can't be corrected.
+fn fixed_term(term: &Term) -> String {
+    let text = match &term.term_kind {
+        TermKind::Normal(OriginalTerm::Phrase(words)) | TermKind::Exact(OriginalTerm::Phrase(words)) => {
+            format!("\"{}\"", words.join(" "))
+        }
+        TermKind::Exact(OriginalTerm::Word(word) | OriginalTerm::Prefix(word)) => format!("\"{word}\""),
+        TermKind::Normal(OriginalTerm::Word(word) | OriginalTerm::Prefix(word)) => word.clone(),
+        TermKind::Derivative(..) => String::new(),
+    };
+    if term.is_negative {
+        format!("-{text}")
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::index::tests::TempIndex;
+    use crate::{Search, SearchResult};
+
+    #[test]
+    fn search_with_did_you_mean() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick brown fox" },
+                { "id": 1, "title": "a quick brown dog" },
+                { "id": 2, "title": "quick brown cats" },
+                { "id": 3, "title": "a slow green turtle" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { suggested_query, .. } =
+            Search::new(&rtxn, &index).query("quikc brwon").did_you_mean(10).execute().unwrap();
+        assert_eq!(suggested_query.as_deref(), Some("quick brown"));
+
+        // the query is already correct.
+        let SearchResult { suggested_query, .. } =
+            Search::new(&rtxn, &index).query("quick brown").did_you_mean(10).execute().unwrap();
+        assert_eq!(suggested_query, None);
+
+        // the search has too many hits to look for a correction.
+        let SearchResult { candidates, suggested_query, .. } =
+            Search::new(&rtxn, &index).query("quikc brwon").did_you_mean(0).execute().unwrap();
+        assert!(!candidates.is_empty());
+        assert_eq!(suggested_query, None);
+
+        let SearchResult { suggested_query, .. } =
+            Search::new(&rtxn, &index).query("quikc brwon").execute().unwrap();
+        assert_eq!(suggested_query, None);
+
+        // the short words, which can't have typos, are kept in the corrected query.
+        let SearchResult { suggested_query, .. } =
+            Search::new(&rtxn, &index).query("a quikc brwon").did_you_mean(10).execute().unwrap();
+        assert_eq!(suggested_query.as_deref(), Some("a quick brown"));
+
+        // the total hits are compared, not the hits of the returned page.
+        let SearchResult { candidates, .. } =
+            Search::new(&rtxn, &index).query("quikc brwon").execute().unwrap();
+        assert!(candidates.len() > 1);
+        let SearchResult { documents_ids, suggested_query, .. } = Search::new(&rtxn, &index)
+            .query("quikc brwon")
+            .limit(1)
+            .did_you_mean(candidates.len() - 1)
+            .execute()
+            .unwrap();
+        assert_eq!(documents_ids.len(), 1);
+        assert_eq!(suggested_query, None);
+    }
+}
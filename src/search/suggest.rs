@@ -0,0 +1,316 @@
+use std::cmp::Reverse;
+
+use fst::automaton::Automaton;
+use fst::{IntoStreamer, Streamer};
+use heed::types::Bytes;
+use heed::{BytesEncode, RoTxn};
+use roaring::RoaringBitmap;
+use serde::Serialize;
+
+use crate::heed_codec::U8StrStrCodec;
+use crate::proximity::ProximityPrecision;
+use crate::search::query_graph::{LEVDIST0, LEVDIST1, LEVDIST2};
+use crate::{Filter, Index, Result};
+
+const DEFAULT_SUGGESTIONS_LIMIT: usize = 10;
+/// The maximum number of words of the index counted as completions of the last word,
+/// the completions of a very short prefix are the first words starting with it in lexicographic order.
+const MAX_VISITED_COMPLETIONS: usize = 10_000;
+
+/// The options of the query suggestions returned by [`Index::suggest`].
+#[derive(Debug, Clone)]
+pub struct SuggestOptions {
+    pub limit: usize,
+    /// Restricts the suggestions to the words of the documents matching the filter.
+    pub filter: Option<Filter>,
+    /// Whether the last word may be completed with typos, as allowed by the typo settings of the index.
+    pub typo_tolerant: bool,
+    /// The maximum number of words appended after a completed word,
+    /// each appended word is the one most often following the previous word.
+    ///
+    /// The `ByAttribute` proximity precision doesn't store the pairs of words,
+    /// no word is appended with it.
+    pub next_words: usize,
+    pub analyzer: Option<String>,
+}
+
+impl Default for SuggestOptions {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_SUGGESTIONS_LIMIT,
+            filter: None,
+            typo_tolerant: false,
+            next_words: 0,
+            analyzer: None,
+        }
+    }
+}
+
+/// A completion of a query with the number of documents containing its words next to each other.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Suggestion {
+    /// The completed query, made of the words of the query normalized by the analyzer.
+    pub text: String,
+    pub count: u64,
+    /// The number of typos of the completed word.
+    pub typos: u8,
+}
+
+impl Index {
+    /// Returns the completions of the last word of the query, ranked by the number of documents containing them.
+    ///
+    /// The completions are the words of the index starting with the last word of the query,
+    /// the previous words of the query only keep the completions directly following them in the documents,
+    /// or in the same attribute with the `ByAttribute` proximity precision.
+    pub fn suggest(&self, rtxn: &RoTxn, query: &str, options: &SuggestOptions) -> Result<Vec<Suggestion>> {
+        let analyzer = self.analyzer(rtxn, &options.analyzer)?;
+        let words: Vec<String> = analyzer
+            .analyze(query)
+            .as_iter()
+            .filter_map(|token| (token.is_word() && !token.text.is_empty()).then(|| token.text.to_string()))
+            .collect();
+        let Some((prefix, previous_words)) = words.split_last() else {
+            return Ok(Vec::new());
+        };
+        let proximity_precision = self.proximity_precision(rtxn)?.unwrap_or_default();
+
+        // the documents containing the previous words next to each other, `None` is every document.
+        let mut universe = match &options.filter {
+            Some(filter) => Some(filter.evaluate(rtxn, self)?),
+            None => None,
+        };
+        for pair in previous_words.windows(2) {
+            let docids = self.adjacent_words_docids(rtxn, proximity_precision, &pair[0], &pair[1])?;
+            universe = Some(universe.map_or(docids.clone(), |universe| universe & docids));
+        }
+
+        let typos_allowed =
+            if options.typo_tolerant { self.typo_config(rtxn)?.allowed_typos(prefix) } else { 0 };
+        let dfa = match typos_allowed {
+            0 => LEVDIST0.build_prefix_dfa(prefix),
+            1 => LEVDIST1.build_prefix_dfa(prefix),
+            _ => LEVDIST2.build_prefix_dfa(prefix),
+        };
+
+        let words_fst = self.words_fst(rtxn)?;
+        let mut stream = words_fst.search_with_state(&dfa).into_stream();
+        let mut completions = Vec::new();
+        let mut visited = 0;
+        while let Some((word, state)) = stream.next() {
+            visited += 1;
+            if visited > MAX_VISITED_COMPLETIONS {
+                break;
+            }
+            let Ok(word) = std::str::from_utf8(word) else { continue };
+            let typos = dfa.distance(state).to_u8();
+            let count = match (previous_words.last(), &universe) {
+                (None, None) => self.word_documents_count(rtxn, word)?.unwrap_or_default(),
+                _ => self
+                    .completion_docids(rtxn, proximity_precision, previous_words.last(), word, universe.as_ref())?
+                    .len(),
+            };
+            if count != 0 {
+                completions.push((word.to_string(), count, typos));
+            }
+        }
+        completions.sort_by_key(|(_, count, typos)| (Reverse(*count), *typos));
+        completions.truncate(options.limit);
+
+        let context = previous_words.join(" ");
+        let text = |words: &[&str]| {
+            let completed = words.join(" ");
+            if context.is_empty() { completed } else { format!("{context} {completed}") }
+        };
+
+        let mut suggestions = Vec::with_capacity(completions.len());
+        for (word, count, typos) in completions {
+            suggestions.push(Suggestion { text: text(&[&word]), count, typos });
+            if options.next_words == 0 {
+                continue;
+            }
+
+            let mut docids =
+                self.completion_docids(rtxn, proximity_precision, previous_words.last(), &word, universe.as_ref())?;
+            let mut appended_words = vec![word.clone()];
+            for _ in 0..options.next_words {
+                let last_word = appended_words.last().unwrap();
+                let Some((next_word, next_docids)) =
+                    self.most_frequent_next_word(rtxn, proximity_precision, last_word, &docids)?
+                else {
+                    break;
+                };
+                docids = next_docids;
+                appended_words.push(next_word);
+                let words: Vec<&str> = appended_words.iter().map(String::as_str).collect();
+                suggestions.push(Suggestion { text: text(&words), count: docids.len(), typos });
+            }
+        }
+
+        // the sort is stable, a completion stays before the longer suggestions built on it.
+        suggestions.sort_by(|a, b| b.count.cmp(&a.count).then(a.typos.cmp(&b.typos)));
+        suggestions.truncate(options.limit);
+
+        Ok(suggestions)
+    }
+
+    /// Returns the documents of the universe containing the word,
+    /// directly following the previous word if there is one.
+    fn completion_docids(
+        &self,
+        rtxn: &RoTxn,
+        proximity_precision: ProximityPrecision,
+        previous_word: Option<&String>,
+        word: &str,
+        universe: Option<&RoaringBitmap>,
+    ) -> Result<RoaringBitmap> {
+        let docids = match previous_word {
+            Some(previous_word) => self.adjacent_words_docids(rtxn, proximity_precision, previous_word, word)?,
+            None => self.word_docids.get(rtxn, word)?.unwrap_or_default(),
+        };
+        Ok(match universe {
+            Some(universe) => docids & universe,
+            None => docids,
+        })
+    }
+
+    /// Returns the documents where the right word directly follows the left word,
+    /// or where both words are in the same attribute with the `ByAttribute` proximity precision.
+    fn adjacent_words_docids(
+        &self,
+        rtxn: &RoTxn,
+        proximity_precision: ProximityPrecision,
+        left: &str,
+        right: &str,
+    ) -> Result<RoaringBitmap> {
+        match proximity_precision {
+            ProximityPrecision::ByWord => {
+                Ok(self.word_pair_proximity_docids.get(rtxn, &(1, left, right))?.unwrap_or_default())
+            }
+            ProximityPrecision::ByAttribute => {
+                let fids = match self.searchable_fields_ids(rtxn)? {
+                    Some(fids) => fids,
+                    None => self.fields_ids_map(rtxn)?.ids().collect(),
+                };
+                let mut docids = RoaringBitmap::new();
+                for fid in fids {
+                    let Some(left_docids) = self.word_fid_docids.get(rtxn, &(left, fid))? else { continue };
+                    let Some(right_docids) = self.word_fid_docids.get(rtxn, &(right, fid))? else { continue };
+                    docids |= left_docids & right_docids;
+                }
+                Ok(docids)
+            }
+        }
+    }
+
+    /// Returns the word most often directly following the given word among the documents,
+    /// with the documents where it follows it.
+    fn most_frequent_next_word(
+        &self,
+        rtxn: &RoTxn,
+        proximity_precision: ProximityPrecision,
+        word: &str,
+        docids: &RoaringBitmap,
+    ) -> Result<Option<(String, RoaringBitmap)>> {
+        if proximity_precision == ProximityPrecision::ByAttribute {
+            return Ok(None);
+        }
+        let key = U8StrStrCodec::bytes_encode(&(1, word, "")).unwrap().into_owned();
+        let iter = self
+            .word_pair_proximity_docids
+            .remap_key_type::<Bytes>()
+            .prefix_iter(rtxn, &key)?
+            .remap_key_type::<U8StrStrCodec>();
+
+        let mut best: Option<(String, RoaringBitmap)> = None;
+        for result in iter {
+            let ((_, _, next_word), next_docids) = result?;
+            let next_docids = next_docids & docids;
+            if !next_docids.is_empty() && best.as_ref().map_or(true, |(_, best)| next_docids.len() > best.len()) {
+                best = Some((next_word.to_string(), next_docids));
+            }
+        }
+
+        Ok(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
+    use crate::index::tests::TempIndex;
+    use crate::search::facet::Filter;
+    use crate::{SuggestOptions, Suggestion};
+
+    #[test]
+    fn suggest_completions() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("id") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "new york city" },
+                { "id": 1, "name": "new york" },
+                { "id": 2, "name": "new jersey" },
+                { "id": 3, "name": "newton" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let texts = |suggestions: Vec<Suggestion>| {
+            suggestions.into_iter().map(|s| (s.text, s.count)).collect::<Vec<_>>()
+        };
+
+        let suggestions = index.suggest(&rtxn, "ne", &SuggestOptions::default()).unwrap();
+        assert_eq!(texts(suggestions), [(S("new"), 3), (S("newton"), 1)]);
+
+        let options = SuggestOptions { next_words: 1, ..Default::default() };
+        let suggestions = index.suggest(&rtxn, "ne", &options).unwrap();
+        assert_eq!(texts(suggestions), [(S("new"), 3), (S("new york"), 2), (S("newton"), 1)]);
+
+        // the previous words only keep the words following them.
+        let suggestions = index.suggest(&rtxn, "new yo", &options).unwrap();
+        assert_eq!(texts(suggestions), [(S("new york"), 2), (S("new york city"), 1)]);
+
+        let suggestions = index.suggest(&rtxn, "new jresey", &SuggestOptions::default()).unwrap();
+        assert!(suggestions.is_empty());
+        let options = SuggestOptions { typo_tolerant: true, ..Default::default() };
+        let suggestions = index.suggest(&rtxn, "new jresey", &options).unwrap();
+        assert_eq!(suggestions, [Suggestion { text: S("new jersey"), count: 1, typos: 1 }]);
+
+        let filter = Filter::from_json(&serde_json::json!({ "id": 3 })).unwrap();
+        let options = SuggestOptions { filter, ..Default::default() };
+        let suggestions = index.suggest(&rtxn, "ne", &options).unwrap();
+        assert_eq!(texts(suggestions), [(S("newton"), 1)]);
+    }
+
+    #[test]
+    fn suggest_completions_by_attribute() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_proximity_precision(crate::proximity::ProximityPrecision::ByAttribute);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "new york city" },
+                { "id": 1, "name": "new", "city": "york" },
+                { "id": 2, "name": "york new" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        // the previous words keep the completions of the same attribute, whatever their position
+        let options = SuggestOptions { next_words: 1, ..Default::default() };
+        let suggestions = index.suggest(&rtxn, "new yo", &options).unwrap();
+        assert_eq!(suggestions, [Suggestion { text: S("new york"), count: 2, typos: 0 }]);
+    }
+}
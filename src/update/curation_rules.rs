@@ -0,0 +1,42 @@
+use deserr::Deserr;
+use serde::{Deserialize, Serialize};
+
+/// Forces some documents to fixed positions and hides others for the queries matching a pattern.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Deserr)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[deserr(rename_all = camelCase, deny_unknown_fields)]
+pub struct CurationRule {
+    pub query: String,
+    #[serde(default)]
+    #[deserr(default)]
+    pub query_match: QueryMatch,
+    #[serde(default)]
+    #[deserr(default)]
+    pub pinned: Vec<PinnedDocument>,
+    #[serde(default)]
+    #[deserr(default)]
+    pub hidden: Vec<String>,
+}
+
+/// How the query of a curation rule is compared to the query of a search.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, Deserr)]
+#[serde(rename_all = "camelCase")]
+#[deserr(rename_all = camelCase)]
+pub enum QueryMatch {
+    /// The queries must be identical.
+    #[default]
+    Exact,
+    /// The queries must contain the same words once normalized by the analyzer of the search.
+    Normalized,
+}
+
+/// A document, identified by its external id, forced to a position of the results.
+///
+/// The position is zero-based and counts from the first hit of the results, not of the page.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Deserr)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[deserr(rename_all = camelCase, deny_unknown_fields)]
+pub struct PinnedDocument {
+    pub id: String,
+    pub position: usize,
+}
@@ -37,6 +37,7 @@ pub use crate::update::index_documents::helpers::CursorClonableMmap;
 use crate::update::{
     IndexerConfig, UpdateIndexingStep, WordPrefixDocids, WordPrefixIntegerDocids, WordsPrefixesFst,
 };
+use crate::vector::distance::ArroyWriter;
 use crate::vector::EmbeddingConfigs;
 use crate::{CboRoaringBitmapCodec, Index, Result, ThreadPoolNoAbortBuilder};
 
@@ -339,14 +340,34 @@ where
             Receiver<Result<TypedChunk>>,
         ) = crossbeam_channel::unbounded();
 
+        // get the primary key field id
+        let primary_key_id = fields_ids_map.id(&primary_key).unwrap();
 
         // get searchable fields for word databases
         let searchable_fields =
             self.index.searchable_fields_ids(self.wtxn)?.map(HashSet::from_iter);
         // get filterable fields for facet databases
         let faceted_fields = self.index.faceted_fields_ids(self.wtxn)?;
-        let field_id_map = self.index.fields_ids_map(self.wtxn)?;
-
+        let mut field_id_map = self.index.fields_ids_map(self.wtxn)?;
+
+        // get the fid of the `_geo.lat` and `_geo.lng` fields.
+        let geo_fields_ids = match field_id_map.id("_geo") {
+            Some(gfid) => {
+                let is_sortable = self.index.sortable_fields_ids(self.wtxn)?.contains(&gfid);
+                let is_filterable = self.index.filterable_fields_ids(self.wtxn)?.contains(&gfid);
+                // if `_geo` is faceted then we get the `lat` and `lng`
+                if is_sortable || is_filterable {
+                    let field_ids = field_id_map
+                        .insert("_geo.lat")
+                        .zip(field_id_map.insert("_geo.lng"))
+                        .ok_or(UserError::AttributeLimitReached)?;
+                    Some(field_ids)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
 
         let analyzer = self.index.analyzer(self.wtxn, &self.analyzer)?;
         let proximity_precision = self.index.proximity_precision(self.wtxn)?.unwrap_or_default();
@@ -419,6 +440,8 @@ where
                         lmdb_writer_sx.clone(),
                         searchable_fields,
                         faceted_fields,
+                        primary_key_id,
+                        geo_fields_ids,
                         field_id_map,
                         max_positions_per_attributes,
                         proximity_precision,
@@ -550,12 +573,17 @@ where
             let embedder_index = self.index.embedder_category_id.get(wtxn, &embedder_name)?.ok_or(
                 InternalError::DatabaseMissingEntry { db_name: "embedder_category_id", key: None },
             )?;
+            let distance = self.index.embedder_distance(wtxn, &embedder_name)?;
 
             pool.install(|| {
                 let writer_index = (embedder_index as u16) << 8;
                 for k in 0..=u8::MAX {
-                    let writer =
-                        arroy::Writer::new(vector_arroy, writer_index | (k as u16), dimension)?;
+                    let writer = ArroyWriter::new(
+                        vector_arroy,
+                        writer_index | (k as u16),
+                        dimension,
+                        distance,
+                    )?;
                     if writer.is_empty(wtxn)? {
                         break;
                     }
@@ -25,6 +25,8 @@ mod word_prefix_docids;
 mod words_prefix_integer_docids;
 mod words_prefixes_fst;
 pub mod analyzer_settings;
+pub mod curation_rules;
+pub mod query_rewrite_rules;
 pub mod split_config;
 pub mod typo_config;
 pub mod thread_pool_no_abort;
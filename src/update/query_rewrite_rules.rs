@@ -0,0 +1,83 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use deserr::Deserr;
+use serde::{Deserialize, Serialize};
+
+use crate::error::UserError;
+use crate::search::facet::Filter;
+use crate::{AscDesc, Member, SortError};
+
+/// Rewrites the queries containing a sequence of words before their query graph is built.
+///
+/// A rule fires when the words of its `pattern` are found in this order among the query terms,
+/// it can then replace these words, restrict the search with a filter and change the sort.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Deserr)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[deserr(rename_all = camelCase, deny_unknown_fields)]
+pub struct QueryRewriteRule {
+    /// The identifier reported in the search results when the rule fired.
+    pub id: String,
+    pub pattern: String,
+    /// The words replacing the pattern in the query, an empty replacement drops the pattern.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[deserr(default)]
+    pub replacement: Option<String>,
+    /// A filter added to the filter of the search.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[deserr(default)]
+    pub filter: Option<serde_json::Value>,
+    /// A sort replacing the sort of the search.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[deserr(default)]
+    pub sort: Option<Vec<String>>,
+}
+
+impl QueryRewriteRule {
+    pub fn filter(&self) -> crate::Result<Option<Filter>> {
+        match &self.filter {
+            Some(filter) => Filter::from_json(filter),
+            None => Ok(None),
+        }
+    }
+
+    pub fn sort(&self) -> crate::Result<Option<Vec<AscDesc>>> {
+        match &self.sort {
+            Some(sort) => sort
+                .iter()
+                .map(|asc_desc| AscDesc::from_str(asc_desc).map_err(|error| SortError::from(error).into()))
+                .collect::<crate::Result<_>>()
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Checks that the pattern isn't blank, that the filter and the sort can be parsed
+    /// and that they only use filterable and sortable fields.
+    pub(crate) fn validate(
+        &self,
+        filterable_fields: &HashSet<String>,
+        sortable_fields: &HashSet<String>,
+    ) -> crate::Result<()> {
+        let invalid = |reason: String| UserError::InvalidQueryRewriteRule { id: self.id.clone(), reason };
+        if self.pattern.trim().is_empty() {
+            return Err(invalid("шаблон не может быть пустым".to_string()).into());
+        }
+        if let Some(filter) = self.filter().map_err(|error| invalid(error.to_string()))? {
+            filter.check_filterable_fields(filterable_fields).map_err(|error| invalid(error.to_string()))?;
+        }
+        for asc_desc in self.sort().map_err(|error| invalid(error.to_string()))?.into_iter().flatten() {
+            let field = match asc_desc.member() {
+                Member::Field(field) if !crate::is_faceted(field, sortable_fields) => field.as_str(),
+                Member::Geo(_) if !sortable_fields.contains("_geo") => "_geo",
+                _ => continue,
+            };
+            let error = UserError::InvalidSortableAttribute {
+                field: field.to_string(),
+                valid_fields: sortable_fields.iter().cloned().collect(),
+            };
+            return Err(invalid(error.to_string()).into());
+        }
+        Ok(())
+    }
+}
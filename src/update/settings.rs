@@ -6,6 +6,7 @@ use std::sync::Arc;
 use charabia::{Normalize, Tokenizer, TokenizerBuilder};
 use deserr::{DeserializeError, Deserr};
 use itertools::{EitherOrBoth, Itertools};
+use rand::SeedableRng;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use time::OffsetDateTime;
 use analyzer::analyzer::{Analyzer, BoxAnalyzer};
@@ -21,9 +22,15 @@ use crate::proximity::ProximityPrecision;
 use crate::update::index_documents::IndexDocumentsMethod;
 use crate::update::{IndexDocuments, UpdateIndexingStep};
 use crate::vector::settings::{check_set, check_unset, EmbedderSource, EmbeddingSettings};
-use crate::vector::{Embedder, EmbeddingConfig, EmbeddingConfigs};
+use crate::vector::distance::{ArroyReader, ArroyWriter};
+use crate::vector::quantization::QuantizedWriter;
+use crate::vector::{
+    Embedder, EmbeddingConfig, EmbeddingConfigs, VectorDistance, VectorQuantization,
+};
 use crate::{FieldsIdsMap, Index, Result};
 use crate::update::analyzer_settings::{AnalyzerConfig, AnalyzerSettings, default_analyzer};
+use crate::update::curation_rules::CurationRule;
+use crate::update::query_rewrite_rules::QueryRewriteRule;
 use crate::update::split_config::{SplitJoinConfig, SplitJoinSettings};
 use crate::update::typo_config::{TypoConfig, TypoSettings};
 
@@ -139,10 +146,14 @@ pub struct Settings<'a, 't, 'i> {
     index: &'i Index,
     indexer_config: &'a IndexerConfig,
     searchable_fields: Setting<Vec<String>>,
+    searchable_attribute_weights: Setting<BTreeMap<String, u16>>,
     filterable_fields: Setting<HashSet<String>>,
     sortable_fields: Setting<HashSet<String>>,
     criteria: Setting<Vec<Criterion>>,
+    distinct_field: Setting<String>,
     synonyms: Setting<BTreeMap<String, Vec<String>>>,
+    curation_rules: Setting<Vec<CurationRule>>,
+    query_rewrite_rules: Setting<Vec<QueryRewriteRule>>,
     primary_key: Setting<String>,
     typo_config: Setting<TypoSettings>,
     split_join_config: Setting<SplitJoinSettings>,
@@ -164,10 +175,14 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
             wtxn,
             index,
             searchable_fields: Setting::NotSet,
+            searchable_attribute_weights: Setting::NotSet,
             filterable_fields: Setting::NotSet,
             sortable_fields: Setting::NotSet,
             criteria: Setting::NotSet,
+            distinct_field: Setting::NotSet,
             synonyms: Setting::NotSet,
+            curation_rules: Setting::NotSet,
+            query_rewrite_rules: Setting::NotSet,
             primary_key: Setting::NotSet,
             typo_config: Setting::NotSet,
             split_join_config: Setting::NotSet,
@@ -190,6 +205,15 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         self.searchable_fields = Setting::Set(names);
     }
 
+    pub fn reset_searchable_attribute_weights(&mut self) {
+        self.searchable_attribute_weights = Setting::Reset;
+    }
+
+    pub fn set_searchable_attribute_weights(&mut self, weights: BTreeMap<String, u16>) {
+        self.searchable_attribute_weights =
+            if weights.is_empty() { Setting::Reset } else { Setting::Set(weights) }
+    }
+
     pub fn reset_filterable_fields(&mut self) {
         self.filterable_fields = Setting::Reset;
     }
@@ -213,6 +237,15 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
     pub fn set_criteria(&mut self, criteria: Vec<Criterion>) {
         self.criteria = Setting::Set(criteria);
     }
+
+    pub fn reset_distinct_field(&mut self) {
+        self.distinct_field = Setting::Reset;
+    }
+
+    pub fn set_distinct_field(&mut self, distinct_field: String) {
+        self.distinct_field = Setting::Set(distinct_field);
+    }
+
     pub fn set_typo_config(&mut self, typo_config: TypoSettings) {
         self.typo_config = Setting::Set(typo_config);
     }
@@ -237,6 +270,22 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         self.synonyms = if synonyms.is_empty() { Setting::Reset } else { Setting::Set(synonyms) }
     }
 
+    pub fn reset_curation_rules(&mut self) {
+        self.curation_rules = Setting::Reset;
+    }
+
+    pub fn set_curation_rules(&mut self, rules: Vec<CurationRule>) {
+        self.curation_rules = if rules.is_empty() { Setting::Reset } else { Setting::Set(rules) }
+    }
+
+    pub fn reset_query_rewrite_rules(&mut self) {
+        self.query_rewrite_rules = Setting::Reset;
+    }
+
+    pub fn set_query_rewrite_rules(&mut self, rules: Vec<QueryRewriteRule>) {
+        self.query_rewrite_rules = if rules.is_empty() { Setting::Reset } else { Setting::Set(rules) }
+    }
+
     pub fn reset_primary_key(&mut self) {
         self.primary_key = Setting::Reset;
     }
@@ -359,7 +408,7 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
     ) -> Result<EmbeddingConfigs> {
         let res: Result<_> = embedding_configs
             .into_iter()
-            .map(|(name, EmbeddingConfig { embedder_options, prompt })| {
+            .map(|(name, EmbeddingConfig { embedder_options, prompt, .. })| {
                 let prompt = Arc::new(prompt.try_into().map_err(crate::Error::from)?);
 
                 let embedder = Arc::new(
@@ -520,6 +569,9 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
     fn update_criteria(&mut self) -> Result<()> {
         match &self.criteria {
             Setting::Set(criteria) => {
+                for criterion in criteria {
+                    criterion.validate()?;
+                }
                 self.index.put_criteria(self.wtxn, criteria)?;
             }
             Setting::Reset => {
@@ -530,6 +582,80 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         Ok(())
     }
 
+    fn update_distinct_field(&mut self) -> Result<()> {
+        match self.distinct_field {
+            Setting::Set(ref attr) => {
+                // the distinct field must be a field of the documents or a filterable field
+                let fields_ids_map = self.index.fields_ids_map(self.wtxn)?;
+                let filterable_fields = self.index.filterable_fields(self.wtxn)?;
+                let is_existing = fields_ids_map.names().any(|field| crate::is_faceted_by(field, attr));
+                if !is_existing && !crate::is_faceted(attr, &filterable_fields) {
+                    let mut valid_fields: BTreeSet<String> =
+                        fields_ids_map.names().map(String::from).collect();
+                    valid_fields.extend(filterable_fields);
+                    return Err(UserError::InvalidDistinctAttribute { field: attr.clone(), valid_fields }.into());
+                }
+                self.index.put_distinct_field(self.wtxn, attr)?;
+            }
+            Setting::Reset => {
+                self.index.delete_distinct_field(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
+    fn update_searchable_attribute_weights(&mut self) -> Result<()> {
+        match self.searchable_attribute_weights {
+            Setting::Set(ref weights) => {
+                self.index.put_searchable_attribute_weights(self.wtxn, weights)?;
+            }
+            Setting::Reset => {
+                self.index.delete_searchable_attribute_weights(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
+    fn update_curation_rules(&mut self) -> Result<()> {
+        match self.curation_rules {
+            Setting::Set(ref rules) => {
+                self.index.put_curation_rules(self.wtxn, rules)?;
+            }
+            Setting::Reset => {
+                self.index.delete_curation_rules(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
+    fn update_query_rewrite_rules(&mut self) -> Result<()> {
+        let filterable_fields = self.index.filterable_fields(self.wtxn)?;
+        let sortable_fields = self.index.sortable_fields(self.wtxn)?;
+        match self.query_rewrite_rules {
+            Setting::Set(ref rules) => {
+                for rule in rules {
+                    rule.validate(&filterable_fields, &sortable_fields)?;
+                }
+                self.index.put_query_rewrite_rules(self.wtxn, rules)?;
+            }
+            Setting::Reset => {
+                self.index.delete_query_rewrite_rules(self.wtxn)?;
+            }
+            Setting::NotSet => {
+                // the stored rules must stay valid when the filterable or sortable fields change
+                if !matches!(self.filterable_fields, Setting::NotSet) || !matches!(self.sortable_fields, Setting::NotSet) {
+                    for rule in self.index.query_rewrite_rules(self.wtxn)? {
+                        rule.validate(&filterable_fields, &sortable_fields)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn update_primary_key(&mut self) -> Result<()> {
         match self.primary_key {
             Setting::Set(ref primary_key) => {
@@ -724,6 +850,11 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
             Setting::Set(configs) => {
                 let mut changed = false;
                 let old_configs = self.index.embedding_configs(self.wtxn)?;
+                let old_vector_options: HashMap<String, (VectorDistance, Option<VectorQuantization>)> =
+                    old_configs
+                        .iter()
+                        .map(|(name, config)| (name.clone(), (config.distance, config.quantization)))
+                        .collect();
                 let old_configs: BTreeMap<String, Setting<EmbeddingSettings>> =
                     old_configs.into_iter().map(|(k, v)| (k, Setting::Set(v.into()))).collect();
 
@@ -772,6 +903,18 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
                     })
                     .collect();
 
+                for (name, config) in &new_configs {
+                    let Some(&(old_distance, old_quantization)) = old_vector_options.get(name) else {
+                        continue;
+                    };
+                    if old_distance != config.distance {
+                        self.rebuild_embedder_vectors(name, old_distance, config.distance)?;
+                    }
+                    if old_quantization != config.quantization {
+                        self.quantize_embedder_vectors(name, config.distance, old_quantization, config.quantization)?;
+                    }
+                }
+
                 self.index.embedder_category_id.clear(self.wtxn)?;
                 for (index, (embedder_name, _)) in new_configs.iter().enumerate() {
                     self.index.embedder_category_id.put_with_flags(
@@ -800,6 +943,106 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         Ok(update)
     }
 
+    /// Moves the vectors of the embedder to new arroy trees built with the new distance.
+    fn rebuild_embedder_vectors(
+        &mut self,
+        embedder_name: &str,
+        old_distance: VectorDistance,
+        new_distance: VectorDistance,
+    ) -> Result<()> {
+        let Some(embedder_index) = self.index.embedder_category_id.get(self.wtxn, embedder_name)? else {
+            return Ok(());
+        };
+        let documents_ids = self.index.documents_ids(self.wtxn)?;
+        let vector_arroy = self.index.vector_arroy;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+
+        let writer_index = (embedder_index as u16) << 8;
+        for k in 0..=u8::MAX {
+            let index = writer_index | (k as u16);
+            let (dimensions, vectors) = match ArroyReader::open(self.wtxn, index, vector_arroy, old_distance) {
+                Ok(reader) => {
+                    let mut vectors = Vec::new();
+                    for docid in &documents_ids {
+                        if let Some(vector) = reader.item_vector(self.wtxn, docid)? {
+                            vectors.push((docid, vector));
+                        }
+                    }
+                    (reader.dimensions(), vectors)
+                }
+                // uses invariant: vectors are packed in the first trees.
+                Err(arroy::Error::MissingMetadata) => break,
+                Err(error) => return Err(error.into()),
+            };
+
+            ArroyWriter::new(vector_arroy, index, dimensions, old_distance)?.clear(self.wtxn)?;
+            let writer = ArroyWriter::new(vector_arroy, index, dimensions, new_distance)?;
+            for (docid, vector) in vectors {
+                writer.add_item(self.wtxn, docid, &vector)?;
+            }
+            writer.build(self.wtxn, &mut rng, None)?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the vectors of the arroy trees of the embedder to its quantized vectors.
+    ///
+    /// The full precision vectors aren't kept, the quantization can't be changed nor removed once the embedder
+    /// has quantized vectors.
+    fn quantize_embedder_vectors(
+        &mut self,
+        embedder_name: &str,
+        distance: VectorDistance,
+        old_quantization: Option<VectorQuantization>,
+        quantization: Option<VectorQuantization>,
+    ) -> Result<()> {
+        let Some(embedder_index) = self.index.embedder_category_id.get(self.wtxn, embedder_name)? else {
+            return Ok(());
+        };
+        let vector_quantized = self.index.vector_quantized;
+        let writer_index = (embedder_index as u16) << 8;
+
+        if let Some(old_quantization) = old_quantization {
+            // uses invariant: vectors are packed in the first trees.
+            if !QuantizedWriter::new(vector_quantized, writer_index, old_quantization).is_empty(self.wtxn)? {
+                return Err(UserError::InvalidSettingsQuantization { embedder_name: embedder_name.to_owned() }.into());
+            }
+            return Ok(());
+        }
+        let Some(quantization) = quantization else {
+            return Ok(());
+        };
+
+        let documents_ids = self.index.documents_ids(self.wtxn)?;
+        let vector_arroy = self.index.vector_arroy;
+        for k in 0..=u8::MAX {
+            let index = writer_index | (k as u16);
+            let (dimensions, vectors) = match ArroyReader::open(self.wtxn, index, vector_arroy, distance) {
+                Ok(reader) => {
+                    let mut vectors = Vec::new();
+                    for docid in &documents_ids {
+                        if let Some(vector) = reader.item_vector(self.wtxn, docid)? {
+                            vectors.push((docid, vector));
+                        }
+                    }
+                    (reader.dimensions(), vectors)
+                }
+                // uses invariant: vectors are packed in the first trees.
+                Err(arroy::Error::MissingMetadata) => break,
+                Err(error) => return Err(error.into()),
+            };
+
+            ArroyWriter::new(vector_arroy, index, dimensions, distance)?.clear(self.wtxn)?;
+            let writer = QuantizedWriter::new(vector_quantized, index, quantization);
+            for (docid, vector) in vectors {
+                writer.put(self.wtxn, docid, &vector)?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn update_analyzer_configs(&mut self) -> Result<bool> {
         let update = match std::mem::take(&mut self.analyzer_settings) {
             Setting::Set(configs) => {
@@ -883,6 +1126,10 @@ impl<'a, 't, 'i> Settings<'a, 't, 'i> {
         self.update_filterable()?;
         self.update_sortable()?;
         self.update_criteria()?;
+        self.update_distinct_field()?;
+        self.update_searchable_attribute_weights()?;
+        self.update_curation_rules()?;
+        self.update_query_rewrite_rules()?;
         self.update_primary_key()?;
         self.update_split_join_config()?;
         self.update_typo_config()?;
@@ -960,6 +1207,11 @@ fn validate_prompt(
             embedding_object,
             input_type,
             distribution,
+            aggregation,
+            aggregation_top_k,
+            distance,
+            quantization,
+            rescore_factor,
         }) => {
             // validate
             let template = crate::prompt::Prompt::new(template)
@@ -980,6 +1232,11 @@ fn validate_prompt(
                 embedding_object,
                 input_type,
                 distribution,
+                aggregation,
+                aggregation_top_k,
+                distance,
+                quantization,
+                rescore_factor,
             }))
         }
         new => Ok(new),
@@ -1006,6 +1263,11 @@ pub fn validate_embedding_settings(
         embedding_object,
         input_type,
         distribution,
+        aggregation,
+        aggregation_top_k,
+        distance,
+        quantization,
+        rescore_factor,
     } = settings;
 
     if let Some(0) = dimensions.set() {
@@ -1015,6 +1277,20 @@ pub fn validate_embedding_settings(
         .into());
     }
 
+    if let Some(0) = aggregation_top_k.set() {
+        return Err(crate::error::UserError::InvalidSettingsAggregationTopK {
+            embedder_name: name.to_owned(),
+        }
+        .into());
+    }
+
+    if let Some(0) = rescore_factor.set() {
+        return Err(crate::error::UserError::InvalidSettingsRescoreFactor {
+            embedder_name: name.to_owned(),
+        }
+        .into());
+    }
+
     if let Some(url) = url.as_ref().set() {
         url::Url::parse(url).map_err(|error| crate::error::UserError::InvalidUrl {
             embedder_name: name.to_owned(),
@@ -1038,6 +1314,11 @@ pub fn validate_embedding_settings(
             embedding_object,
             input_type,
             distribution,
+            aggregation,
+            aggregation_top_k,
+            distance,
+            quantization,
+            rescore_factor,
         }));
     };
     match inferred_source {
@@ -1183,6 +1464,11 @@ pub fn validate_embedding_settings(
         embedding_object,
         input_type,
         distribution,
+        aggregation,
+        aggregation_top_k,
+        distance,
+        quantization,
+        rescore_factor,
     }))
 }
 
@@ -0,0 +1,329 @@
+use std::num::NonZeroUsize;
+
+use arroy::distances::{Angular, DotProduct, Euclidean, Manhattan};
+use arroy::{Database, ItemId, Reader, Writer};
+use deserr::Deserr;
+use heed::{RoTxn, RwTxn};
+use rand::{Rng, SeedableRng};
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+
+use super::quantization::{QuantizedDatabase, QuantizedReader, QuantizedWriter, VectorQuantization};
+
+/// The distance used to compare the vectors of an embedder.
+///
+/// The vectors of all the embedders are stored in the same arroy database, typed with the angular distance,
+/// the database is remapped to the distance of the embedder to read and write its trees.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Deserr)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[deserr(rename_all = camelCase, deny_unknown_fields)]
+pub enum VectorDistance {
+    #[default]
+    Angular,
+    Euclidean,
+    DotProduct,
+    Manhattan,
+}
+
+impl VectorDistance {
+    /// Converts a distance returned by arroy to a similarity, the greater the more similar.
+    ///
+    /// The angular similarity is in the [0, 1] interval, the euclidean and manhattan similarities
+    /// in the ]0, 1] interval and the dot product similarity, the logistic function of the dot product,
+    /// in the ]0, 1[ interval.
+    pub fn similarity(&self, distance: f32) -> f32 {
+        match self {
+            VectorDistance::Angular => 1.0 - distance,
+            VectorDistance::Euclidean | VectorDistance::Manhattan => 1.0 / (1.0 + distance),
+            // arroy orders the vectors by the opposite of their dot product
+            VectorDistance::DotProduct => 1.0 / (1.0 + distance.exp()),
+        }
+    }
+
+    /// Computes the distance between two vectors the way arroy does for its search results.
+    pub fn distance(&self, left: &[f32], right: &[f32]) -> f32 {
+        let pairs = left.iter().zip(right);
+        match self {
+            VectorDistance::Angular => {
+                let norms = norm(left) * norm(right);
+                if norms == 0.0 {
+                    0.0
+                } else {
+                    let cos = pairs.map(|(l, r)| l * r).sum::<f32>() / norms;
+                    (1.0 - cos) / 2.0
+                }
+            }
+            VectorDistance::Euclidean => pairs.map(|(l, r)| (l - r) * (l - r)).sum::<f32>().sqrt(),
+            VectorDistance::DotProduct => -pairs.map(|(l, r)| l * r).sum::<f32>(),
+            VectorDistance::Manhattan => pairs.map(|(l, r)| (l - r).abs()).sum(),
+        }
+    }
+}
+
+fn norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|value| value * value).sum::<f32>().sqrt()
+}
+
+/// A reader of an arroy tree, with the distance of its embedder.
+pub enum ArroyReader<'t> {
+    Angular(Reader<'t, Angular>),
+    Euclidean(Reader<'t, Euclidean>),
+    DotProduct(Reader<'t, DotProduct>),
+    Manhattan(Reader<'t, Manhattan>),
+}
+
+impl<'t> ArroyReader<'t> {
+    pub fn open(
+        rtxn: &'t RoTxn,
+        index: u16,
+        database: Database<Angular>,
+        distance: VectorDistance,
+    ) -> arroy::Result<Self> {
+        Ok(match distance {
+            VectorDistance::Angular => Self::Angular(Reader::open(rtxn, index, database)?),
+            VectorDistance::Euclidean => {
+                Self::Euclidean(Reader::open(rtxn, index, database.remap_data_type())?)
+            }
+            VectorDistance::DotProduct => {
+                Self::DotProduct(Reader::open(rtxn, index, database.remap_data_type())?)
+            }
+            VectorDistance::Manhattan => {
+                Self::Manhattan(Reader::open(rtxn, index, database.remap_data_type())?)
+            }
+        })
+    }
+
+    pub fn dimensions(&self) -> usize {
+        match self {
+            ArroyReader::Angular(reader) => reader.dimensions(),
+            ArroyReader::Euclidean(reader) => reader.dimensions(),
+            ArroyReader::DotProduct(reader) => reader.dimensions(),
+            ArroyReader::Manhattan(reader) => reader.dimensions(),
+        }
+    }
+
+    /// Returns the number of items of the tree.
+    pub fn n_items(&self) -> u64 {
+        match self {
+            ArroyReader::Angular(reader) => reader.n_items(),
+            ArroyReader::Euclidean(reader) => reader.n_items(),
+            ArroyReader::DotProduct(reader) => reader.n_items(),
+            ArroyReader::Manhattan(reader) => reader.n_items(),
+        }
+    }
+
+    pub fn item_vector(&self, rtxn: &RoTxn, item: ItemId) -> arroy::Result<Option<Vec<f32>>> {
+        match self {
+            ArroyReader::Angular(reader) => reader.item_vector(rtxn, item),
+            ArroyReader::Euclidean(reader) => reader.item_vector(rtxn, item),
+            ArroyReader::DotProduct(reader) => reader.item_vector(rtxn, item),
+            ArroyReader::Manhattan(reader) => reader.item_vector(rtxn, item),
+        }
+    }
+
+    /// Returns the nearest items of the vector with their distance, the nearest first.
+    pub fn nns_by_vector(
+        &self,
+        rtxn: &RoTxn,
+        vector: &[f32],
+        count: usize,
+        search_k: Option<NonZeroUsize>,
+        candidates: Option<&RoaringBitmap>,
+    ) -> arroy::Result<Vec<(ItemId, f32)>> {
+        match self {
+            ArroyReader::Angular(reader) => reader.nns_by_vector(rtxn, vector, count, search_k, candidates),
+            ArroyReader::Euclidean(reader) => reader.nns_by_vector(rtxn, vector, count, search_k, candidates),
+            ArroyReader::DotProduct(reader) => reader.nns_by_vector(rtxn, vector, count, search_k, candidates),
+            ArroyReader::Manhattan(reader) => reader.nns_by_vector(rtxn, vector, count, search_k, candidates),
+        }
+    }
+}
+
+/// A reader of the vectors of a tree of an embedder, the arroy tree or the quantized vectors replacing it.
+pub enum VectorReader<'t> {
+    Arroy(ArroyReader<'t>),
+    Quantized(QuantizedReader),
+}
+
+impl<'t> VectorReader<'t> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn open(
+        rtxn: &'t RoTxn,
+        index: u16,
+        database: Database<Angular>,
+        quantized_database: QuantizedDatabase,
+        distance: VectorDistance,
+        quantization: Option<VectorQuantization>,
+    ) -> arroy::Result<Self> {
+        Ok(match quantization {
+            Some(quantization) => {
+                Self::Quantized(QuantizedReader::open(rtxn, quantized_database, index, quantization, distance)?)
+            }
+            None => Self::Arroy(ArroyReader::open(rtxn, index, database, distance)?),
+        })
+    }
+
+    pub fn dimensions(&self) -> usize {
+        match self {
+            VectorReader::Arroy(reader) => reader.dimensions(),
+            VectorReader::Quantized(reader) => reader.dimensions(),
+        }
+    }
+
+    /// Returns the vector of the item, dequantized when the vectors are quantized.
+    pub fn item_vector(&self, rtxn: &RoTxn, item: ItemId) -> arroy::Result<Option<Vec<f32>>> {
+        match self {
+            VectorReader::Arroy(reader) => reader.item_vector(rtxn, item),
+            VectorReader::Quantized(reader) => reader.item_vector(rtxn, item).map_err(arroy::Error::Heed),
+        }
+    }
+}
+
+/// A writer of an arroy tree, with the distance of its embedder.
+///
+/// When the embedder is quantized, the quantized vectors are written instead of the tree.
+pub struct ArroyWriter {
+    writer: DistanceWriter,
+    quantized: Option<QuantizedWriter>,
+}
+
+enum DistanceWriter {
+    Angular(Writer<Angular>),
+    Euclidean(Writer<Euclidean>),
+    DotProduct(Writer<DotProduct>),
+    Manhattan(Writer<Manhattan>),
+}
+
+impl ArroyWriter {
+    pub fn new(
+        database: Database<Angular>,
+        index: u16,
+        dimensions: usize,
+        distance: VectorDistance,
+    ) -> arroy::Result<Self> {
+        let writer = match distance {
+            VectorDistance::Angular => DistanceWriter::Angular(Writer::new(database, index, dimensions)?),
+            VectorDistance::Euclidean => {
+                DistanceWriter::Euclidean(Writer::new(database.remap_data_type(), index, dimensions)?)
+            }
+            VectorDistance::DotProduct => {
+                DistanceWriter::DotProduct(Writer::new(database.remap_data_type(), index, dimensions)?)
+            }
+            VectorDistance::Manhattan => {
+                DistanceWriter::Manhattan(Writer::new(database.remap_data_type(), index, dimensions)?)
+            }
+        };
+        Ok(Self { writer, quantized: None })
+    }
+
+    /// Writes the quantized vectors instead of the tree, the tree is written when `quantization` is `None`.
+    pub fn with_quantization(
+        mut self,
+        database: QuantizedDatabase,
+        index: u16,
+        quantization: Option<VectorQuantization>,
+    ) -> Self {
+        self.quantized = quantization.map(|quantization| QuantizedWriter::new(database, index, quantization));
+        self
+    }
+
+    pub fn is_empty(&self, rtxn: &RoTxn) -> arroy::Result<bool> {
+        if let Some(quantized) = &self.quantized {
+            return quantized.is_empty(rtxn).map_err(arroy::Error::Heed);
+        }
+        match &self.writer {
+            DistanceWriter::Angular(writer) => writer.is_empty(rtxn),
+            DistanceWriter::Euclidean(writer) => writer.is_empty(rtxn),
+            DistanceWriter::DotProduct(writer) => writer.is_empty(rtxn),
+            DistanceWriter::Manhattan(writer) => writer.is_empty(rtxn),
+        }
+    }
+
+    /// Returns the vector of the item, dequantized when the vectors are quantized.
+    pub fn item_vector(&self, rtxn: &RoTxn, item: ItemId) -> arroy::Result<Option<Vec<f32>>> {
+        if let Some(quantized) = &self.quantized {
+            return quantized.item_vector(rtxn, item).map_err(arroy::Error::Heed);
+        }
+        match &self.writer {
+            DistanceWriter::Angular(writer) => writer.item_vector(rtxn, item),
+            DistanceWriter::Euclidean(writer) => writer.item_vector(rtxn, item),
+            DistanceWriter::DotProduct(writer) => writer.item_vector(rtxn, item),
+            DistanceWriter::Manhattan(writer) => writer.item_vector(rtxn, item),
+        }
+    }
+
+    /// Returns the vector as [`ArroyWriter::item_vector`] would read it back once added.
+    pub fn stored_vector(&self, vector: &[f32]) -> Vec<f32> {
+        match &self.quantized {
+            Some(quantized) => quantized.stored_vector(vector),
+            None => vector.to_vec(),
+        }
+    }
+
+    pub fn contains_item(&self, rtxn: &RoTxn, item: ItemId) -> arroy::Result<bool> {
+        if let Some(quantized) = &self.quantized {
+            return quantized.contains_item(rtxn, item).map_err(arroy::Error::Heed);
+        }
+        match &self.writer {
+            DistanceWriter::Angular(writer) => writer.contains_item(rtxn, item),
+            DistanceWriter::Euclidean(writer) => writer.contains_item(rtxn, item),
+            DistanceWriter::DotProduct(writer) => writer.contains_item(rtxn, item),
+            DistanceWriter::Manhattan(writer) => writer.contains_item(rtxn, item),
+        }
+    }
+
+    pub fn add_item(&self, wtxn: &mut RwTxn, item: ItemId, vector: &[f32]) -> arroy::Result<()> {
+        if let Some(quantized) = &self.quantized {
+            return quantized.put(wtxn, item, vector).map_err(arroy::Error::Heed);
+        }
+        match &self.writer {
+            DistanceWriter::Angular(writer) => writer.add_item(wtxn, item, vector),
+            DistanceWriter::Euclidean(writer) => writer.add_item(wtxn, item, vector),
+            DistanceWriter::DotProduct(writer) => writer.add_item(wtxn, item, vector),
+            DistanceWriter::Manhattan(writer) => writer.add_item(wtxn, item, vector),
+        }
+    }
+
+    pub fn del_item(&self, wtxn: &mut RwTxn, item: ItemId) -> arroy::Result<bool> {
+        if let Some(quantized) = &self.quantized {
+            return quantized.delete(wtxn, item).map_err(arroy::Error::Heed);
+        }
+        match &self.writer {
+            DistanceWriter::Angular(writer) => writer.del_item(wtxn, item),
+            DistanceWriter::Euclidean(writer) => writer.del_item(wtxn, item),
+            DistanceWriter::DotProduct(writer) => writer.del_item(wtxn, item),
+            DistanceWriter::Manhattan(writer) => writer.del_item(wtxn, item),
+        }
+    }
+
+    /// Removes every item and node of the tree.
+    pub fn clear(&self, wtxn: &mut RwTxn) -> arroy::Result<()> {
+        if let Some(quantized) = &self.quantized {
+            return quantized.clear(wtxn).map_err(arroy::Error::Heed);
+        }
+        match &self.writer {
+            DistanceWriter::Angular(writer) => writer.clear(wtxn),
+            DistanceWriter::Euclidean(writer) => writer.clear(wtxn),
+            DistanceWriter::DotProduct(writer) => writer.clear(wtxn),
+            DistanceWriter::Manhattan(writer) => writer.clear(wtxn),
+        }
+    }
+
+    pub fn build<R: Rng + SeedableRng>(
+        &self,
+        wtxn: &mut RwTxn,
+        rng: &mut R,
+        n_trees: Option<usize>,
+    ) -> arroy::Result<()> {
+        // the quantized vectors are read in order, there is no tree to build
+        if self.quantized.is_some() {
+            return Ok(());
+        }
+        match &self.writer {
+            DistanceWriter::Angular(writer) => writer.build(wtxn, rng, n_trees),
+            DistanceWriter::Euclidean(writer) => writer.build(wtxn, rng, n_trees),
+            DistanceWriter::DotProduct(writer) => writer.build(wtxn, rng, n_trees),
+            DistanceWriter::Manhattan(writer) => writer.build(wtxn, rng, n_trees),
+        }
+    }
+}
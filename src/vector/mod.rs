@@ -8,16 +8,20 @@ use serde::{Deserialize, Serialize};
 use self::error::{EmbedError, NewEmbedderError};
 use crate::prompt::{Prompt, PromptData};
 
+pub mod distance;
 pub mod error;
 pub mod hf;
 pub mod manual;
 pub mod openai;
+pub mod quantization;
 pub mod settings;
 
 pub mod ollama;
 pub mod rest;
 
+pub use self::distance::VectorDistance;
 pub use self::error::Error;
+pub use self::quantization::VectorQuantization;
 
 pub type Embedding = Vec<f32>;
 
@@ -121,7 +125,76 @@ pub struct EmbeddingConfig {
     pub embedder_options: EmbedderOptions,
     /// Document template
     pub prompt: PromptData,
-    // TODO: add metrics and anything needed
+    /// How the similarities of the vectors of a document are combined
+    #[serde(default)]
+    pub aggregation: VectorAggregation,
+    /// Number of vectors averaged by [`VectorAggregation::MeanOfTopK`]
+    #[serde(default)]
+    pub aggregation_top_k: Option<usize>,
+    /// Distance used to compare the vectors, the vectors are stored in arroy trees of this distance
+    #[serde(default)]
+    pub distance: VectorDistance,
+    /// Quantization of the vectors, the quantized vectors are stored instead of the arroy trees
+    #[serde(default)]
+    pub quantization: Option<VectorQuantization>,
+    /// Number of nearest quantized vectors rescored for each requested result
+    #[serde(default)]
+    pub rescore_factor: Option<usize>,
+}
+
+impl EmbeddingConfig {
+    /// The number of best vectors of a document averaged by [`VectorAggregation::MeanOfTopK`].
+    pub fn aggregation_top_k(&self) -> usize {
+        self.aggregation_top_k.unwrap_or(DEFAULT_AGGREGATION_TOP_K)
+    }
+
+    /// The number of nearest quantized vectors rescored with the full precision query for each requested result.
+    pub fn rescore_factor(&self) -> usize {
+        self.rescore_factor.unwrap_or(quantization::DEFAULT_RESCORE_FACTOR)
+    }
+}
+
+pub const DEFAULT_AGGREGATION_TOP_K: usize = 3;
+
+/// How the similarities of the vectors of a document to the query are combined into the similarity
+/// of the document, a document with several vectors gets a single score.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Deserr)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[deserr(rename_all = camelCase, deny_unknown_fields)]
+pub enum VectorAggregation {
+    /// The similarity of the closest vector.
+    #[default]
+    Max,
+    /// The mean similarity of the `aggregationTopK` closest vectors.
+    MeanOfTopK,
+    /// The probabilistic sum of the similarities, favouring the documents with many close vectors.
+    ///
+    /// Each vector closes a part of the remaining gap to 1, `1 - (1 - s1) * (1 - s2) * ...`,
+    /// which keeps the score in the [0, 1] interval of the other scores.
+    Sum,
+}
+
+impl VectorAggregation {
+    /// Combines the similarities of the vectors of a document, sorted from the most similar.
+    pub fn aggregate(&self, sorted_similarities: &[f32], top_k: usize) -> f32 {
+        match self {
+            VectorAggregation::Max => sorted_similarities.first().copied().unwrap_or_default(),
+            VectorAggregation::MeanOfTopK => {
+                let top = &sorted_similarities[..top_k.min(sorted_similarities.len())];
+                if top.is_empty() {
+                    0.0
+                } else {
+                    top.iter().sum::<f32>() / top.len() as f32
+                }
+            }
+            VectorAggregation::Sum => {
+                1.0 - sorted_similarities
+                    .iter()
+                    .map(|similarity| 1.0 - similarity.clamp(0.0, 1.0))
+                    .product::<f32>()
+            }
+        }
+    }
 }
 
 /// Map of embedder configurations.
@@ -205,6 +278,17 @@ impl EmbedderOptions {
     pub fn ollama(api_key: Option<String>, url: Option<String>) -> Self {
         Self::Ollama(ollama::EmbedderOptions::with_default_model(api_key, url))
     }
+
+    /// The distribution shift applied to the similarity scores of the embedder.
+    pub fn distribution(&self) -> Option<DistributionShift> {
+        match self {
+            EmbedderOptions::HuggingFace(options) => options.distribution,
+            EmbedderOptions::OpenAi(options) => options.distribution,
+            EmbedderOptions::Ollama(options) => options.distribution,
+            EmbedderOptions::UserProvided(options) => options.distribution,
+            EmbedderOptions::Rest(options) => options.distribution,
+        }
+    }
 }
 
 impl Embedder {
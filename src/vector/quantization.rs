@@ -0,0 +1,307 @@
+//! The quantized vectors of an embedder replace its arroy trees: the full precision vectors aren't stored,
+//! a vector takes `dimensions / 8 + 4` bytes with the binary quantization, about 3% of the `4 * dimensions`
+//! bytes of a full precision vector, and `dimensions + 4` bytes, about 25%, with the scalar quantization.
+//!
+//! A vector search reads the quantized vectors of the candidates in order, compares them to the quantized
+//! query with a few xor and popcount of 64 bits words for the binary quantization, and only `rescore_factor`
+//! times the requested results are rescored, with their dequantized vectors and the full precision query.
+//!
+//! The full precision vectors being gone, the quantization of an embedder can't be changed nor removed
+//! once its vectors are quantized.
+
+use std::collections::BinaryHeap;
+
+use arroy::ItemId;
+use deserr::Deserr;
+use heed::types::Bytes;
+use heed::{RoTxn, RwTxn};
+use ordered_float::OrderedFloat;
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+
+use super::VectorDistance;
+use crate::heed_codec::BEU16BEU32Codec;
+
+/// Maps the arroy tree index and the item id with the quantized vector of the item.
+pub type QuantizedDatabase = heed::Database<BEU16BEU32Codec, Bytes>;
+
+/// The default number of quantized candidates fetched for each requested result,
+/// the candidates are then rescored with their dequantized vectors and the full precision query.
+pub const DEFAULT_RESCORE_FACTOR: usize = 4;
+
+/// The quantization of the vectors of an embedder.
+///
+/// The quantized vectors are stored instead of the arroy trees of the embedder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Deserr)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[deserr(rename_all = camelCase, deny_unknown_fields)]
+pub enum VectorQuantization {
+    /// Keeps the sign of each dimension as a bit, the vectors are compared with the Hamming distance.
+    ///
+    /// The number of dimensions is followed by the bits packed in little endian 64 bits words,
+    /// the vectors are read back with `1` or `-1` in each dimension.
+    Binary,
+    /// Scales each dimension to an `i8`, with the greatest absolute value of the vector as the scale.
+    Scalar,
+}
+
+impl VectorQuantization {
+    pub fn quantize(&self, vector: &[f32]) -> Vec<u8> {
+        match self {
+            VectorQuantization::Binary => {
+                let mut words = vec![0u64; (vector.len() + 63) / 64];
+                for (i, value) in vector.iter().enumerate() {
+                    if *value > 0.0 {
+                        words[i / 64] |= 1 << (i % 64);
+                    }
+                }
+                let mut bytes = Vec::with_capacity(4 + 8 * words.len());
+                bytes.extend_from_slice(&(vector.len() as u32).to_le_bytes());
+                bytes.extend(words.iter().flat_map(|word| word.to_le_bytes()));
+                bytes
+            }
+            VectorQuantization::Scalar => {
+                let scale = vector.iter().fold(0.0f32, |max, value| max.max(value.abs()));
+                let mut bytes = Vec::with_capacity(4 + vector.len());
+                bytes.extend_from_slice(&scale.to_le_bytes());
+                bytes.extend(vector.iter().map(|value| {
+                    let value = if scale == 0.0 { 0.0 } else { value / scale * 127.0 };
+                    value.round().clamp(-127.0, 127.0) as i8 as u8
+                }));
+                bytes
+            }
+        }
+    }
+
+    /// Returns the vector stored as the quantized vector.
+    pub fn dequantize(&self, bytes: &[u8]) -> Vec<f32> {
+        match self {
+            VectorQuantization::Binary => {
+                let (dimensions, words) = split_binary(bytes);
+                let words = self::words(words);
+                (0..dimensions)
+                    .map(|i| match words.get(i / 64) {
+                        Some(word) if word & (1 << (i % 64)) != 0 => 1.0,
+                        _ => -1.0,
+                    })
+                    .collect()
+            }
+            VectorQuantization::Scalar => dequantize_scalar(bytes),
+        }
+    }
+
+    /// Returns the number of dimensions of the quantized vector.
+    pub fn dimensions(&self, bytes: &[u8]) -> usize {
+        match self {
+            VectorQuantization::Binary => split_binary(bytes).0,
+            VectorQuantization::Scalar => bytes.len().saturating_sub(4),
+        }
+    }
+
+    /// Returns the approximate distance between two quantized vectors, the smaller the nearer.
+    ///
+    /// The binary quantization always uses the Hamming distance, whatever the distance of the embedder.
+    pub fn distance(&self, metric: VectorDistance, left: &[u8], right: &[u8]) -> f32 {
+        match self {
+            VectorQuantization::Binary => {
+                let left = words(split_binary(left).1);
+                hamming_within(&left, split_binary(right).1, u32::MAX).unwrap_or(u32::MAX) as f32
+            }
+            VectorQuantization::Scalar => {
+                let left = dequantize_scalar(left);
+                let right = dequantize_scalar(right);
+                let pairs = left.iter().zip(&right);
+                match metric {
+                    VectorDistance::Angular => {
+                        let dot: f32 = pairs.map(|(l, r)| l * r).sum();
+                        let norms = norm(&left) * norm(&right);
+                        if norms == 0.0 {
+                            1.0
+                        } else {
+                            1.0 - dot / norms
+                        }
+                    }
+                    VectorDistance::Euclidean => {
+                        pairs.map(|(l, r)| (l - r) * (l - r)).sum::<f32>().sqrt()
+                    }
+                    VectorDistance::DotProduct => -pairs.map(|(l, r)| l * r).sum::<f32>(),
+                    VectorDistance::Manhattan => pairs.map(|(l, r)| (l - r).abs()).sum(),
+                }
+            }
+        }
+    }
+}
+
+/// Splits a binary quantized vector in its number of dimensions and its words.
+fn split_binary(bytes: &[u8]) -> (usize, &[u8]) {
+    if bytes.len() < 4 {
+        return (0, &[]);
+    }
+    let (dimensions, words) = bytes.split_at(4);
+    (u32::from_le_bytes([dimensions[0], dimensions[1], dimensions[2], dimensions[3]]) as usize, words)
+}
+
+/// Reads the 64 bits words of a binary quantized vector.
+fn words(bytes: &[u8]) -> Vec<u64> {
+    bytes.chunks_exact(8).map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap())).collect()
+}
+
+/// Returns the Hamming distance between the words and the binary quantized vector,
+/// `None` as soon as it exceeds the bound.
+fn hamming_within(left: &[u64], right: &[u8], bound: u32) -> Option<u32> {
+    let mut distance = 0;
+    for (left, right) in left.iter().zip(right.chunks_exact(8)) {
+        distance += (left ^ u64::from_le_bytes(right.try_into().unwrap())).count_ones();
+        if distance > bound {
+            return None;
+        }
+    }
+    Some(distance)
+}
+
+fn dequantize_scalar(bytes: &[u8]) -> Vec<f32> {
+    if bytes.len() < 4 {
+        return Vec::new();
+    }
+    let (scale, values) = bytes.split_at(4);
+    let scale = f32::from_le_bytes([scale[0], scale[1], scale[2], scale[3]]);
+    values.iter().map(|value| *value as i8 as f32 / 127.0 * scale).collect()
+}
+
+fn norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|value| value * value).sum::<f32>().sqrt()
+}
+
+/// A writer of the quantized vectors replacing an arroy tree.
+pub struct QuantizedWriter {
+    database: QuantizedDatabase,
+    index: u16,
+    quantization: VectorQuantization,
+}
+
+impl QuantizedWriter {
+    pub fn new(database: QuantizedDatabase, index: u16, quantization: VectorQuantization) -> Self {
+        Self { database, index, quantization }
+    }
+
+    pub fn is_empty(&self, rtxn: &RoTxn) -> heed::Result<bool> {
+        Ok(self.database.range(rtxn, &((self.index, 0)..=(self.index, ItemId::MAX)))?.next().is_none())
+    }
+
+    /// Returns the vector of the item as it is stored, dequantized.
+    pub fn item_vector(&self, rtxn: &RoTxn, item: ItemId) -> heed::Result<Option<Vec<f32>>> {
+        Ok(self.database.get(rtxn, &(self.index, item))?.map(|bytes| self.quantization.dequantize(bytes)))
+    }
+
+    /// Returns the vector as it would be read back once stored.
+    pub fn stored_vector(&self, vector: &[f32]) -> Vec<f32> {
+        self.quantization.dequantize(&self.quantization.quantize(vector))
+    }
+
+    pub fn contains_item(&self, rtxn: &RoTxn, item: ItemId) -> heed::Result<bool> {
+        Ok(self.database.get(rtxn, &(self.index, item))?.is_some())
+    }
+
+    pub fn put(&self, wtxn: &mut RwTxn, item: ItemId, vector: &[f32]) -> heed::Result<()> {
+        self.database.put(wtxn, &(self.index, item), &self.quantization.quantize(vector))
+    }
+
+    pub fn delete(&self, wtxn: &mut RwTxn, item: ItemId) -> heed::Result<bool> {
+        self.database.delete(wtxn, &(self.index, item))
+    }
+
+    pub fn clear(&self, wtxn: &mut RwTxn) -> heed::Result<()> {
+        self.database.delete_range(wtxn, &((self.index, 0)..=(self.index, ItemId::MAX))).map(drop)
+    }
+}
+
+/// A reader of the quantized vectors replacing an arroy tree.
+#[derive(Clone, Copy)]
+pub struct QuantizedReader {
+    database: QuantizedDatabase,
+    index: u16,
+    quantization: VectorQuantization,
+    metric: VectorDistance,
+    dimensions: usize,
+}
+
+impl QuantizedReader {
+    /// Opens the quantized vectors of the tree, as arroy fails with [`arroy::Error::MissingMetadata`]
+    /// when the tree has no vectors.
+    pub fn open(
+        rtxn: &RoTxn,
+        database: QuantizedDatabase,
+        index: u16,
+        quantization: VectorQuantization,
+        metric: VectorDistance,
+    ) -> arroy::Result<Self> {
+        let first = database
+            .range(rtxn, &((index, 0)..=(index, ItemId::MAX)))
+            .and_then(|mut range| range.next().transpose())
+            .map_err(arroy::Error::Heed)?;
+        let Some((_, bytes)) = first else {
+            return Err(arroy::Error::MissingMetadata);
+        };
+        let dimensions = quantization.dimensions(bytes);
+        Ok(Self { database, index, quantization, metric, dimensions })
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Returns the vector of the item as it is stored, dequantized.
+    pub fn item_vector(&self, rtxn: &RoTxn, item: ItemId) -> heed::Result<Option<Vec<f32>>> {
+        Ok(self.database.get(rtxn, &(self.index, item))?.map(|bytes| self.quantization.dequantize(bytes)))
+    }
+
+    /// Returns the `count` items whose quantized vectors are the nearest of the target.
+    ///
+    /// Only the window of items between the smallest and the greatest candidates is read.
+    /// Once `count` items are found, a binary quantized vector is dropped as soon as its Hamming distance
+    /// exceeds the farthest of them.
+    pub fn nearest(
+        &self,
+        rtxn: &RoTxn,
+        target: &[f32],
+        candidates: &RoaringBitmap,
+        count: usize,
+    ) -> heed::Result<RoaringBitmap> {
+        let window = match (candidates.min(), candidates.max()) {
+            (Some(min), Some(max)) if count > 0 => (self.index, min)..=(self.index, max),
+            _ => return Ok(RoaringBitmap::new()),
+        };
+
+        let target = self.quantization.quantize(target);
+        let target_words = match self.quantization {
+            VectorQuantization::Binary => words(split_binary(&target).1),
+            VectorQuantization::Scalar => Vec::new(),
+        };
+        // a max-heap of the nearest items found so far, the farthest on top
+        let mut nearest = BinaryHeap::with_capacity(count + 1);
+        for result in self.database.range(rtxn, &window)? {
+            let ((_, item), bytes) = result?;
+            if !candidates.contains(item) {
+                continue;
+            }
+            let distance = match self.quantization {
+                VectorQuantization::Binary => {
+                    let bound = match nearest.peek() {
+                        Some(&(OrderedFloat(farthest), _)) if nearest.len() == count => farthest as u32,
+                        _ => u32::MAX,
+                    };
+                    match hamming_within(&target_words, split_binary(bytes).1, bound) {
+                        Some(distance) => distance as f32,
+                        None => continue,
+                    }
+                }
+                VectorQuantization::Scalar => self.quantization.distance(self.metric, &target, bytes),
+            };
+            nearest.push((OrderedFloat(distance), item));
+            if nearest.len() > count {
+                nearest.pop();
+            }
+        }
+        Ok(nearest.into_iter().map(|(_, item)| item).collect())
+    }
+}
@@ -2,7 +2,9 @@ use deserr::Deserr;
 use serde::{Deserialize, Serialize};
 
 use super::rest::InputType;
-use super::{ollama, openai, DistributionShift};
+use super::{
+    ollama, openai, DistributionShift, VectorAggregation, VectorDistance, VectorQuantization,
+};
 use crate::prompt::PromptData;
 use crate::update::Setting;
 use crate::vector::EmbeddingConfig;
@@ -51,6 +53,21 @@ pub struct EmbeddingSettings {
     #[serde(default, skip_serializing_if = "Setting::is_not_set")]
     #[deserr(default)]
     pub distribution: Setting<DistributionShift>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    pub aggregation: Setting<VectorAggregation>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    pub aggregation_top_k: Setting<usize>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    pub distance: Setting<VectorDistance>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    pub quantization: Setting<VectorQuantization>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default)]
+    pub rescore_factor: Setting<usize>,
 }
 
 pub fn check_unset<T>(
@@ -105,6 +122,11 @@ impl EmbeddingSettings {
     pub const INPUT_TYPE: &'static str = "inputType";
 
     pub const DISTRIBUTION: &'static str = "distribution";
+    pub const AGGREGATION: &'static str = "aggregation";
+    pub const AGGREGATION_TOP_K: &'static str = "aggregationTopK";
+    pub const DISTANCE: &'static str = "distance";
+    pub const QUANTIZATION: &'static str = "quantization";
+    pub const RESCORE_FACTOR: &'static str = "rescoreFactor";
 
     pub fn allowed_sources_for_field(field: &'static str) -> &'static [EmbedderSource] {
         match field {
@@ -137,7 +159,12 @@ impl EmbeddingSettings {
             Self::PATH_TO_EMBEDDINGS => &[EmbedderSource::Rest],
             Self::EMBEDDING_OBJECT => &[EmbedderSource::Rest],
             Self::INPUT_TYPE => &[EmbedderSource::Rest],
-            Self::DISTRIBUTION => &[
+            Self::DISTRIBUTION
+            | Self::AGGREGATION
+            | Self::AGGREGATION_TOP_K
+            | Self::DISTANCE
+            | Self::QUANTIZATION
+            | Self::RESCORE_FACTOR => &[
                 EmbedderSource::HuggingFace,
                 EmbedderSource::Ollama,
                 EmbedderSource::OpenAi,
@@ -157,6 +184,11 @@ impl EmbeddingSettings {
                 Self::DOCUMENT_TEMPLATE,
                 Self::DIMENSIONS,
                 Self::DISTRIBUTION,
+                Self::AGGREGATION,
+                Self::AGGREGATION_TOP_K,
+                Self::DISTANCE,
+                Self::QUANTIZATION,
+                Self::RESCORE_FACTOR,
             ],
             EmbedderSource::HuggingFace => &[
                 Self::SOURCE,
@@ -164,6 +196,11 @@ impl EmbeddingSettings {
                 Self::REVISION,
                 Self::DOCUMENT_TEMPLATE,
                 Self::DISTRIBUTION,
+                Self::AGGREGATION,
+                Self::AGGREGATION_TOP_K,
+                Self::DISTANCE,
+                Self::QUANTIZATION,
+                Self::RESCORE_FACTOR,
             ],
             EmbedderSource::Ollama => &[
                 Self::SOURCE,
@@ -172,8 +209,22 @@ impl EmbeddingSettings {
                 Self::URL,
                 Self::API_KEY,
                 Self::DISTRIBUTION,
+                Self::AGGREGATION,
+                Self::AGGREGATION_TOP_K,
+                Self::DISTANCE,
+                Self::QUANTIZATION,
+                Self::RESCORE_FACTOR,
+            ],
+            EmbedderSource::UserProvided => &[
+                Self::SOURCE,
+                Self::DIMENSIONS,
+                Self::DISTRIBUTION,
+                Self::AGGREGATION,
+                Self::AGGREGATION_TOP_K,
+                Self::DISTANCE,
+                Self::QUANTIZATION,
+                Self::RESCORE_FACTOR,
             ],
-            EmbedderSource::UserProvided => &[Self::SOURCE, Self::DIMENSIONS, Self::DISTRIBUTION],
             EmbedderSource::Rest => &[
                 Self::SOURCE,
                 Self::API_KEY,
@@ -186,6 +237,11 @@ impl EmbeddingSettings {
                 Self::EMBEDDING_OBJECT,
                 Self::INPUT_TYPE,
                 Self::DISTRIBUTION,
+                Self::AGGREGATION,
+                Self::AGGREGATION_TOP_K,
+                Self::DISTANCE,
+                Self::QUANTIZATION,
+                Self::RESCORE_FACTOR,
             ],
         }
     }
@@ -231,6 +287,11 @@ impl EmbeddingSettings {
                     embedding_object: old_embedding_object,
                     input_type: old_input_type,
                     distribution: old_distribution,
+                    aggregation: old_aggregation,
+                    aggregation_top_k: old_aggregation_top_k,
+                    distance: old_distance,
+                    quantization: old_quantization,
+                    rescore_factor: old_rescore_factor,
                 }),
                 Setting::Set(EmbeddingSettings {
                     source: new_source,
@@ -246,6 +307,11 @@ impl EmbeddingSettings {
                     embedding_object: new_embedding_object,
                     input_type: new_input_type,
                     distribution: new_distribution,
+                    aggregation: new_aggregation,
+                    aggregation_top_k: new_aggregation_top_k,
+                    distance: new_distance,
+                    quantization: new_quantization,
+                    rescore_factor: new_rescore_factor,
                 }),
             ) => {
                 let mut needs_reindex = false;
@@ -263,6 +329,13 @@ impl EmbeddingSettings {
                 needs_reindex |= old_input_type.apply(new_input_type);
 
                 old_distribution.apply(new_distribution);
+                old_aggregation.apply(new_aggregation);
+                old_aggregation_top_k.apply(new_aggregation_top_k);
+                // the trees are rebuilt with the new distance without embedding the documents again
+                old_distance.apply(new_distance);
+                // the vectors of the trees are quantized in the same way, but can't be requantized
+                old_quantization.apply(new_quantization);
+                old_rescore_factor.apply(new_rescore_factor);
                 old_api_key.apply(new_api_key);
                 needs_reindex
             }
@@ -299,7 +372,20 @@ impl std::fmt::Display for EmbedderSource {
 
 impl From<EmbeddingConfig> for EmbeddingSettings {
     fn from(value: EmbeddingConfig) -> Self {
-        let EmbeddingConfig { embedder_options, prompt } = value;
+        let EmbeddingConfig {
+            embedder_options,
+            prompt,
+            aggregation,
+            aggregation_top_k,
+            distance,
+            quantization,
+            rescore_factor,
+        } = value;
+        let aggregation = Setting::Set(aggregation);
+        let aggregation_top_k = aggregation_top_k.map(Setting::Set).unwrap_or_default();
+        let distance = Setting::Set(distance);
+        let quantization = quantization.map(Setting::Set).unwrap_or_default();
+        let rescore_factor = rescore_factor.map(Setting::Set).unwrap_or_default();
         match embedder_options {
             super::EmbedderOptions::HuggingFace(options) => Self {
                 source: Setting::Set(EmbedderSource::HuggingFace),
@@ -315,6 +401,11 @@ impl From<EmbeddingConfig> for EmbeddingSettings {
                 embedding_object: Setting::NotSet,
                 input_type: Setting::NotSet,
                 distribution: options.distribution.map(Setting::Set).unwrap_or_default(),
+                aggregation,
+                aggregation_top_k,
+                distance,
+                quantization,
+                rescore_factor,
             },
             super::EmbedderOptions::OpenAi(options) => Self {
                 source: Setting::Set(EmbedderSource::OpenAi),
@@ -330,6 +421,11 @@ impl From<EmbeddingConfig> for EmbeddingSettings {
                 embedding_object: Setting::NotSet,
                 input_type: Setting::NotSet,
                 distribution: options.distribution.map(Setting::Set).unwrap_or_default(),
+                aggregation,
+                aggregation_top_k,
+                distance,
+                quantization,
+                rescore_factor,
             },
             super::EmbedderOptions::Ollama(options) => Self {
                 source: Setting::Set(EmbedderSource::Ollama),
@@ -345,6 +441,11 @@ impl From<EmbeddingConfig> for EmbeddingSettings {
                 embedding_object: Setting::NotSet,
                 input_type: Setting::NotSet,
                 distribution: options.distribution.map(Setting::Set).unwrap_or_default(),
+                aggregation,
+                aggregation_top_k,
+                distance,
+                quantization,
+                rescore_factor,
             },
             super::EmbedderOptions::UserProvided(options) => Self {
                 source: Setting::Set(EmbedderSource::UserProvided),
@@ -360,6 +461,11 @@ impl From<EmbeddingConfig> for EmbeddingSettings {
                 embedding_object: Setting::NotSet,
                 input_type: Setting::NotSet,
                 distribution: options.distribution.map(Setting::Set).unwrap_or_default(),
+                aggregation,
+                aggregation_top_k,
+                distance,
+                quantization,
+                rescore_factor,
             },
             super::EmbedderOptions::Rest(super::rest::EmbedderOptions {
                 api_key,
@@ -385,6 +491,11 @@ impl From<EmbeddingConfig> for EmbeddingSettings {
                 embedding_object: Setting::Set(embedding_object),
                 input_type: Setting::Set(input_type),
                 distribution: distribution.map(Setting::Set).unwrap_or_default(),
+                aggregation,
+                aggregation_top_k,
+                distance,
+                quantization,
+                rescore_factor,
             },
         }
     }
@@ -407,6 +518,11 @@ impl From<EmbeddingSettings> for EmbeddingConfig {
             embedding_object,
             input_type,
             distribution,
+            aggregation,
+            aggregation_top_k,
+            distance,
+            quantization,
+            rescore_factor,
         } = value;
 
         if let Some(source) = source.set() {
@@ -490,6 +606,11 @@ impl From<EmbeddingSettings> for EmbeddingConfig {
         if let Setting::Set(template) = document_template {
             this.prompt = PromptData { template }
         }
+        this.aggregation = aggregation.set().unwrap_or_default();
+        this.aggregation_top_k = aggregation_top_k.set();
+        this.distance = distance.set().unwrap_or_default();
+        this.quantization = quantization.set();
+        this.rescore_factor = rescore_factor.set();
 
         this
     }